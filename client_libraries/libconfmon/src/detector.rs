@@ -1,11 +1,17 @@
 use crate::Platform;
+use crate::platform::{
+    IPTABLES_V4_RULES_PATH, IPTABLES_V6_RULES_PATH, NFTABLES_CONFIG_PATH,
+    OPNSENSE_DIRTY_MARKERS_PATH, PFSENSE_DIRTY_MARKERS_PATH, rooted,
+};
+use serde::{Deserialize, Serialize};
 use std::ffi::OsStr;
 use std::path::Path;
 use tokio::fs;
 use tokio::fs::ReadDir;
+use tokio::process::Command;
 
 /// Represents the possible states of the system configuration.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum State {
     /// The configuration is in draft mode and has not been applied.
     Draft,
@@ -29,20 +35,46 @@ impl Detector {
     /// - `State::Applied` if the configuration has been applied.
     /// - `State::Undefined` if the check fails or the state cannot be determined.
     pub async fn check(platform: Platform) -> State {
+        Detector::check_with_root(platform, Path::new("/")).await
+    }
+
+    /// Checks the current configuration state, looking up the files below a filesystem root.
+    ///
+    /// Commands dumping the live ruleset (on Linux) are not affected by the root.
+    ///
+    /// # Parameters
+    /// - `platform`: The target platform for which the configuration state is being checked.
+    /// - `root`: The filesystem root (`/` on a real host), e.g. a fake tree built for tests.
+    ///
+    /// # Returns
+    /// The same as `check`.
+    pub async fn check_with_root(platform: Platform, root: &Path) -> State {
         match platform {
-            Platform::PfSense => Detector::check_pfsense().await,
-            Platform::OPNsense => todo!("Not implemented"),
+            Platform::PfSense => {
+                Detector::check_dirty_markers(root, PFSENSE_DIRTY_MARKERS_PATH).await
+            }
+            Platform::OPNsense => {
+                Detector::check_dirty_markers(root, OPNSENSE_DIRTY_MARKERS_PATH).await
+            }
+            Platform::Linux => Detector::check_linux(root).await,
         }
     }
 
-    /// Checks the configuration state for the **PfSense** platform.
+    /// Checks the configuration state for the **PfSense** and **OPNsense** platforms.
+    ///
+    /// Both mark subsystems with pending changes by creating `<subsystem>.dirty` files:
+    /// pfSense in `/var/run/`, OPNsense in `/tmp/`.
+    ///
+    /// # Parameters
+    /// - `root`: The filesystem root.
+    /// - `directory`: The directory holding the `.dirty` markers.
     ///
     /// # Returns
-    /// - `State::Draft` if a file with a `.dirty` extension exists in `/var/run/`, indicating pending changes.
+    /// - `State::Draft` if a file with a `.dirty` extension exists in `directory`, indicating pending changes.
     /// - `State::Applied` if no such files are found.
     /// - `State::Undefined` if an error occurs while reading the directory.
-    async fn check_pfsense() -> State {
-        let mut entries: ReadDir = match fs::read_dir("/var/run/").await {
+    async fn check_dirty_markers(root: &Path, directory: &str) -> State {
+        let mut entries: ReadDir = match fs::read_dir(rooted(root, directory)).await {
             Ok(entries) => entries,
             Err(_) => return State::Undefined,
         };
@@ -51,13 +83,208 @@ impl Detector {
             if let Some(ext) = Path::new(&entry.file_name())
                 .extension()
                 .and_then(OsStr::to_str)
+                && ext == "dirty"
             {
-                if ext == "dirty" {
-                    return State::Draft;
-                }
+                return State::Draft;
             }
         }
 
         State::Applied
     }
+
+    /// Checks the configuration state for the **Linux** platform.
+    ///
+    /// The saved ruleset is compared against a dump of the live ruleset:
+    /// `/etc/nftables.conf` against `nft list ruleset` if present, otherwise
+    /// `/etc/iptables/rules.v4` and `/etc/iptables/rules.v6` against
+    /// `iptables-save` and `ip6tables-save`.
+    ///
+    /// # Returns
+    /// - `State::Draft` if the saved ruleset differs from the live one.
+    /// - `State::Applied` if both rulesets match.
+    /// - `State::Undefined` if no saved ruleset exists or the live ruleset cannot be dumped.
+    async fn check_linux(root: &Path) -> State {
+        let nftables_config = rooted(root, NFTABLES_CONFIG_PATH);
+
+        if nftables_config.exists() {
+            return Detector::compare_rulesets(
+                &nftables_config,
+                "nft",
+                &["list", "ruleset"],
+                normalize_nftables,
+            )
+            .await;
+        }
+
+        let mut state = State::Undefined;
+
+        for (path, program) in [
+            (IPTABLES_V4_RULES_PATH, "iptables-save"),
+            (IPTABLES_V6_RULES_PATH, "ip6tables-save"),
+        ] {
+            let path = rooted(root, path);
+
+            if !path.exists() {
+                continue;
+            }
+
+            match Detector::compare_rulesets(&path, program, &[], normalize_iptables).await {
+                State::Applied => state = State::Applied,
+                other => return other,
+            }
+        }
+
+        state
+    }
+
+    /// Compares a saved ruleset file with the output of a command dumping the live ruleset.
+    ///
+    /// # Parameters
+    /// - `path`: Path of the saved ruleset.
+    /// - `program`: The command printing the live ruleset.
+    /// - `args`: Arguments passed to `program`.
+    /// - `normalize`: A function turning a ruleset into a comparable form.
+    ///
+    /// # Returns
+    /// - `State::Draft` if the normalized rulesets differ.
+    /// - `State::Applied` if they are identical.
+    /// - `State::Undefined` if the file cannot be read or the command fails.
+    async fn compare_rulesets(
+        path: &Path,
+        program: &str,
+        args: &[&str],
+        normalize: fn(&str) -> Vec<String>,
+    ) -> State {
+        let Ok(saved) = fs::read_to_string(path).await else {
+            return State::Undefined;
+        };
+
+        let live = match Command::new(program).args(args).output().await {
+            Ok(output) if output.status.success() => output.stdout,
+            _ => return State::Undefined,
+        };
+
+        if normalize(&saved) == normalize(&String::from_utf8_lossy(&live)) {
+            State::Applied
+        } else {
+            State::Draft
+        }
+    }
+}
+
+/// Normalizes an nftables ruleset so that a saved file and `nft list ruleset` output can be compared.
+///
+/// Comments, blank lines, `flush ruleset` commands, statement terminators and
+/// counter values are dropped, and whitespace is collapsed.
+fn normalize_nftables(ruleset: &str) -> Vec<String> {
+    ruleset
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .map(|line| {
+            let mut words = Vec::new();
+            let mut iter = line.split_whitespace();
+
+            while let Some(word) = iter.next() {
+                if word == "packets" || word == "bytes" {
+                    iter.next();
+                    continue;
+                }
+                words.push(word.trim_end_matches(';'));
+            }
+
+            words.retain(|word| !word.is_empty());
+            words.join(" ")
+        })
+        .filter(|line| !line.is_empty() && line != "flush ruleset")
+        .collect()
+}
+
+/// Normalizes an `iptables-save` ruleset so that a saved file and a live dump can be compared.
+///
+/// Comments, blank lines and the packet/byte counters of chain declarations are dropped.
+fn normalize_iptables(ruleset: &str) -> Vec<String> {
+    ruleset
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            if line.starts_with(':') {
+                match line.rfind(" [") {
+                    Some(index) => line[..index].to_string(),
+                    None => line.to_string(),
+                }
+            } else {
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_iptables, normalize_nftables};
+
+    #[test]
+    fn test_normalize_nftables_ignores_formatting_and_counters() {
+        let saved = r#"#!/usr/sbin/nft -f
+flush ruleset
+
+table inet filter {
+    chain input {
+        type filter hook input priority 0; policy drop;
+        # allow ssh
+        tcp dport 22 counter accept
+    }
+}
+"#;
+        let live = r#"table inet filter {
+	chain input {
+		type filter hook input priority 0; policy drop;
+		tcp dport 22 counter packets 12 bytes 720 accept
+	}
+}
+"#;
+
+        assert_eq!(normalize_nftables(saved), normalize_nftables(live));
+    }
+
+    #[test]
+    fn test_normalize_nftables_detects_rule_changes() {
+        let saved = "table inet filter {\n chain input {\n tcp dport 22 accept\n }\n}\n";
+        let live = "table inet filter {\n chain input {\n tcp dport 2222 accept\n }\n}\n";
+
+        assert_ne!(normalize_nftables(saved), normalize_nftables(live));
+    }
+
+    #[test]
+    fn test_normalize_iptables_ignores_comments_and_counters() {
+        let saved = r#"# Generated by iptables-save v1.8.7 on Mon Jan  1 00:00:00 2024
+*filter
+:INPUT DROP [0:0]
+:FORWARD DROP [0:0]
+:OUTPUT ACCEPT [0:0]
+-A INPUT -p tcp -m tcp --dport 22 -j ACCEPT
+COMMIT
+# Completed on Mon Jan  1 00:00:00 2024
+"#;
+        let live = r#"# Generated by iptables-save v1.8.7 on Tue Feb  2 10:00:00 2024
+*filter
+:INPUT DROP [1234:56789]
+:FORWARD DROP [0:0]
+:OUTPUT ACCEPT [42:4200]
+-A INPUT -p tcp -m tcp --dport 22 -j ACCEPT
+COMMIT
+# Completed on Tue Feb  2 10:00:00 2024
+"#;
+
+        assert_eq!(normalize_iptables(saved), normalize_iptables(live));
+    }
+
+    #[test]
+    fn test_normalize_iptables_detects_rule_changes() {
+        let saved = "*filter\n:INPUT DROP [0:0]\n-A INPUT -j ACCEPT\nCOMMIT\n";
+        let live = "*filter\n:INPUT DROP [0:0]\nCOMMIT\n";
+
+        assert_ne!(normalize_iptables(saved), normalize_iptables(live));
+    }
 }
@@ -0,0 +1,144 @@
+pub mod types;
+
+use crate::{Error, ErrorKind, watcher::utils::make_error_mapper};
+use serde::{Serialize, de::DeserializeOwned};
+use types::{Codec, ENVELOPE_MAGIC, EnvelopeHeader};
+
+/// Length of the envelope header: magic, schema version (`u16`, big-endian) and codec (`u8`).
+const HEADER_LEN: usize = ENVELOPE_MAGIC.len() + 3;
+
+/// Encodes a value into a versioned envelope.
+///
+/// # Parameters
+/// - `value`: The value to encode.
+/// - `schema_version`: The version of the schema of `value`, stored in the envelope.
+/// - `codec`: The codec used to encode `value`.
+///
+/// # Returns
+/// - `Ok(Vec<u8>)`: The envelope header followed by the encoded value.
+/// - `Err(Error)`: An error with `ErrorKind::ErrorEncoding` if the value cannot be encoded.
+pub fn encode<T: Serialize>(
+    value: &T,
+    schema_version: u16,
+    codec: Codec,
+) -> Result<Vec<u8>, Error> {
+    let mut data = ENVELOPE_MAGIC.to_vec();
+    data.extend_from_slice(&schema_version.to_be_bytes());
+    data.push(codec as u8);
+
+    match codec {
+        Codec::Bincode => bincode::serialize_into(&mut data, value)
+            .map_err(make_error_mapper(ErrorKind::ErrorEncoding))?,
+        Codec::Json => serde_json::to_writer(&mut data, value)
+            .map_err(make_error_mapper(ErrorKind::ErrorEncoding))?,
+        Codec::Cbor => ciborium::into_writer(value, &mut data)
+            .map_err(make_error_mapper(ErrorKind::ErrorEncoding))?,
+    }
+
+    Ok(data)
+}
+
+/// Reads the header of a versioned envelope.
+///
+/// # Parameters
+/// - `data`: The encoded data.
+///
+/// # Returns
+/// - `Some((EnvelopeHeader, &[u8]))`: The header and the encoded value that follows it.
+/// - `None`: If `data` does not start with an envelope header (e.g. legacy payloads).
+pub fn read_header(data: &[u8]) -> Option<(EnvelopeHeader, &[u8])> {
+    if data.len() < HEADER_LEN || !data.starts_with(ENVELOPE_MAGIC) {
+        return None;
+    }
+
+    let (header, payload) = data.split_at(HEADER_LEN);
+    let schema_version = u16::from_be_bytes([header[4], header[5]]);
+    let codec = Codec::from_tag(header[6])?;
+
+    Some((
+        EnvelopeHeader {
+            schema_version,
+            codec,
+        },
+        payload,
+    ))
+}
+
+/// Decodes a value from a versioned envelope.
+///
+/// The schema version is not checked: callers are expected to inspect the returned
+/// header and decide whether they understand it.
+///
+/// # Parameters
+/// - `data`: The encoded data, starting with an envelope header.
+///
+/// # Returns
+/// - `Ok((EnvelopeHeader, T))`: The header and the decoded value.
+/// - `Err(Error)`: An error with `ErrorKind::ErrorDecoding` if the data is not a valid envelope
+///   or the value cannot be decoded.
+pub fn decode<T: DeserializeOwned>(data: &[u8]) -> Result<(EnvelopeHeader, T), Error> {
+    let (header, payload) = read_header(data).ok_or(Error {
+        kind: ErrorKind::ErrorDecoding,
+        message: String::from("Data does not start with a valid envelope header"),
+    })?;
+
+    let value = decode_payload(header.codec, payload)?;
+
+    Ok((header, value))
+}
+
+/// Decodes a value encoded with a given codec, without envelope.
+pub(crate) fn decode_payload<T: DeserializeOwned>(
+    codec: Codec,
+    payload: &[u8],
+) -> Result<T, Error> {
+    match codec {
+        Codec::Bincode => {
+            bincode::deserialize(payload).map_err(make_error_mapper(ErrorKind::ErrorDecoding))
+        }
+        Codec::Json => {
+            serde_json::from_slice(payload).map_err(make_error_mapper(ErrorKind::ErrorDecoding))
+        }
+        Codec::Cbor => {
+            ciborium::from_reader(payload).map_err(make_error_mapper(ErrorKind::ErrorDecoding))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, read_header};
+    use crate::{Codec, EnvelopeHeader};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Value {
+        name: String,
+        count: u32,
+    }
+
+    #[test]
+    fn test_roundtrip_all_codecs() {
+        let value = Value {
+            name: String::from("em0"),
+            count: 3,
+        };
+
+        for codec in [Codec::Bincode, Codec::Json, Codec::Cbor] {
+            let data = encode(&value, 7, codec).unwrap();
+            let (header, decoded): (EnvelopeHeader, Value) = decode(&data).unwrap();
+
+            assert_eq!(header.schema_version, 7);
+            assert_eq!(header.codec, codec);
+            assert_eq!(decoded, value);
+        }
+    }
+
+    #[test]
+    fn test_read_header_rejects_foreign_data() {
+        assert!(read_header(b"").is_none());
+        assert!(read_header(&[1, 0, 0, 0, 0, 0, 0, 0]).is_none());
+        assert!(read_header(b"NNCM\x00\x01\x09").is_none());
+        assert!(decode::<Value>(b"{}").is_err());
+    }
+}
@@ -0,0 +1,42 @@
+/// Marker starting every versioned envelope.
+pub const ENVELOPE_MAGIC: &[u8; 4] = b"NNCM";
+
+/// The codec used to encode the value held by an envelope.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Codec {
+    /// Compact binary encoding. Not self-describing: both ends must agree on the schema version.
+    #[default]
+    Bincode = 0,
+    /// JSON. Self-describing: unknown fields are ignored and missing ones take their default value.
+    Json = 1,
+    /// CBOR. Self-describing, like JSON, but more compact.
+    Cbor = 2,
+}
+
+impl Codec {
+    /// Returns the codec identified by a tag, as stored in an envelope header.
+    pub fn from_tag(tag: u8) -> Option<Codec> {
+        match tag {
+            0 => Some(Codec::Bincode),
+            1 => Some(Codec::Json),
+            2 => Some(Codec::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if data encoded with this codec can be decoded with a different schema version.
+    pub fn is_self_describing(self) -> bool {
+        self != Codec::Bincode
+    }
+}
+
+/// The header of a versioned envelope.
+///
+/// # Fields
+/// - `schema_version`: The version of the schema of the encoded value.
+/// - `codec`: The codec used to encode the value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvelopeHeader {
+    pub schema_version: u16,
+    pub codec: Codec,
+}
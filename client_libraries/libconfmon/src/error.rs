@@ -8,6 +8,12 @@ pub enum ErrorKind {
     ErrorReadingFile,
     ErrorHandlingSnapshot,
     ErrorUnsupportedPlatform,
+    ErrorWritingFile,
+    ErrorAccessingHistory,
+    ErrorVersionNotFound,
+    ErrorMonitoringInterfaces,
+    ErrorEncoding,
+    ErrorDecoding,
 }
 
 impl Display for ErrorKind {
@@ -19,6 +25,12 @@ impl Display for ErrorKind {
             ErrorKind::ErrorReadingFile => write!(f, "ErrorReadingFile"),
             ErrorKind::ErrorHandlingSnapshot => write!(f, "ErrorHandlingSnapshot"),
             ErrorKind::ErrorUnsupportedPlatform => write!(f, "ErrorUnsupportedPlatform"),
+            ErrorKind::ErrorWritingFile => write!(f, "ErrorWritingFile"),
+            ErrorKind::ErrorAccessingHistory => write!(f, "ErrorAccessingHistory"),
+            ErrorKind::ErrorVersionNotFound => write!(f, "ErrorVersionNotFound"),
+            ErrorKind::ErrorMonitoringInterfaces => write!(f, "ErrorMonitoringInterfaces"),
+            ErrorKind::ErrorEncoding => write!(f, "ErrorEncoding"),
+            ErrorKind::ErrorDecoding => write!(f, "ErrorDecoding"),
         }
     }
 }
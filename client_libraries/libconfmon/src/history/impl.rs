@@ -0,0 +1,445 @@
+use super::{
+    types::{HistoryEntry, HistoryFile},
+    utils::{compress, decompress, restore_path, write_atomically},
+};
+use crate::{
+    Error, ErrorKind, FileData, Revision, Snapshot, SnapshotDiff, State, snapshot_digest,
+    watcher::utils::make_error_mapper,
+};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tokio::fs;
+
+const INDEX_FILENAME: &str = "index.bin";
+const OBJECTS_DIRNAME: &str = "objects";
+
+/// A bounded, compressed and content-addressed local store of past snapshots.
+///
+/// File contents are stored once per distinct hash under `<root>/objects/`, compressed with zlib,
+/// and the list of versions is kept in `<root>/index.bin`. When more than `max_versions`
+/// versions are recorded, the oldest ones are dropped along with the objects no longer referenced.
+pub struct SnapshotHistory {
+    /// Directory holding the history.
+    root: PathBuf,
+    /// Maximum number of versions to keep.
+    max_versions: usize,
+    /// Recorded versions, from the oldest to the newest.
+    entries: Vec<HistoryEntry>,
+}
+
+impl SnapshotHistory {
+    /// Opens the history stored in `root`, creating it if it does not exist.
+    ///
+    /// # Parameters
+    /// - `root`: The directory holding the history.
+    /// - `max_versions`: The maximum number of versions to keep (at least one is always kept).
+    ///
+    /// # Returns
+    /// - `Ok(Self)`: The opened history.
+    /// - `Err(Error)`: If the directory cannot be created or the index cannot be read.
+    pub async fn open(root: impl Into<PathBuf>, max_versions: usize) -> Result<Self, Error> {
+        let root = root.into();
+
+        fs::create_dir_all(root.join(OBJECTS_DIRNAME))
+            .await
+            .map_err(make_error_mapper(ErrorKind::ErrorAccessingHistory))?;
+
+        let index_path = root.join(INDEX_FILENAME);
+
+        let entries = if fs::try_exists(&index_path).await.unwrap_or(false) {
+            let data = fs::read(&index_path)
+                .await
+                .map_err(make_error_mapper(ErrorKind::ErrorAccessingHistory))?;
+
+            bincode::deserialize(&data)
+                .map_err(make_error_mapper(ErrorKind::ErrorAccessingHistory))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            root,
+            max_versions: max_versions.max(1),
+            entries,
+        })
+    }
+
+    /// Records a snapshot as a new version.
+    ///
+    /// If the latest version has the same content and state, no new version is created.
+    ///
+    /// # Parameters
+    /// - `snapshot`: The snapshot to record.
+    /// - `state`: The configuration state at the time the snapshot was taken.
+    ///
+    /// # Returns
+    /// - `Ok(u64)`: The version number of the recorded (or identical latest) version.
+    /// - `Err(Error)`: If the snapshot cannot be stored.
+    pub async fn record(&mut self, snapshot: &Snapshot, state: State) -> Result<u64, Error> {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or_default();
+
+        self.record_at(snapshot, state, timestamp).await
+    }
+
+    /// Imports past snapshots, such as the configuration backups returned by `Watcher::backups`.
+    ///
+    /// Snapshots are recorded in order as `Applied` versions. Each version is timestamped with
+    /// the time of its revision (see `Revision`), or with the latest modification time
+    /// of its files if it carries no attribution.
+    ///
+    /// # Parameters
+    /// - `snapshots`: The snapshots to import, from the oldest to the newest.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<u64>)`: The version numbers of the imported snapshots.
+    /// - `Err(Error)`: If a snapshot cannot be stored.
+    pub async fn import(&mut self, snapshots: &[Snapshot]) -> Result<Vec<u64>, Error> {
+        let mut versions = Vec::with_capacity(snapshots.len());
+
+        for snapshot in snapshots {
+            let timestamp = Revision::from_snapshot(snapshot)
+                .map(|revision| revision.time)
+                .or_else(|| snapshot.iter().map(|file| file.mtime).max())
+                .unwrap_or_default();
+
+            versions.push(self.record_at(snapshot, State::Applied, timestamp).await?);
+        }
+
+        Ok(versions)
+    }
+
+    /// Records a snapshot as a new version with the given timestamp (see `record`).
+    async fn record_at(
+        &mut self,
+        snapshot: &Snapshot,
+        state: State,
+        timestamp: u128,
+    ) -> Result<u64, Error> {
+        let digest = snapshot_digest(snapshot);
+
+        if let Some(latest) = self.entries.last()
+            && latest.digest == digest
+            && latest.state == state
+        {
+            return Ok(latest.version);
+        }
+
+        for file in snapshot {
+            let object_path = self.object_path(&file.hash);
+
+            if !fs::try_exists(&object_path).await.unwrap_or(false) {
+                write_atomically(&object_path, &compress(&file.content)?).await?;
+            }
+        }
+
+        let version = self.entries.last().map_or(1, |latest| latest.version + 1);
+
+        self.entries.push(HistoryEntry {
+            version,
+            timestamp,
+            state,
+            digest,
+            files: snapshot
+                .iter()
+                .map(|file| HistoryFile {
+                    filename: file.filename.clone(),
+                    hash: file.hash.clone(),
+                    size: file.size,
+                    mtime: file.mtime,
+                })
+                .collect(),
+        });
+
+        let evicted = self.entries.len().saturating_sub(self.max_versions);
+        self.entries.drain(..evicted);
+
+        self.save_index().await?;
+
+        if evicted > 0 {
+            self.collect_garbage().await?;
+        }
+
+        Ok(version)
+    }
+
+    /// Lists the recorded versions, from the oldest to the newest.
+    pub fn list(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Returns the most recent version, if any.
+    pub fn latest(&self) -> Option<&HistoryEntry> {
+        self.entries.last()
+    }
+
+    /// Fetches the snapshot recorded as a given version.
+    ///
+    /// # Parameters
+    /// - `version`: The version number.
+    ///
+    /// # Returns
+    /// - `Ok(Snapshot)`: The snapshot, with the original file contents and metadata.
+    /// - `Err(Error)`: If the version does not exist or its objects cannot be read.
+    pub async fn fetch(&self, version: u64) -> Result<Snapshot, Error> {
+        let entry = self.entry(version)?;
+        let mut snapshot = Snapshot::new();
+
+        for file in &entry.files {
+            let data = fs::read(self.object_path(&file.hash))
+                .await
+                .map_err(make_error_mapper(ErrorKind::ErrorAccessingHistory))?;
+
+            snapshot.push(FileData::new(
+                file.filename.clone(),
+                decompress(&data)?,
+                file.mtime,
+            ));
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Computes the differences between two recorded versions.
+    ///
+    /// # Parameters
+    /// - `from`: The older version.
+    /// - `to`: The newer version.
+    ///
+    /// # Returns
+    /// - `Ok(SnapshotDiff)`: The changes needed to go from `from` to `to`.
+    /// - `Err(Error)`: If either version cannot be fetched.
+    pub async fn diff(&self, from: u64, to: u64) -> Result<SnapshotDiff, Error> {
+        let old = self.fetch(from).await?;
+        let new = self.fetch(to).await?;
+
+        Ok(SnapshotDiff::compute(&old, &new))
+    }
+
+    /// Restores a recorded version by writing its files back into a directory.
+    ///
+    /// Every file is written atomically, under `directory`. Synthesized entries (whose name
+    /// starts with `#`, such as `#NetworkInterfaces`) are not written. Nothing is written if
+    /// any recorded name is absolute or contains `..`.
+    ///
+    /// # Parameters
+    /// - `version`: The version to restore.
+    /// - `directory`: The directory the files are written to (e.g. `/conf` for `config.xml`).
+    ///
+    /// # Returns
+    /// - `Ok(())`: If all files were restored.
+    /// - `Err(Error)`: If the version cannot be fetched, a file would be written outside of
+    ///   `directory` or a file cannot be written.
+    pub async fn restore(&self, version: u64, directory: &Path) -> Result<(), Error> {
+        let mut files = Vec::new();
+
+        for file in self.fetch(version).await? {
+            if file.filename.starts_with('#') {
+                continue;
+            }
+
+            files.push((restore_path(directory, &file.filename)?, file.content));
+        }
+
+        for (path, content) in files {
+            write_atomically(&path, &content).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Looks up a version in the index.
+    fn entry(&self, version: u64) -> Result<&HistoryEntry, Error> {
+        self.entries
+            .iter()
+            .find(|entry| entry.version == version)
+            .ok_or(Error {
+                kind: ErrorKind::ErrorVersionNotFound,
+                message: format!("Version {version} is not in the history"),
+            })
+    }
+
+    /// Returns the path of the object storing the content with the given hash.
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.root.join(OBJECTS_DIRNAME).join(hash)
+    }
+
+    /// Persists the index.
+    async fn save_index(&self) -> Result<(), Error> {
+        let data = bincode::serialize(&self.entries)
+            .map_err(make_error_mapper(ErrorKind::ErrorAccessingHistory))?;
+
+        write_atomically(&self.root.join(INDEX_FILENAME), &data).await
+    }
+
+    /// Removes the objects that are no longer referenced by any version.
+    async fn collect_garbage(&self) -> Result<(), Error> {
+        let referenced: HashSet<&str> = self
+            .entries
+            .iter()
+            .flat_map(|entry| entry.files.iter().map(|file| file.hash.as_str()))
+            .collect();
+
+        let mut objects = fs::read_dir(self.root.join(OBJECTS_DIRNAME))
+            .await
+            .map_err(make_error_mapper(ErrorKind::ErrorAccessingHistory))?;
+
+        while let Ok(Some(object)) = objects.next_entry().await {
+            let name = object.file_name();
+
+            if !referenced.contains(name.to_string_lossy().as_ref()) {
+                fs::remove_file(object.path())
+                    .await
+                    .map_err(make_error_mapper(ErrorKind::ErrorAccessingHistory))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SnapshotHistory;
+    use crate::{FileData, Revision, State};
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("libconfmon-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn snapshot(content: &str) -> Vec<FileData> {
+        vec![
+            FileData::new("config.xml", content.as_bytes().to_vec(), 1),
+            FileData::new("#NetworkInterfaces", vec![0, 1, 2], 0),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_record_fetch_and_diff() {
+        let dir = temp_dir("history-record");
+        let mut history = SnapshotHistory::open(&dir, 10).await.unwrap();
+
+        let v1 = history
+            .record(&snapshot("<a>1</a>"), State::Applied)
+            .await
+            .unwrap();
+        let same = history
+            .record(&snapshot("<a>1</a>"), State::Applied)
+            .await
+            .unwrap();
+        let v2 = history
+            .record(&snapshot("<a>2</a>"), State::Draft)
+            .await
+            .unwrap();
+
+        assert_eq!(v1, 1);
+        assert_eq!(same, 1);
+        assert_eq!(v2, 2);
+        assert_eq!(history.list().len(), 2);
+        assert_eq!(history.latest().unwrap().state, State::Draft);
+
+        let fetched = history.fetch(v1).await.unwrap();
+        assert_eq!(fetched[0].content, b"<a>1</a>");
+
+        let diff = history.diff(v1, v2).await.unwrap();
+        assert_eq!(diff.files.len(), 1);
+        assert_eq!(diff.files[0].filename, "config.xml");
+
+        // The index survives reopening
+        let reopened = SnapshotHistory::open(&dir, 10).await.unwrap();
+        assert_eq!(reopened.list(), history.list());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_history_and_restore() {
+        let dir = temp_dir("history-bounded");
+        let target = dir.join("target");
+        std::fs::create_dir_all(&target).unwrap();
+
+        let mut history = SnapshotHistory::open(dir.join("store"), 2).await.unwrap();
+
+        for content in ["<a>1</a>", "<a>2</a>", "<a>3</a>"] {
+            history
+                .record(&snapshot(content), State::Applied)
+                .await
+                .unwrap();
+        }
+
+        let versions: Vec<u64> = history.list().iter().map(|e| e.version).collect();
+        assert_eq!(versions, vec![2, 3]);
+        assert!(history.fetch(1).await.is_err());
+
+        // Objects of the evicted version are removed, shared ones are kept
+        let objects = std::fs::read_dir(dir.join("store").join("objects")).unwrap();
+        assert_eq!(objects.count(), 3);
+
+        history.restore(2, &target).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(target.join("config.xml")).unwrap(),
+            "<a>2</a>"
+        );
+        assert!(!target.join("#NetworkInterfaces").exists());
+
+        // Files recorded with a name escaping the target directory are never written
+        let version = history
+            .record(
+                &vec![
+                    FileData::new("config.xml", b"<a>4</a>".to_vec(), 1),
+                    FileData::new("../escaped.xml", b"<a>4</a>".to_vec(), 1),
+                ],
+                State::Applied,
+            )
+            .await
+            .unwrap();
+        assert!(history.restore(version, &target).await.is_err());
+        assert!(!dir.join("escaped.xml").exists());
+        assert_eq!(
+            std::fs::read_to_string(target.join("config.xml")).unwrap(),
+            "<a>2</a>"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_import_backups() {
+        let dir = temp_dir("history-import");
+        let mut history = SnapshotHistory::open(&dir, 10).await.unwrap();
+
+        let revision = Revision {
+            time: 1_700_000_000_000,
+            username: String::from("admin"),
+            description: String::from("Firewall: Rules - saved"),
+        };
+
+        let backups = vec![
+            vec![
+                FileData::new("config.xml", b"<a>1</a>".to_vec(), 1_699_999_999_000),
+                revision.to_entry().unwrap(),
+            ],
+            vec![FileData::new(
+                "config.xml",
+                b"<a>2</a>".to_vec(),
+                1_700_000_100_000,
+            )],
+        ];
+
+        assert_eq!(history.import(&backups).await.unwrap(), vec![1, 2]);
+
+        let timestamps: Vec<u128> = history.list().iter().map(|e| e.timestamp).collect();
+        assert_eq!(timestamps, vec![1_700_000_000_000, 1_700_000_100_000]);
+        assert_eq!(history.latest().unwrap().state, State::Applied);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
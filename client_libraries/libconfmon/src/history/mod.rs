@@ -0,0 +1,4 @@
+pub mod r#impl;
+pub mod types;
+
+pub(crate) mod utils;
@@ -0,0 +1,34 @@
+use crate::State;
+use serde::{Deserialize, Serialize};
+
+/// Metadata of a file stored in a history version.
+///
+/// # Fields
+/// - `filename`: The name of the file.
+/// - `hash`: The hex-encoded SHA-256 digest of the content, also used as the key of the stored object.
+/// - `size`: The size of the content in bytes.
+/// - `mtime`: The modification time of the file in milliseconds since the UNIX epoch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryFile {
+    pub filename: String,
+    pub hash: String,
+    pub size: u64,
+    pub mtime: u128,
+}
+
+/// A single version recorded in the local snapshot history.
+///
+/// # Fields
+/// - `version`: A monotonically increasing version number.
+/// - `timestamp`: The time the version was recorded, in milliseconds since the UNIX epoch.
+/// - `state`: The configuration state at the time the snapshot was taken.
+/// - `digest`: The digest of the whole snapshot (see `snapshot_digest`).
+/// - `files`: The files making up the snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub version: u64,
+    pub timestamp: u128,
+    pub state: State,
+    pub digest: String,
+    pub files: Vec<HistoryFile>,
+}
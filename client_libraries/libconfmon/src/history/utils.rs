@@ -0,0 +1,140 @@
+use crate::{Error, ErrorKind, watcher::utils::make_error_mapper};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tokio::{fs, io::AsyncWriteExt};
+
+/// Compresses data with zlib.
+///
+/// # Parameters
+/// - `data`: The bytes to compress.
+///
+/// # Returns
+/// - `Ok(Vec<u8>)`: The compressed bytes.
+/// - `Err(Error)`: If compression fails.
+pub fn compress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+
+    encoder
+        .write_all(data)
+        .map_err(make_error_mapper(ErrorKind::ErrorAccessingHistory))?;
+
+    encoder
+        .finish()
+        .map_err(make_error_mapper(ErrorKind::ErrorAccessingHistory))
+}
+
+/// Decompresses zlib data.
+///
+/// # Parameters
+/// - `data`: The compressed bytes.
+///
+/// # Returns
+/// - `Ok(Vec<u8>)`: The original bytes.
+/// - `Err(Error)`: If the data is not valid zlib.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut content = Vec::new();
+
+    ZlibDecoder::new(data)
+        .read_to_end(&mut content)
+        .map_err(make_error_mapper(ErrorKind::ErrorAccessingHistory))?;
+
+    Ok(content)
+}
+
+/// Writes a file atomically.
+///
+/// The content is first written to a temporary file in the same directory,
+/// flushed to disk and then renamed over the destination, so readers never
+/// observe a partially written file.
+///
+/// # Parameters
+/// - `path`: The destination path.
+/// - `content`: The content to write.
+///
+/// # Returns
+/// - `Ok(())`: If the file was written.
+/// - `Err(Error)`: An error with `ErrorKind::ErrorWritingFile` otherwise.
+pub async fn write_atomically(path: &Path, content: &[u8]) -> Result<(), Error> {
+    let filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let tmp_path = path.with_file_name(format!(".{filename}.tmp"));
+
+    let mut file = fs::File::create(&tmp_path)
+        .await
+        .map_err(make_error_mapper(ErrorKind::ErrorWritingFile))?;
+
+    file.write_all(content)
+        .await
+        .map_err(make_error_mapper(ErrorKind::ErrorWritingFile))?;
+
+    file.sync_all()
+        .await
+        .map_err(make_error_mapper(ErrorKind::ErrorWritingFile))?;
+
+    fs::rename(&tmp_path, path)
+        .await
+        .map_err(make_error_mapper(ErrorKind::ErrorWritingFile))
+}
+
+/// Resolves the path a recorded file is restored to.
+///
+/// # Parameters
+/// - `directory`: The directory the files are restored into.
+/// - `filename`: The recorded name of the file.
+///
+/// # Returns
+/// - `Ok(PathBuf)`: The path of the file, under `directory`.
+/// - `Err(Error)`: An error with `ErrorKind::ErrorWritingFile` if `filename` is empty,
+///   absolute or contains `..`, and would thus escape `directory`.
+pub fn restore_path(directory: &Path, filename: &str) -> Result<PathBuf, Error> {
+    let relative = Path::new(filename);
+    let is_contained = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+
+    if filename.is_empty() || !is_contained {
+        return Err(Error {
+            kind: ErrorKind::ErrorWritingFile,
+            message: format!("Refusing to restore '{filename}' outside of the target directory"),
+        });
+    }
+
+    Ok(directory.join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decompress, restore_path};
+    use std::path::Path;
+
+    #[test]
+    fn test_compress_roundtrip() {
+        let data = b"<pfsense><system/></pfsense>".repeat(100);
+        let compressed = compress(&data).unwrap();
+
+        assert!(compressed.len() < data.len());
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn test_restore_path() {
+        let directory = Path::new("/conf");
+
+        assert_eq!(
+            restore_path(directory, "config.xml").unwrap(),
+            directory.join("config.xml")
+        );
+        assert_eq!(
+            restore_path(directory, "backup/config.xml").unwrap(),
+            directory.join("backup/config.xml")
+        );
+        assert!(restore_path(directory, "").is_err());
+        assert!(restore_path(directory, "/etc/passwd").is_err());
+        assert!(restore_path(directory, "../etc/passwd").is_err());
+        assert!(restore_path(directory, "backup/../../etc/passwd").is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use super::{
+    types::{InterfaceChange, InterfaceChangeStream, MonitorMode},
+    utils::diff_interfaces,
+};
+use crate::{InterfaceSnapshot, WatchTrigger};
+use std::{future::Future, time::Duration};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+/// Delay used to group the bursts of netlink notifications caused by a single change.
+const NETLINK_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// A monitor of the network interfaces of the host.
+///
+/// Interface address or link changes (DHCP renewals, cables pulled, VLANs added) don't touch
+/// the monitored configuration files, so the `Watcher` can't see them. The monitor detects
+/// them, reports them as `InterfaceChange`s and can request the `Watcher` to dispatch a new
+/// snapshot through a `WatchTrigger`.
+pub struct InterfaceMonitor {
+    /// Last known state of the interfaces.
+    interfaces: Vec<InterfaceSnapshot>,
+    /// Polling interval (in milliseconds), used when netlink is not available.
+    poll_interval: u64,
+    /// How changes are detected.
+    mode: MonitorMode,
+    /// Sender of the change stream, if subscribed.
+    events: Option<mpsc::UnboundedSender<InterfaceChange>>,
+    /// Trigger fired whenever a change is detected.
+    trigger: Option<WatchTrigger>,
+}
+
+impl InterfaceMonitor {
+    /// Creates a new `InterfaceMonitor`, capturing the current state of the interfaces.
+    ///
+    /// Netlink notifications are used on Linux, polling on other platforms.
+    ///
+    /// # Parameters
+    /// - `poll_interval`: Time interval (in milliseconds) between two polls, when polling is used.
+    pub async fn new(poll_interval: u64) -> Self {
+        let mode = if cfg!(target_os = "linux") {
+            MonitorMode::Netlink
+        } else {
+            MonitorMode::Polling
+        };
+
+        Self {
+            interfaces: take_all().await,
+            poll_interval,
+            mode,
+            events: None,
+            trigger: None,
+        }
+    }
+
+    /// Sets how changes are detected.
+    ///
+    /// Requesting `MonitorMode::Netlink` where netlink is not available falls back to polling.
+    pub fn with_mode(mut self, mode: MonitorMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets a trigger to fire whenever a change is detected, typically obtained
+    /// with `Watcher::trigger` so that a new snapshot is dispatched.
+    pub fn with_trigger(mut self, trigger: WatchTrigger) -> Self {
+        self.trigger = Some(trigger);
+        self
+    }
+
+    /// Subscribes to the changes detected by the monitor.
+    ///
+    /// Only one subscriber is supported: subscribing again replaces the previous stream,
+    /// which then ends.
+    pub fn subscribe(&mut self) -> InterfaceChangeStream {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.events = Some(sender);
+        UnboundedReceiverStream::new(receiver)
+    }
+
+    /// Returns the last known state of the interfaces.
+    pub fn interfaces(&self) -> &[InterfaceSnapshot] {
+        &self.interfaces
+    }
+
+    /// Returns how changes are currently detected.
+    pub fn mode(&self) -> MonitorMode {
+        self.mode
+    }
+
+    /// Starts monitoring the interfaces.
+    ///
+    /// This function never returns; see `run_until` for a cancellable alternative.
+    pub async fn run(&mut self) {
+        self.run_until(std::future::pending()).await
+    }
+
+    /// Monitors the interfaces until `shutdown` completes.
+    ///
+    /// If netlink notifications cannot be received, or stop being received,
+    /// the monitor falls back to polling.
+    ///
+    /// # Parameters
+    /// - `shutdown`: A future whose completion stops the monitor.
+    pub async fn run_until<F: Future<Output = ()>>(&mut self, shutdown: F) {
+        tokio::pin!(shutdown);
+
+        let mut notifications = self.subscribe_netlink();
+
+        loop {
+            let poll_interval = Duration::from_millis(self.poll_interval);
+
+            let wait = async {
+                let Some(receiver) = &mut notifications else {
+                    tokio::time::sleep(poll_interval).await;
+                    return true;
+                };
+
+                if receiver.recv().await.is_none() {
+                    return false;
+                }
+
+                tokio::time::sleep(NETLINK_DEBOUNCE).await;
+                while receiver.try_recv().is_ok() {}
+
+                true
+            };
+
+            tokio::select! {
+                _ = &mut shutdown => return,
+                alive = wait => {
+                    if !alive {
+                        notifications = None;
+                        self.mode = MonitorMode::Polling;
+                    }
+                }
+            }
+
+            self.refresh().await;
+        }
+    }
+
+    /// Captures the interfaces and reports the changes since the last known state.
+    ///
+    /// Every change is sent to the subscriber, and the trigger is fired if anything changed.
+    ///
+    /// # Returns
+    /// The detected changes.
+    pub async fn refresh(&mut self) -> Vec<InterfaceChange> {
+        let interfaces = take_all().await;
+        let changes = diff_interfaces(&self.interfaces, &interfaces);
+
+        if !changes.is_empty() {
+            if let Some(events) = &self.events {
+                for change in &changes {
+                    let _ = events.send(change.clone());
+                }
+            }
+
+            if let Some(trigger) = &self.trigger {
+                trigger.fire();
+            }
+        }
+
+        self.interfaces = interfaces;
+        changes
+    }
+
+    /// Subscribes to netlink notifications if the mode requires it,
+    /// falling back to polling if they are not available.
+    fn subscribe_netlink(&mut self) -> Option<mpsc::UnboundedReceiver<()>> {
+        if self.mode != MonitorMode::Netlink {
+            return None;
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Ok(receiver) = super::netlink::subscribe() {
+            return Some(receiver);
+        }
+
+        self.mode = MonitorMode::Polling;
+        None
+    }
+}
+
+/// Captures the interfaces without blocking the runtime.
+async fn take_all() -> Vec<InterfaceSnapshot> {
+    tokio::task::spawn_blocking(InterfaceSnapshot::take_all)
+        .await
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InterfaceMonitor;
+    use crate::{InterfaceChange, MonitorMode, WatchTrigger};
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_refresh_reports_changes_and_fires_trigger() {
+        let (sender, mut triggers) = tokio::sync::mpsc::unbounded_channel();
+
+        let mut monitor = InterfaceMonitor::new(10)
+            .await
+            .with_mode(MonitorMode::Polling)
+            .with_trigger(WatchTrigger { sender });
+        let mut changes = monitor.subscribe();
+
+        assert!(monitor.refresh().await.is_empty());
+        assert!(triggers.try_recv().is_err());
+
+        let removed = monitor.interfaces.remove(0);
+        let detected = monitor.refresh().await;
+
+        assert_eq!(detected, vec![InterfaceChange::Added(removed.name.clone())]);
+        assert_eq!(
+            changes.next().await,
+            Some(InterfaceChange::Added(removed.name))
+        );
+        assert!(triggers.try_recv().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_run_until_stops() {
+        let mut monitor = InterfaceMonitor::new(10).await;
+
+        monitor
+            .run_until(tokio::time::sleep(std::time::Duration::from_millis(50)))
+            .await;
+    }
+}
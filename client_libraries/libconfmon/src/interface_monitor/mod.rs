@@ -0,0 +1,6 @@
+pub mod r#impl;
+pub mod types;
+
+#[cfg(target_os = "linux")]
+mod netlink;
+pub mod utils;
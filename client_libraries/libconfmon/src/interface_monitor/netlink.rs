@@ -0,0 +1,52 @@
+use crate::{Error, ErrorKind, watcher::utils::make_error_mapper};
+use netlink_sys::{AsyncSocket, SocketAddr};
+use rtnetlink::constants::{
+    RTMGRP_IPV4_IFADDR, RTMGRP_IPV4_ROUTE, RTMGRP_IPV6_IFADDR, RTMGRP_IPV6_ROUTE, RTMGRP_LINK,
+};
+use tokio::sync::mpsc;
+use tokio_stream::StreamExt;
+
+/// Subscribes to the rtnetlink link, address and route notifications.
+///
+/// The content of the notifications is not decoded: each one only signals that the
+/// interfaces should be inspected again.
+///
+/// # Returns
+/// - `Ok(UnboundedReceiver<()>)`: A receiver getting a message for every notification.
+///   It is closed if the netlink connection terminates.
+/// - `Err(Error)`: If the netlink socket cannot be opened or bound.
+pub fn subscribe() -> Result<mpsc::UnboundedReceiver<()>, Error> {
+    let (mut connection, _handle, mut messages) = rtnetlink::new_connection()
+        .map_err(make_error_mapper(ErrorKind::ErrorMonitoringInterfaces))?;
+
+    let groups = RTMGRP_LINK
+        | RTMGRP_IPV4_IFADDR
+        | RTMGRP_IPV6_IFADDR
+        | RTMGRP_IPV4_ROUTE
+        | RTMGRP_IPV6_ROUTE;
+
+    connection
+        .socket_mut()
+        .socket_mut()
+        .bind(&SocketAddr::new(0, groups))
+        .map_err(make_error_mapper(ErrorKind::ErrorMonitoringInterfaces))?;
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    // The connection is dropped once the receiver is
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = connection => {}
+            _ = sender.closed() => {}
+            _ = async {
+                while messages.next().await.is_some() {
+                    if sender.send(()).is_err() {
+                        break;
+                    }
+                }
+            } => {}
+        }
+    });
+
+    Ok(receiver)
+}
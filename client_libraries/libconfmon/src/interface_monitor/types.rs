@@ -0,0 +1,36 @@
+use std::net::IpAddr;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+/// A change of the network interfaces detected by an `InterfaceMonitor`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterfaceChange {
+    /// A new interface appeared. Holds the name of the interface.
+    Added(String),
+    /// An interface disappeared. Holds the name of the interface.
+    Removed(String),
+    /// An interface went up.
+    LinkUp(String),
+    /// An interface went down (e.g. a cable was pulled).
+    LinkDown(String),
+    /// Other link attributes of an interface changed (MTU, speed, MAC address,
+    /// VLAN, bond or bridge membership).
+    LinkChanged(String),
+    /// An address was assigned to an interface.
+    AddressAdded { interface: String, address: IpAddr },
+    /// An address was removed from an interface.
+    AddressRemoved { interface: String, address: IpAddr },
+    /// The routes or default gateways of an interface changed.
+    RoutesChanged(String),
+}
+
+/// A stream of `InterfaceChange`s, obtained with `InterfaceMonitor::subscribe`.
+pub type InterfaceChangeStream = UnboundedReceiverStream<InterfaceChange>;
+
+/// How an `InterfaceMonitor` learns about changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MonitorMode {
+    /// Notifications from the kernel through rtnetlink (Linux only).
+    Netlink,
+    /// Periodic comparison of `InterfaceSnapshot::take_all` results.
+    Polling,
+}
@@ -0,0 +1,162 @@
+use super::types::InterfaceChange;
+use crate::InterfaceSnapshot;
+use std::collections::HashMap;
+
+/// Computes the changes between two lists of interfaces.
+///
+/// Traffic counters are ignored, since they change all the time.
+///
+/// # Parameters
+/// - `old`: The previously known interfaces.
+/// - `new`: The current interfaces.
+///
+/// # Returns
+/// The changes, grouped by interface and sorted by interface name.
+pub fn diff_interfaces(
+    old: &[InterfaceSnapshot],
+    new: &[InterfaceSnapshot],
+) -> Vec<InterfaceChange> {
+    let old: HashMap<&str, &InterfaceSnapshot> = old
+        .iter()
+        .map(|iface| (iface.name.as_str(), iface))
+        .collect();
+    let new: HashMap<&str, &InterfaceSnapshot> = new
+        .iter()
+        .map(|iface| (iface.name.as_str(), iface))
+        .collect();
+
+    let mut names: Vec<&str> = old.keys().chain(new.keys()).copied().collect();
+    names.sort_unstable();
+    names.dedup();
+
+    let mut changes = Vec::new();
+
+    for name in names {
+        let (before, after) = match (old.get(name), new.get(name)) {
+            (None, Some(_)) => {
+                changes.push(InterfaceChange::Added(name.to_string()));
+                continue;
+            }
+            (Some(_), None) => {
+                changes.push(InterfaceChange::Removed(name.to_string()));
+                continue;
+            }
+            (Some(before), Some(after)) => (before, after),
+            (None, None) => continue,
+        };
+
+        let was_up = before.is_up && before.oper_state.as_deref().is_none_or(|s| s != "down");
+        let is_up = after.is_up && after.oper_state.as_deref().is_none_or(|s| s != "down");
+
+        match (was_up, is_up) {
+            (false, true) => changes.push(InterfaceChange::LinkUp(name.to_string())),
+            (true, false) => changes.push(InterfaceChange::LinkDown(name.to_string())),
+            _ => {}
+        }
+
+        if before.mtu != after.mtu
+            || before.speed_mbps != after.speed_mbps
+            || before.duplex != after.duplex
+            || before.mac_address != after.mac_address
+            || before.vlan != after.vlan
+            || before.bond_master != after.bond_master
+            || before.bridge_master != after.bridge_master
+            || before.bond_members != after.bond_members
+            || before.bridge_members != after.bridge_members
+        {
+            changes.push(InterfaceChange::LinkChanged(name.to_string()));
+        }
+
+        for address in &after.ip_addresses {
+            if !before.ip_addresses.contains(address) {
+                changes.push(InterfaceChange::AddressAdded {
+                    interface: name.to_string(),
+                    address: *address,
+                });
+            }
+        }
+
+        for address in &before.ip_addresses {
+            if !after.ip_addresses.contains(address) {
+                changes.push(InterfaceChange::AddressRemoved {
+                    interface: name.to_string(),
+                    address: *address,
+                });
+            }
+        }
+
+        if before.routes != after.routes
+            || before.gateway != after.gateway
+            || before.gateway_v6 != after.gateway_v6
+        {
+            changes.push(InterfaceChange::RoutesChanged(name.to_string()));
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff_interfaces;
+    use crate::{InterfaceChange, InterfaceCounters, InterfaceSnapshot};
+
+    fn iface(name: &str, is_up: bool, addresses: &[&str]) -> InterfaceSnapshot {
+        InterfaceSnapshot {
+            name: name.to_string(),
+            is_up,
+            ip_addresses: addresses.iter().map(|a| a.parse().unwrap()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_interfaces() {
+        let old = vec![
+            iface("eth0", true, &["192.168.1.10"]),
+            iface("eth1", true, &[]),
+            iface("eth2", true, &[]),
+        ];
+
+        let mut eth2 = iface("eth2", true, &[]);
+        eth2.gateway = Some("10.0.0.1".parse().unwrap());
+
+        let new = vec![
+            iface("eth0", true, &["192.168.1.11"]),
+            iface("eth1", false, &[]),
+            eth2,
+            iface("eth0.10", true, &[]),
+        ];
+
+        assert_eq!(
+            diff_interfaces(&old, &new),
+            vec![
+                InterfaceChange::AddressAdded {
+                    interface: "eth0".to_string(),
+                    address: "192.168.1.11".parse().unwrap(),
+                },
+                InterfaceChange::AddressRemoved {
+                    interface: "eth0".to_string(),
+                    address: "192.168.1.10".parse().unwrap(),
+                },
+                InterfaceChange::Added("eth0.10".to_string()),
+                InterfaceChange::LinkDown("eth1".to_string()),
+                InterfaceChange::RoutesChanged("eth2".to_string()),
+            ]
+        );
+
+        assert!(diff_interfaces(&new, &[]).contains(&InterfaceChange::Removed("eth1".to_string())));
+    }
+
+    #[test]
+    fn test_diff_interfaces_ignores_counters() {
+        let old = vec![iface("eth0", true, &[])];
+        let mut new = vec![iface("eth0", true, &[])];
+        new[0].counters = Some(InterfaceCounters {
+            rx_bytes: 1000,
+            ..Default::default()
+        });
+
+        assert!(diff_interfaces(&old, &new).is_empty());
+    }
+}
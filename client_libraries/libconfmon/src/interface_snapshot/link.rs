@@ -0,0 +1,208 @@
+use super::InterfaceSnapshot;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Root of the network devices tree in sysfs.
+pub(crate) const SYSFS_NET_PATH: &str = "/sys/class/net";
+/// Table of the configured VLAN devices.
+pub(crate) const PROC_VLAN_CONFIG_PATH: &str = "/proc/net/vlan/config";
+
+/// Traffic counters of a network interface.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct InterfaceCounters {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+}
+
+/// VLAN configuration of a network interface.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VlanInfo {
+    /// The name of the parent interface.
+    pub parent: String,
+    /// The VLAN identifier.
+    pub id: u16,
+}
+
+/// Fills the link details of an interface from sysfs.
+///
+/// Missing or unreadable attributes (for example the speed of a virtual interface) are left unset.
+///
+/// # Arguments
+/// - `entry`: The interface to fill.
+/// - `sysfs`: The root of the network devices tree (usually `/sys/class/net`).
+/// - `vlans`: The VLAN devices, as returned by `parse_vlan_config`.
+pub(crate) fn fill_link_details(
+    entry: &mut InterfaceSnapshot,
+    sysfs: &Path,
+    vlans: &HashMap<String, VlanInfo>,
+) {
+    let device = sysfs.join(&entry.name);
+
+    if !device.exists() {
+        return;
+    }
+
+    let read = |attribute: &str| {
+        std::fs::read_to_string(device.join(attribute))
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let read_number = |attribute: &str| read(attribute).and_then(|value| value.parse::<u64>().ok());
+
+    entry.mtu = read_number("mtu").and_then(|mtu| u32::try_from(mtu).ok());
+    entry.oper_state = read("operstate");
+    // Virtual interfaces and links that are down report `-1` or fail to read
+    entry.speed_mbps = read_number("speed").and_then(|speed| u32::try_from(speed).ok());
+    entry.duplex = read("duplex").filter(|duplex| duplex != "unknown");
+
+    entry.counters = read_number("statistics/rx_bytes").map(|rx_bytes| InterfaceCounters {
+        rx_bytes,
+        tx_bytes: read_number("statistics/tx_bytes").unwrap_or_default(),
+        rx_packets: read_number("statistics/rx_packets").unwrap_or_default(),
+        tx_packets: read_number("statistics/tx_packets").unwrap_or_default(),
+        rx_errors: read_number("statistics/rx_errors").unwrap_or_default(),
+        tx_errors: read_number("statistics/tx_errors").unwrap_or_default(),
+    });
+
+    entry.vlan = vlans.get(&entry.name).cloned();
+
+    if let Some(master) = std::fs::read_link(device.join("master"))
+        .ok()
+        .and_then(|link| {
+            link.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+    {
+        if sysfs.join(&master).join("bridge").exists() {
+            entry.bridge_master = Some(master);
+        } else {
+            entry.bond_master = Some(master);
+        }
+    }
+
+    entry.bond_members = read("bonding/slaves")
+        .map(|slaves| slaves.split_whitespace().map(String::from).collect())
+        .unwrap_or_default();
+
+    entry.bridge_members = std::fs::read_dir(device.join("brif"))
+        .map(|members| {
+            let mut members: Vec<String> = members
+                .filter_map(Result::ok)
+                .map(|member| member.file_name().to_string_lossy().into_owned())
+                .collect();
+            members.sort();
+            members
+        })
+        .unwrap_or_default();
+}
+
+/// Parses the content of `/proc/net/vlan/config`.
+///
+/// # Returns
+/// The VLAN devices, indexed by name.
+pub(crate) fn parse_vlan_config(content: &str) -> HashMap<String, VlanInfo> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+
+            match fields.as_slice() {
+                [name, id, parent] => Some((
+                    name.to_string(),
+                    VlanInfo {
+                        parent: parent.to_string(),
+                        id: id.parse().ok()?,
+                    },
+                )),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InterfaceCounters, VlanInfo, fill_link_details, parse_vlan_config};
+    use crate::InterfaceSnapshot;
+    use std::fs;
+
+    #[test]
+    fn test_parse_vlan_config() {
+        let content = "VLAN Dev name    | VLAN ID\n\
+            Name-Type: VLAN_NAME_TYPE_RAW_PLUS_VID_NO_PAD\n\
+            eth0.10        | 10  | eth0\n";
+
+        let vlans = parse_vlan_config(content);
+
+        assert_eq!(vlans.len(), 1);
+        assert_eq!(
+            vlans["eth0.10"],
+            VlanInfo {
+                parent: String::from("eth0"),
+                id: 10
+            }
+        );
+    }
+
+    #[test]
+    fn test_fill_link_details() {
+        let sysfs = std::env::temp_dir().join(format!("libconfmon-sysfs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&sysfs);
+
+        let eth0 = sysfs.join("eth0");
+        let br0 = sysfs.join("br0");
+        fs::create_dir_all(eth0.join("statistics")).unwrap();
+        fs::create_dir_all(br0.join("bridge")).unwrap();
+        fs::create_dir_all(br0.join("brif").join("eth0")).unwrap();
+
+        fs::write(eth0.join("mtu"), "1500\n").unwrap();
+        fs::write(eth0.join("operstate"), "up\n").unwrap();
+        fs::write(eth0.join("speed"), "1000\n").unwrap();
+        fs::write(eth0.join("duplex"), "full\n").unwrap();
+        fs::write(eth0.join("statistics").join("rx_bytes"), "100\n").unwrap();
+        fs::write(eth0.join("statistics").join("tx_bytes"), "200\n").unwrap();
+        fs::write(br0.join("speed"), "-1\n").unwrap();
+        std::os::unix::fs::symlink(&br0, eth0.join("master")).unwrap();
+
+        let vlans = parse_vlan_config("eth0.10 | 10 | eth0\n");
+
+        let mut eth0_entry = InterfaceSnapshot {
+            name: String::from("eth0"),
+            ..Default::default()
+        };
+        let mut br0_entry = InterfaceSnapshot {
+            name: String::from("br0"),
+            ..Default::default()
+        };
+
+        fill_link_details(&mut eth0_entry, &sysfs, &vlans);
+        fill_link_details(&mut br0_entry, &sysfs, &vlans);
+
+        assert_eq!(eth0_entry.mtu, Some(1500));
+        assert_eq!(eth0_entry.oper_state.as_deref(), Some("up"));
+        assert_eq!(eth0_entry.speed_mbps, Some(1000));
+        assert_eq!(eth0_entry.duplex.as_deref(), Some("full"));
+        assert_eq!(
+            eth0_entry.counters,
+            Some(InterfaceCounters {
+                rx_bytes: 100,
+                tx_bytes: 200,
+                ..Default::default()
+            })
+        );
+        assert_eq!(eth0_entry.bridge_master.as_deref(), Some("br0"));
+        assert_eq!(eth0_entry.bond_master, None);
+        assert_eq!(eth0_entry.vlan, None);
+
+        assert_eq!(br0_entry.speed_mbps, None);
+        assert_eq!(br0_entry.bridge_members, vec![String::from("eth0")]);
+
+        let _ = fs::remove_dir_all(&sysfs);
+    }
+}
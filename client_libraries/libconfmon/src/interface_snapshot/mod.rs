@@ -1,4 +1,8 @@
+mod link;
+mod routes;
 mod serde_ext;
 mod snapshot;
 
+pub use link::{InterfaceCounters, VlanInfo};
+pub use routes::Route;
 pub use snapshot::InterfaceSnapshot;
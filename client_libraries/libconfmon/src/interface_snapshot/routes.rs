@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// `RTF_UP`: the route is usable.
+const RTF_UP: u32 = 0x0001;
+/// `RTF_GATEWAY`: the destination is reached through a gateway.
+const RTF_GATEWAY: u32 = 0x0002;
+
+/// Represents an entry of the kernel routing table.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Route {
+    /// The destination network address.
+    pub destination: IpAddr,
+    /// The prefix length of the destination network (`0` for a default route).
+    pub prefix_length: u8,
+    /// The next hop, if the destination is not directly connected.
+    pub gateway: Option<IpAddr>,
+    /// The route metric.
+    pub metric: u32,
+}
+
+impl Route {
+    /// Returns `true` if this is a default route (`0.0.0.0/0` or `::/0`).
+    pub fn is_default(&self) -> bool {
+        self.prefix_length == 0 && self.destination.is_unspecified()
+    }
+}
+
+/// Reads the kernel routing table.
+///
+/// Only available on Linux, where the routes are read from `/proc/net/route`
+/// and `/proc/net/ipv6_route`.
+///
+/// # Returns
+/// - `Some(Vec<(String, Route)>)`: The usable routes, paired with the name of their interface.
+/// - `None`: If the routing table cannot be read on this system.
+pub(crate) fn read_routes() -> Option<Vec<(String, Route)>> {
+    let ipv4 = std::fs::read_to_string("/proc/net/route").ok();
+    let ipv6 = std::fs::read_to_string("/proc/net/ipv6_route").ok();
+
+    if ipv4.is_none() && ipv6.is_none() {
+        return None;
+    }
+
+    let mut routes = parse_ipv4_routes(&ipv4.unwrap_or_default());
+    routes.extend(parse_ipv6_routes(&ipv6.unwrap_or_default()));
+
+    Some(routes)
+}
+
+/// Selects the default gateway of an interface among the routes.
+///
+/// When several default routes exist, the one with the lowest metric is chosen.
+///
+/// # Arguments
+/// - `routes`: The routes of the interface.
+/// - `ipv6`: Whether to look for the IPv6 default gateway instead of the IPv4 one.
+pub(crate) fn default_gateway(routes: &[Route], ipv6: bool) -> Option<IpAddr> {
+    routes
+        .iter()
+        .filter(|route| route.is_default() && route.destination.is_ipv6() == ipv6)
+        .filter_map(|route| route.gateway.map(|gateway| (route.metric, gateway)))
+        .min_by_key(|(metric, _)| *metric)
+        .map(|(_, gateway)| gateway)
+}
+
+/// Parses the content of `/proc/net/route`.
+///
+/// Addresses are printed as hexadecimal numbers in host byte order.
+fn parse_ipv4_routes(content: &str) -> Vec<(String, Route)> {
+    let parse_addr = |value: &str| {
+        u32::from_str_radix(value, 16)
+            .ok()
+            .map(|value| Ipv4Addr::from(value.to_ne_bytes()))
+    };
+
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            if fields.len() < 8 {
+                return None;
+            }
+
+            let flags = u32::from_str_radix(fields[3], 16).ok()?;
+
+            if flags & RTF_UP == 0 {
+                return None;
+            }
+
+            let destination = parse_addr(fields[1])?;
+            let gateway = parse_addr(fields[2])?;
+            let mask = parse_addr(fields[7])?;
+
+            Some((
+                fields[0].to_string(),
+                Route {
+                    destination: IpAddr::V4(destination),
+                    prefix_length: u32::from(mask).count_ones() as u8,
+                    gateway: (flags & RTF_GATEWAY != 0).then_some(IpAddr::V4(gateway)),
+                    metric: fields[6].parse().ok()?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Parses the content of `/proc/net/ipv6_route`.
+///
+/// Routes of the loopback interface, which include the local and unreachable
+/// entries maintained by the kernel, are skipped.
+fn parse_ipv6_routes(content: &str) -> Vec<(String, Route)> {
+    let parse_addr = |value: &str| u128::from_str_radix(value, 16).ok().map(Ipv6Addr::from);
+
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            if fields.len() < 10 || fields[9] == "lo" {
+                return None;
+            }
+
+            let flags = u32::from_str_radix(fields[8], 16).ok()?;
+
+            if flags & RTF_UP == 0 {
+                return None;
+            }
+
+            let gateway = parse_addr(fields[4])?;
+
+            Some((
+                fields[9].to_string(),
+                Route {
+                    destination: IpAddr::V6(parse_addr(fields[0])?),
+                    prefix_length: u8::from_str_radix(fields[1], 16).ok()?,
+                    gateway: (!gateway.is_unspecified()).then_some(IpAddr::V6(gateway)),
+                    metric: u32::from_str_radix(fields[5], 16).ok()?,
+                },
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Route, default_gateway, parse_ipv4_routes, parse_ipv6_routes};
+
+    #[test]
+    fn test_parse_ipv4_routes() {
+        let content = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+            eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0\n\
+            eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0\n\
+            eth1\t0000000A\t00000000\t0000\t0\t0\t0\t000000FF\t0\t0\t0\n";
+
+        let routes = parse_ipv4_routes(content);
+
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].0, "eth0");
+        assert!(routes[0].1.is_default());
+        assert_eq!(routes[0].1.gateway, Some("192.168.1.1".parse().unwrap()));
+        assert_eq!(routes[0].1.metric, 100);
+        assert_eq!(
+            routes[1].1.destination,
+            "192.168.1.0".parse::<std::net::IpAddr>().unwrap()
+        );
+        assert_eq!(routes[1].1.prefix_length, 24);
+        assert_eq!(routes[1].1.gateway, None);
+    }
+
+    #[test]
+    fn test_parse_ipv6_routes() {
+        let content = "\
+fe800000000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001     eth0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000001 00000400 00000001 00000000 00000003     eth0
+00000000000000000000000000000001 80 00000000000000000000000000000000 00 00000000000000000000000000000000 00000000 00000002 00000000 80200001       lo
+";
+
+        let routes = parse_ipv6_routes(content);
+
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].1.prefix_length, 64);
+        assert_eq!(routes[0].1.gateway, None);
+        assert_eq!(routes[1].1.metric, 1024);
+
+        let eth0: Vec<Route> = routes.into_iter().map(|(_, route)| route).collect();
+        assert_eq!(
+            default_gateway(&eth0, true),
+            Some("fe80::1".parse().unwrap())
+        );
+        assert_eq!(default_gateway(&eth0, false), None);
+    }
+}
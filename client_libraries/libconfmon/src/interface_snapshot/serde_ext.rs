@@ -78,7 +78,6 @@ pub mod serde_ipaddr_vec {
 #[cfg(test)]
 mod tests {
     use super::{serde_ipaddr_option, serde_ipaddr_vec};
-    use bincode;
     use serde::{Deserialize, Serialize};
     use std::net::IpAddr;
 
@@ -1,13 +1,25 @@
+use super::link::{
+    InterfaceCounters, PROC_VLAN_CONFIG_PATH, SYSFS_NET_PATH, VlanInfo, fill_link_details,
+    parse_vlan_config,
+};
+use super::routes::{Route, default_gateway, read_routes};
 use super::serde_ext::*;
-use bincode::{Error, deserialize, serialize};
+use crate::encoding::{decode_payload, encode, read_header, types::Codec};
+use crate::watcher::utils::make_error_mapper;
+use crate::{Error, ErrorKind};
 use get_if_addrs::{IfAddr, get_if_addrs};
 use pnet::datalink;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
 
 /// Represents a snapshot of a network interface
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+///
+/// Fields missing from self-describing payloads (JSON, CBOR) take their default value,
+/// so new fields must be optional or have a meaningful default.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct InterfaceSnapshot {
     pub name: String,
     pub is_up: bool,
@@ -16,17 +28,116 @@ pub struct InterfaceSnapshot {
     pub is_broadcast: bool,
     pub mac_address: Option<String>,
     pub interface_index: Option<u32>,
-    #[serde(with = "serde_ipaddr_vec")]
     pub ip_addresses: Vec<IpAddr>,
+    /// Prefix length of each address, in the same order as `ip_addresses`
+    /// (`None` if unknown, e.g. in snapshots taken by older versions).
+    pub prefix_lengths: Vec<Option<u8>>,
+    /// IPv4 default gateway reached through this interface.
+    pub gateway: Option<IpAddr>,
+    /// IPv6 default gateway reached through this interface.
+    pub gateway_v6: Option<IpAddr>,
+    /// Routes going through this interface, if the routing table could be read.
+    pub routes: Option<Vec<Route>>,
+    /// Maximum transmission unit, in bytes.
+    pub mtu: Option<u32>,
+    /// Operational state as reported by the kernel (e.g. `up`, `down`, `lowerlayerdown`).
+    pub oper_state: Option<String>,
+    /// Link speed, in Mbit/s.
+    pub speed_mbps: Option<u32>,
+    /// Link duplex mode (`full` or `half`).
+    pub duplex: Option<String>,
+    /// Traffic counters.
+    pub counters: Option<InterfaceCounters>,
+    /// VLAN configuration, if this interface is a VLAN device.
+    pub vlan: Option<VlanInfo>,
+    /// The bond (LAGG) this interface is a member of.
+    pub bond_master: Option<String>,
+    /// The bridge this interface is a member of.
+    pub bridge_master: Option<String>,
+    /// Members of this interface, if it is a bond.
+    pub bond_members: Vec<String>,
+    /// Members of this interface, if it is a bridge.
+    pub bridge_members: Vec<String>,
+}
+
+/// Layout of `InterfaceSnapshot` before the versioned envelope was introduced,
+/// serialized with bincode and without header (schema version 1).
+#[derive(Serialize, Deserialize)]
+struct LegacyInterfaceSnapshot {
+    name: String,
+    is_up: bool,
+    is_loopback: bool,
+    is_multicast: bool,
+    is_broadcast: bool,
+    mac_address: Option<String>,
+    interface_index: Option<u32>,
+    #[serde(with = "serde_ipaddr_vec")]
+    ip_addresses: Vec<IpAddr>,
     #[serde(with = "serde_ipaddr_option")]
-    pub subnet_mask: Option<IpAddr>,
+    subnet_mask: Option<IpAddr>,
     #[serde(with = "serde_ipaddr_option")]
-    pub gateway: Option<IpAddr>,
+    gateway: Option<IpAddr>,
+}
+
+impl From<LegacyInterfaceSnapshot> for InterfaceSnapshot {
+    fn from(legacy: LegacyInterfaceSnapshot) -> Self {
+        // The legacy format held a single mask, shared by the IPv4 addresses
+        let prefix_length = legacy.subnet_mask.map(prefix_length_of);
+
+        InterfaceSnapshot {
+            prefix_lengths: legacy
+                .ip_addresses
+                .iter()
+                .map(|ip| prefix_length.filter(|_| ip.is_ipv4()))
+                .collect(),
+            name: legacy.name,
+            is_up: legacy.is_up,
+            is_loopback: legacy.is_loopback,
+            is_multicast: legacy.is_multicast,
+            is_broadcast: legacy.is_broadcast,
+            mac_address: legacy.mac_address,
+            interface_index: legacy.interface_index,
+            ip_addresses: legacy.ip_addresses,
+            gateway: legacy.gateway,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&InterfaceSnapshot> for LegacyInterfaceSnapshot {
+    fn from(snapshot: &InterfaceSnapshot) -> Self {
+        // The legacy format only holds a single mask: keep the one of the first IPv4 address
+        let subnet_mask = snapshot
+            .ip_addresses
+            .iter()
+            .zip(&snapshot.prefix_lengths)
+            .find_map(|(ip, prefix_length)| prefix_length.filter(|_| ip.is_ipv4()))
+            .map(|prefix_length| IpAddr::V4(Ipv4Addr::from(mask_of(prefix_length))));
+
+        LegacyInterfaceSnapshot {
+            name: snapshot.name.clone(),
+            is_up: snapshot.is_up,
+            is_loopback: snapshot.is_loopback,
+            is_multicast: snapshot.is_multicast,
+            is_broadcast: snapshot.is_broadcast,
+            mac_address: snapshot.mac_address.clone(),
+            interface_index: snapshot.interface_index,
+            ip_addresses: snapshot.ip_addresses.clone(),
+            subnet_mask,
+            gateway: snapshot.gateway,
+        }
+    }
 }
 
 impl InterfaceSnapshot {
+    /// Version of the schema of the serialized snapshots.
+    pub const SCHEMA_VERSION: u16 = 2;
+
     /// Serializes a vector of `InterfaceSnapshot` objects into a binary format (`Vec<u8>`).
     ///
+    /// The data is wrapped into a versioned envelope and encoded with bincode. Servers that
+    /// predate the envelope cannot read it: use `serialize_legacy_snapshot` until they are upgraded.
+    ///
     /// # Arguments
     /// - `snapshot`: A reference to a vector of `InterfaceSnapshot` instances.
     ///
@@ -34,11 +145,52 @@ impl InterfaceSnapshot {
     /// - `Ok(Vec<u8>)`: Serialized binary data.
     /// - `Err(Error)`: If serialization fails.
     pub fn serialize_snapshot(snapshot: &Vec<InterfaceSnapshot>) -> Result<Vec<u8>, Error> {
-        serialize(snapshot)
+        InterfaceSnapshot::serialize_snapshot_with(snapshot, Codec::Bincode)
+    }
+
+    /// Serializes a vector of `InterfaceSnapshot` objects into the legacy format, without envelope.
+    ///
+    /// Meant for servers that predate the versioned envelope and cannot read the output of
+    /// `serialize_snapshot`. The legacy format holds a single IPv4 subnet mask per interface
+    /// and no routes or link details, so those are dropped.
+    ///
+    /// # Arguments
+    /// - `snapshot`: A reference to a vector of `InterfaceSnapshot` instances.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<u8>)`: Serialized binary data.
+    /// - `Err(Error)`: If serialization fails.
+    pub fn serialize_legacy_snapshot(snapshot: &[InterfaceSnapshot]) -> Result<Vec<u8>, Error> {
+        let legacy: Vec<LegacyInterfaceSnapshot> =
+            snapshot.iter().map(LegacyInterfaceSnapshot::from).collect();
+        bincode::serialize(&legacy).map_err(make_error_mapper(ErrorKind::ErrorEncoding))
+    }
+
+    /// Serializes a vector of `InterfaceSnapshot` objects with the given codec.
+    ///
+    /// Self-describing codecs (JSON, CBOR) can be read by peers using another schema
+    /// version, so agents and servers can be upgraded independently.
+    ///
+    /// # Arguments
+    /// - `snapshot`: A reference to a vector of `InterfaceSnapshot` instances.
+    /// - `codec`: The codec to use.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<u8>)`: Serialized data, starting with the envelope header.
+    /// - `Err(Error)`: If serialization fails.
+    pub fn serialize_snapshot_with(
+        snapshot: &Vec<InterfaceSnapshot>,
+        codec: Codec,
+    ) -> Result<Vec<u8>, Error> {
+        encode(snapshot, InterfaceSnapshot::SCHEMA_VERSION, codec)
     }
 
     /// Deserializes binary data (`Vec<u8>`) back into a vector of `InterfaceSnapshot` objects.
     ///
+    /// Accepts data in any codec, as well as the legacy format emitted by older versions of the
+    /// library, which lacks the envelope. Data using another schema version is accepted
+    /// only if it was encoded with a self-describing codec.
+    ///
     /// # Arguments
     /// - `data`: A byte slice containing serialized `InterfaceSnapshot` data.
     ///
@@ -46,13 +198,33 @@ impl InterfaceSnapshot {
     /// - `Ok(Vec<InterfaceSnapshot>)`: The deserialized vector of network interface snapshots.
     /// - `Err(Error)`: If deserialization fails.
     pub fn deserialize_snapshot(data: &[u8]) -> Result<Vec<InterfaceSnapshot>, Error> {
-        deserialize(data)
+        let Some((header, payload)) = read_header(data) else {
+            let legacy: Vec<LegacyInterfaceSnapshot> = decode_payload(Codec::Bincode, data)?;
+            return Ok(legacy.into_iter().map(InterfaceSnapshot::from).collect());
+        };
+
+        if header.schema_version != InterfaceSnapshot::SCHEMA_VERSION
+            && !header.codec.is_self_describing()
+        {
+            return Err(Error {
+                kind: ErrorKind::ErrorDecoding,
+                message: format!(
+                    "Unsupported interface snapshot schema version {} for codec {:?}",
+                    header.schema_version, header.codec
+                ),
+            });
+        }
+
+        decode_payload(header.codec, payload)
     }
 
     /// Captures the current state of all network interfaces available on the system.
     ///
     /// - Retrieves interface names, statuses, MAC addresses, and other properties using `pnet::datalink`.
-    /// - Fetches assigned IP addresses and subnet masks using `get_if_addrs`.
+    /// - Fetches assigned IP addresses and their prefix lengths using `get_if_addrs`.
+    /// - Reads the routes and default gateways from the kernel routing table, where available.
+    /// - Reads the MTU, link state, counters and VLAN, bond and bridge relationships from sysfs,
+    ///   where available (Linux only).
     ///
     /// # Returns
     /// - A `Vec<InterfaceSnapshot>` containing details of all detected network interfaces.
@@ -71,9 +243,7 @@ impl InterfaceSnapshot {
                     is_broadcast: iface.is_broadcast(),
                     mac_address: iface.mac.as_ref().map(|mac| mac.to_string()),
                     interface_index: Some(iface.index),
-                    ip_addresses: Vec::new(),
-                    subnet_mask: None,
-                    gateway: None,
+                    ..Default::default()
                 },
             );
         }
@@ -84,24 +254,75 @@ impl InterfaceSnapshot {
                     match iface.addr {
                         IfAddr::V4(ipv4) => {
                             entry.ip_addresses.push(IpAddr::V4(ipv4.ip));
-                            entry.subnet_mask = Some(IpAddr::V4(ipv4.netmask));
+                            entry
+                                .prefix_lengths
+                                .push(Some(prefix_length_of(IpAddr::V4(ipv4.netmask))));
                         }
                         IfAddr::V6(ipv6) => {
                             entry.ip_addresses.push(IpAddr::V6(ipv6.ip));
+                            entry
+                                .prefix_lengths
+                                .push(Some(prefix_length_of(IpAddr::V6(ipv6.netmask))));
                         }
                     }
                 }
             }
         }
 
+        if let Some(routes) = read_routes() {
+            for entry in iface_map.values_mut() {
+                entry.routes = Some(Vec::new());
+            }
+
+            for (name, route) in routes {
+                if let Some(entry) = iface_map.get_mut(&name)
+                    && let Some(entry_routes) = &mut entry.routes
+                {
+                    entry_routes.push(route);
+                }
+            }
+
+            for entry in iface_map.values_mut() {
+                let routes = entry.routes.as_deref().unwrap_or_default();
+                entry.gateway = default_gateway(routes, false);
+                entry.gateway_v6 = default_gateway(routes, true);
+            }
+        }
+
+        if Path::new(SYSFS_NET_PATH).exists() {
+            let vlans = std::fs::read_to_string(PROC_VLAN_CONFIG_PATH)
+                .map(|content| parse_vlan_config(&content))
+                .unwrap_or_default();
+
+            for entry in iface_map.values_mut() {
+                fill_link_details(entry, Path::new(SYSFS_NET_PATH), &vlans);
+            }
+        }
+
         iface_map.into_values().collect()
     }
 }
 
+/// Returns the IPv4 subnet mask with the given prefix length.
+fn mask_of(prefix_length: u8) -> u32 {
+    u32::MAX
+        .checked_shl(32 - u32::from(prefix_length.min(32)))
+        .unwrap_or(0)
+}
+
+/// Converts a network mask into a prefix length.
+fn prefix_length_of(mask: IpAddr) -> u8 {
+    match mask {
+        IpAddr::V4(mask) => u32::from(mask).count_ones() as u8,
+        IpAddr::V6(mask) => u128::from(mask).count_ones() as u8,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::InterfaceSnapshot;
-    use bincode;
+    use super::{InterfaceSnapshot, LegacyInterfaceSnapshot};
+    use crate::Codec;
+    use crate::interface_snapshot::{InterfaceCounters, Route, VlanInfo};
 
     #[test]
     fn test_interface_snapshot_serialize_deserialize() {
@@ -117,8 +338,35 @@ mod tests {
                 "192.168.1.100".parse().unwrap(),
                 "10.0.0.1".parse().unwrap(),
             ],
-            subnet_mask: Some("255.255.255.0".parse().unwrap()),
+            prefix_lengths: vec![Some(24), Some(8)],
             gateway: Some("192.168.1.1".parse().unwrap()),
+            gateway_v6: Some("fe80::1".parse().unwrap()),
+            routes: Some(vec![Route {
+                destination: "0.0.0.0".parse().unwrap(),
+                prefix_length: 0,
+                gateway: Some("192.168.1.1".parse().unwrap()),
+                metric: 100,
+            }]),
+            mtu: Some(1500),
+            oper_state: Some("up".to_string()),
+            speed_mbps: Some(1000),
+            duplex: Some("full".to_string()),
+            counters: Some(InterfaceCounters {
+                rx_bytes: 1024,
+                tx_bytes: 2048,
+                rx_packets: 10,
+                tx_packets: 20,
+                rx_errors: 0,
+                tx_errors: 1,
+            }),
+            vlan: Some(VlanInfo {
+                parent: "eth1".to_string(),
+                id: 10,
+            }),
+            bond_master: None,
+            bridge_master: Some("br0".to_string()),
+            bond_members: vec![],
+            bridge_members: vec![],
         }];
 
         let serialized = InterfaceSnapshot::serialize_snapshot(&snapshot).unwrap();
@@ -165,16 +413,16 @@ mod tests {
             mac_address: Some("00:1A:2B:3C:4D:5E".to_string()),
             interface_index: Some(1),
             ip_addresses: vec!["192.168.1.100".parse().unwrap()],
-            subnet_mask: None,
             gateway: Some("192.168.1.1".parse().unwrap()),
+            ..Default::default()
         };
 
         let serialized = bincode::serialize(&snapshot).unwrap();
         let deserialized: InterfaceSnapshot = bincode::deserialize(&serialized).unwrap();
 
         assert_eq!(
-            deserialized.subnet_mask, None,
-            "Subnet mask should remain `None` after deserialization"
+            deserialized.gateway_v6, None,
+            "IPv6 gateway should remain `None` after deserialization"
         );
         assert_eq!(
             deserialized.gateway,
@@ -198,8 +446,8 @@ mod tests {
                 "192.168.1.100".parse().unwrap(),
                 "10.0.0.1".parse().unwrap(),
             ],
-            subnet_mask: Some("255.255.255.0".parse().unwrap()),
-            gateway: None,
+            prefix_lengths: vec![Some(24), Some(8)],
+            ..Default::default()
         };
 
         let serialized = bincode::serialize(&snapshot).unwrap();
@@ -215,4 +463,87 @@ mod tests {
             "IP addresses should match after deserialization"
         );
     }
+
+    /// Test that snapshots serialized by older versions can still be read.
+    #[test]
+    fn test_deserialize_legacy_format() {
+        let legacy = vec![LegacyInterfaceSnapshot {
+            name: "em0".to_string(),
+            is_up: true,
+            is_loopback: false,
+            is_multicast: true,
+            is_broadcast: true,
+            mac_address: None,
+            interface_index: Some(1),
+            ip_addresses: vec!["10.0.0.1".parse().unwrap(), "fe80::1".parse().unwrap()],
+            subnet_mask: Some("255.255.0.0".parse().unwrap()),
+            gateway: Some("10.0.0.254".parse().unwrap()),
+        }];
+
+        let serialized = bincode::serialize(&legacy).unwrap();
+        let deserialized = InterfaceSnapshot::deserialize_snapshot(&serialized).unwrap();
+
+        assert_eq!(deserialized.len(), 1);
+        assert_eq!(deserialized[0].name, "em0");
+        assert_eq!(deserialized[0].prefix_lengths, vec![Some(16), None]);
+        assert_eq!(deserialized[0].gateway, Some("10.0.0.254".parse().unwrap()));
+        assert_eq!(deserialized[0].routes, None);
+    }
+
+    /// Test that every codec round-trips and that self-describing payloads written
+    /// with another schema version can be read.
+    #[test]
+    fn test_codecs_and_schema_evolution() {
+        let snapshot = vec![InterfaceSnapshot {
+            name: "eth0".to_string(),
+            is_up: true,
+            ip_addresses: vec!["192.168.1.100".parse().unwrap()],
+            prefix_lengths: vec![Some(24)],
+            gateway: Some("192.168.1.1".parse().unwrap()),
+            ..Default::default()
+        }];
+
+        for codec in [Codec::Bincode, Codec::Json, Codec::Cbor] {
+            let serialized = InterfaceSnapshot::serialize_snapshot_with(&snapshot, codec).unwrap();
+            let deserialized = InterfaceSnapshot::deserialize_snapshot(&serialized).unwrap();
+            assert_eq!(deserialized, snapshot);
+        }
+
+        // A newer agent adding a field, and omitting others
+        let newer = br#"[{"name":"eth0","is_up":true,"ip_addresses":["192.168.1.100"],"prefix_lengths":[24],"gateway":"192.168.1.1","future_field":42}]"#;
+        let data = [b"NNCM\x00\x03\x01".as_slice(), newer].concat();
+        assert_eq!(
+            InterfaceSnapshot::deserialize_snapshot(&data).unwrap(),
+            snapshot
+        );
+
+        // Bincode payloads with an unknown schema version are rejected
+        let mut data = InterfaceSnapshot::serialize_snapshot(&snapshot).unwrap();
+        data[5] = 3;
+        assert!(InterfaceSnapshot::deserialize_snapshot(&data).is_err());
+    }
+
+    /// Test that the legacy serialization can be read back, keeping what the format can hold.
+    #[test]
+    fn test_serialize_legacy_format() {
+        let snapshot = vec![InterfaceSnapshot {
+            name: "em0".to_string(),
+            is_up: true,
+            ip_addresses: vec!["fe80::1".parse().unwrap(), "10.0.0.1".parse().unwrap()],
+            prefix_lengths: vec![Some(64), Some(16)],
+            gateway: Some("10.0.0.254".parse().unwrap()),
+            mtu: Some(1500),
+            ..Default::default()
+        }];
+
+        let serialized = InterfaceSnapshot::serialize_legacy_snapshot(&snapshot).unwrap();
+        let legacy: Vec<LegacyInterfaceSnapshot> = bincode::deserialize(&serialized).unwrap();
+        assert_eq!(legacy[0].subnet_mask, Some("255.255.0.0".parse().unwrap()));
+
+        let deserialized = InterfaceSnapshot::deserialize_snapshot(&serialized).unwrap();
+        assert_eq!(deserialized[0].name, "em0");
+        assert_eq!(deserialized[0].prefix_lengths, vec![None, Some(16)]);
+        assert_eq!(deserialized[0].gateway, Some("10.0.0.254".parse().unwrap()));
+        assert_eq!(deserialized[0].mtu, None);
+    }
 }
@@ -1,22 +1,57 @@
 pub use detector::{Detector, State};
+pub use encoding::{
+    decode, encode, read_header,
+    types::{Codec, ENVELOPE_MAGIC, EnvelopeHeader},
+};
 pub use error::{Error, ErrorKind};
-pub use interface_snapshot::InterfaceSnapshot;
-pub use platform::Platform;
+pub use history::{
+    r#impl::SnapshotHistory,
+    types::{HistoryEntry, HistoryFile},
+};
+pub use interface_monitor::{
+    r#impl::InterfaceMonitor,
+    types::{InterfaceChange, InterfaceChangeStream, MonitorMode},
+    utils::diff_interfaces,
+};
+pub use interface_snapshot::{InterfaceCounters, InterfaceSnapshot, Route, VlanInfo};
+pub use platform::{Platform, PlatformInfo};
+pub use revision::{Revision, read_backups};
+pub use snapshot_diff::types::{FileChangeKind, FileDiff, SnapshotDiff, XmlChange, XmlChangeKind};
+pub use synthesized::{
+    HOSTNAME_ENTRY, NETWORK_INTERFACES_ENTRY, PLATFORM_VERSION_ENTRY, REVISION_ENTRY, STATE_ENTRY,
+    SynthesizedEntries,
+};
+#[cfg(any(test, feature = "testing"))]
+pub use testing::FakePlatform;
 pub use watcher::{
+    dispatch::{DispatchPolicy, PendingDispatch},
+    dynamic::{DynWatcherHandler, HandlerFuture},
+    events::{NoopHandler, WatchEvent, WatchEventStream},
+    fanout::FanoutHandler,
+    handle::{WatchTrigger, WatcherHandle},
     r#impl::{Watcher, WatcherHandler},
-    types::{FileData, Snapshot},
+    types::{FileData, Snapshot, snapshot_digest},
 };
 
 mod detector;
+mod encoding;
 mod error;
+mod history;
+mod interface_monitor;
 mod interface_snapshot;
 mod platform;
+mod revision;
+mod snapshot_diff;
+mod synthesized;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 mod watcher;
 
 /// Creates and initializes a new `Watcher` to monitor file changes on a specified platform.
 ///
 /// # Parameters
-/// - `platform`: A string representing the target platform for the watcher (e.g., `"pfsense"` or `"opnsense"`).
+/// - `platform`: A string representing the target platform for the watcher (e.g., `"pfsense"`, `"opnsense"` or `"linux"`),
+///   or `"auto"` to detect it with `Platform::detect`.
 /// - `poll_interval`: The polling interval in milliseconds to check for file changes.
 /// - `handler`: A user-defined function or closure that gets executed when a change is detected.
 ///   This function must implement the `WatcherHandler` trait.
@@ -26,7 +61,8 @@ mod watcher;
 /// - `Err(Error)`: Returns an error if initialization fails.
 ///
 /// # Errors
-/// - Returns `ErrorKind::ErrorUnsupportedPlatform` if the specified platform is not recognized.
+/// - Returns `ErrorKind::ErrorUnsupportedPlatform` if the specified platform is not recognized,
+///   or cannot be detected.
 /// - Returns `ErrorKind::ErrorInitializingWatcher` if the watcher fails to initialize.
 pub async fn make_watcher<T>(
     platform: &str,
@@ -36,8 +72,37 @@ pub async fn make_watcher<T>(
 where
     T: WatcherHandler,
 {
-    let pval = Platform::from_string(platform)?;
+    let pval = if platform.eq_ignore_ascii_case("auto") {
+        Platform::detect().await?.platform
+    } else {
+        Platform::from_string(platform)?
+    };
     let retval = Watcher::new(pval, poll_interval, handler).await?;
 
     Ok(retval)
 }
+
+/// Creates a `Watcher` for the specified platform, starts it in the background
+/// and returns the stream of its events.
+///
+/// # Parameters
+/// - `platform`: A string representing the target platform for the watcher (e.g., `"pfsense"`, `"opnsense"` or `"linux"`),
+///   or `"auto"` to detect it with `Platform::detect`.
+/// - `poll_interval`: The polling interval in milliseconds to check for file changes.
+///
+/// # Returns
+/// - `Ok((WatcherHandle, WatchEventStream))`: A handle to stop the watcher and the stream of its events.
+/// - `Err(Error)`: Returns an error if initialization fails.
+///
+/// # Errors
+/// - Returns `ErrorKind::ErrorUnsupportedPlatform` if the specified platform is not recognized.
+/// - Returns `ErrorKind::ErrorInitializingWatcher` if the watcher fails to initialize.
+pub async fn make_watch_stream(
+    platform: &str,
+    poll_interval: u64,
+) -> Result<(WatcherHandle<NoopHandler>, WatchEventStream), Error> {
+    let mut watcher = make_watcher(platform, poll_interval, NoopHandler).await?;
+    let stream = watcher.subscribe();
+
+    Ok((watcher.spawn(), stream))
+}
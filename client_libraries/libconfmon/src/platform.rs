@@ -1,10 +1,72 @@
 use crate::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Path of the configuration file on pfSense and OPNsense hosts.
+pub(crate) const CONFIG_XML_PATH: &str = "/conf/config.xml";
+/// Path of the directory holding the configuration backups on pfSense and OPNsense hosts.
+pub(crate) const CONFIG_BACKUP_PATH: &str = "/conf/backup";
+/// Path of the directory holding the `.dirty` markers of pending changes on pfSense hosts.
+pub(crate) const PFSENSE_DIRTY_MARKERS_PATH: &str = "/var/run/";
+/// Path of the directory holding the `.dirty` markers of pending changes on OPNsense hosts.
+pub(crate) const OPNSENSE_DIRTY_MARKERS_PATH: &str = "/tmp/";
+/// Path of the persistent nftables ruleset on Linux hosts.
+pub(crate) const NFTABLES_CONFIG_PATH: &str = "/etc/nftables.conf";
+/// Path of the persistent IPv4 iptables ruleset (as written by `iptables-save`).
+pub(crate) const IPTABLES_V4_RULES_PATH: &str = "/etc/iptables/rules.v4";
+/// Path of the persistent IPv6 iptables ruleset (as written by `ip6tables-save`).
+pub(crate) const IPTABLES_V6_RULES_PATH: &str = "/etc/iptables/rules.v6";
+
+/// Path of the file holding the pfSense version.
+pub(crate) const PFSENSE_VERSION_PATH: &str = "/etc/version";
+/// Path of the file describing the installed OPNsense core package.
+pub(crate) const OPNSENSE_VERSION_PATH: &str = "/usr/local/opnsense/version/core";
+/// Path of the operating system identification file on Linux hosts.
+pub(crate) const LINUX_OS_RELEASE_PATH: &str = "/etc/os-release";
+
+/// Path of the file holding the product name on pfSense hosts.
+const PFSENSE_PLATFORM_PATH: &str = "/etc/platform";
+/// Path of the directory describing the installed OPNsense packages.
+const OPNSENSE_VERSION_DIR_PATH: &str = "/usr/local/opnsense/version";
+/// Locations of the binaries of the firewalls supported on Linux hosts.
+const LINUX_FIREWALL_BINARY_PATHS: [&str; 6] = [
+    "/usr/sbin/nft",
+    "/sbin/nft",
+    "/usr/bin/nft",
+    "/usr/sbin/iptables-save",
+    "/sbin/iptables-save",
+    "/usr/bin/iptables-save",
+];
+
+/// Resolves an absolute path of the host inside a filesystem root.
+///
+/// # Parameters
+/// - `root`: The filesystem root (`/` on a real host).
+/// - `path`: An absolute path, such as `/conf/config.xml`.
+///
+/// # Returns
+/// The path of `path` below `root`.
+pub(crate) fn rooted(root: &Path, path: &str) -> PathBuf {
+    root.join(path.trim_start_matches('/'))
+}
 
 /// Represents the supported platforms.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Platform {
     PfSense,
     OPNsense,
+    Linux,
+}
+
+/// A platform found by `Platform::detect`.
+///
+/// # Fields
+/// - `platform`: The detected platform.
+/// - `version`: The version of the platform (e.g. `2.7.2-RELEASE`), if it can be determined.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlatformInfo {
+    pub platform: Platform,
+    pub version: Option<String>,
 }
 
 impl Platform {
@@ -23,10 +85,174 @@ impl Platform {
         match value.to_lowercase().as_str() {
             "pfsense" => Ok(Platform::PfSense),
             "opnsense" => Ok(Platform::OPNsense),
+            "linux" => Ok(Platform::Linux),
             _ => Err(Error {
                 kind: ErrorKind::ErrorUnsupportedPlatform,
                 message: format!("Unsupported platform: {value}"),
             }),
         }
     }
+
+    /// Detects the platform of the host.
+    ///
+    /// OPNsense is recognized by `/usr/local/opnsense/version`, pfSense by `/etc/platform`,
+    /// and Linux by `/etc/os-release` together with an nftables or iptables binary.
+    ///
+    /// # Returns
+    /// - `Ok(PlatformInfo)`: The detected platform and its version.
+    /// - `Err(Error)`: If the host is not a supported platform.
+    ///
+    /// # Errors
+    /// Returns an `Error` with the kind `ErrorUnsupportedPlatform` if no platform is recognized.
+    pub async fn detect() -> Result<PlatformInfo, Error> {
+        Platform::detect_with_root(Path::new("/")).await
+    }
+
+    /// Detects the platform of a host whose filesystem is found below a root.
+    ///
+    /// # Parameters
+    /// - `root`: The filesystem root (`/` on a real host), e.g. a fake tree built for tests.
+    ///
+    /// # Returns
+    /// The same as `detect`.
+    pub async fn detect_with_root(root: &Path) -> Result<PlatformInfo, Error> {
+        let exists = async |path: &str| fs::try_exists(rooted(root, path)).await.unwrap_or(false);
+
+        let platform = if exists(OPNSENSE_VERSION_DIR_PATH).await {
+            Platform::OPNsense
+        } else if fs::read_to_string(rooted(root, PFSENSE_PLATFORM_PATH))
+            .await
+            .is_ok_and(|content| content.trim().eq_ignore_ascii_case("pfsense"))
+        {
+            Platform::PfSense
+        } else if exists(LINUX_OS_RELEASE_PATH).await && has_linux_firewall(root).await {
+            Platform::Linux
+        } else {
+            return Err(Error {
+                kind: ErrorKind::ErrorUnsupportedPlatform,
+                message: format!(
+                    "Unable to detect a supported platform in {}",
+                    root.display()
+                ),
+            });
+        };
+
+        Ok(PlatformInfo {
+            platform,
+            version: read_platform_version(platform, root).await,
+        })
+    }
+}
+
+/// Checks whether the nftables or iptables binary is installed below a root.
+async fn has_linux_firewall(root: &Path) -> bool {
+    for path in LINUX_FIREWALL_BINARY_PATHS {
+        if fs::try_exists(rooted(root, path)).await.unwrap_or(false) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Reads the version of a platform whose filesystem is found below a root.
+pub(crate) async fn read_platform_version(platform: Platform, root: &Path) -> Option<String> {
+    let path = match platform {
+        Platform::PfSense => PFSENSE_VERSION_PATH,
+        Platform::OPNsense => OPNSENSE_VERSION_PATH,
+        Platform::Linux => LINUX_OS_RELEASE_PATH,
+    };
+
+    let content = fs::read_to_string(rooted(root, path)).await.ok()?;
+
+    let version = match platform {
+        Platform::PfSense => content,
+        Platform::OPNsense => parse_opnsense_version(&content)?,
+        Platform::Linux => parse_os_release(&content)?,
+    };
+
+    Some(version.trim().to_string()).filter(|version| !version.is_empty())
+}
+
+/// Extracts the product version from the content of `/usr/local/opnsense/version/core`.
+fn parse_opnsense_version(content: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(content).ok()?;
+    value["product_version"].as_str().map(String::from)
+}
+
+/// Extracts a readable version from the content of `/etc/os-release`,
+/// preferring `PRETTY_NAME` over `NAME` and `VERSION_ID`.
+fn parse_os_release(content: &str) -> Option<String> {
+    let field = |key: &str| {
+        content.lines().find_map(|line| {
+            let value = line.strip_prefix(key)?.strip_prefix('=')?;
+            Some(value.trim().trim_matches('"').to_string())
+        })
+    };
+
+    field("PRETTY_NAME").or_else(|| match (field("NAME"), field("VERSION_ID")) {
+        (Some(name), Some(version)) => Some(format!("{name} {version}")),
+        (name, version) => name.or(version),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Platform, PlatformInfo, parse_opnsense_version, parse_os_release};
+    use crate::FakePlatform;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_versions() {
+        assert_eq!(
+            parse_opnsense_version(r#"{"product_name":"OPNsense","product_version":"24.1.1"}"#),
+            Some(String::from("24.1.1"))
+        );
+        assert_eq!(
+            parse_os_release(
+                "NAME=\"Debian GNU/Linux\"\nPRETTY_NAME=\"Debian GNU/Linux 12 (bookworm)\"\n"
+            ),
+            Some(String::from("Debian GNU/Linux 12 (bookworm)"))
+        );
+        assert_eq!(
+            parse_os_release("NAME=Alpine\nVERSION_ID=3.19.1\n"),
+            Some(String::from("Alpine 3.19.1"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detect_fake_platforms() {
+        for (platform, version) in [
+            (Platform::PfSense, "2.7.2-RELEASE"),
+            (Platform::OPNsense, "24.1.1"),
+            (Platform::Linux, "Debian GNU/Linux 12 (bookworm)"),
+        ] {
+            let fake = FakePlatform::new(platform).unwrap();
+
+            assert_eq!(
+                Platform::detect_with_root(fake.root()).await.unwrap(),
+                PlatformInfo {
+                    platform,
+                    version: Some(String::from(version)),
+                }
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detect_unknown_platform() {
+        let root = std::env::temp_dir().join(format!("libconfmon-unknown-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("etc")).unwrap();
+        std::fs::write(root.join("etc").join("os-release"), "NAME=Alpine\n").unwrap();
+
+        // An os-release file alone is not enough without a supported firewall
+        assert!(Platform::detect_with_root(&root).await.is_err());
+        assert!(
+            Platform::detect_with_root(Path::new("/nonexistent"))
+                .await
+                .is_err()
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
 }
@@ -0,0 +1,264 @@
+use crate::{
+    Error, ErrorKind, FileData, REVISION_ENTRY, Snapshot, watcher::utils::make_error_mapper,
+};
+use roxmltree::Document;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::fs;
+
+/// Name of the configuration file on pfSense and OPNsense, as it appears in snapshots.
+const CONFIG_XML_FILENAME: &str = "config.xml";
+
+/// The attribution of a configuration change, as recorded by pfSense and OPNsense
+/// in the `<revision>` block of `config.xml`.
+///
+/// # Fields
+/// - `time`: The time of the change, in milliseconds since the UNIX epoch.
+/// - `username`: The user who made the change (e.g. `admin@192.168.1.10 (Local Database)`).
+/// - `description`: The description of the change (e.g. `Firewall: Rules - saved/edited a rule`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Revision {
+    pub time: u128,
+    pub username: String,
+    pub description: String,
+}
+
+impl Revision {
+    /// Extracts the attribution of the last change from the content of a `config.xml`.
+    ///
+    /// # Parameters
+    /// - `content`: The content of the configuration file.
+    ///
+    /// # Returns
+    /// The revision, or `None` if the file has no valid `<revision>` block.
+    pub fn from_config(content: &[u8]) -> Option<Revision> {
+        let content = std::str::from_utf8(content).ok()?;
+        let document = Document::parse(content).ok()?;
+
+        let revision = document
+            .root_element()
+            .children()
+            .find(|node| node.has_tag_name("revision"))?;
+
+        let field = |name: &str| {
+            revision
+                .children()
+                .find(|node| node.has_tag_name(name))
+                .and_then(|node| node.text())
+                .map(|text| text.trim().to_string())
+        };
+
+        // OPNsense records fractional seconds (e.g. `1700000000.1234`)
+        let seconds: f64 = field("time")?.parse().ok()?;
+
+        if !seconds.is_finite() || seconds < 0.0 {
+            return None;
+        }
+
+        Some(Revision {
+            time: (seconds * 1000.0) as u128,
+            username: field("username").unwrap_or_default(),
+            description: field("description").unwrap_or_default(),
+        })
+    }
+
+    /// Extracts the revision attached to a snapshot.
+    ///
+    /// The `#Revision` entry is used if present, otherwise the revision is
+    /// read from the `config.xml` file of the snapshot.
+    ///
+    /// # Parameters
+    /// - `snapshot`: The snapshot to inspect.
+    ///
+    /// # Returns
+    /// The revision, or `None` if the snapshot carries no attribution.
+    pub fn from_snapshot(snapshot: &[FileData]) -> Option<Revision> {
+        if let Some(entry) = snapshot.iter().find(|file| file.filename == REVISION_ENTRY) {
+            return serde_json::from_slice(&entry.content).ok();
+        }
+
+        snapshot
+            .iter()
+            .find(|file| file.filename == CONFIG_XML_FILENAME)
+            .and_then(|file| Revision::from_config(&file.content))
+    }
+
+    /// Builds the `#Revision` synthesized entry, holding the revision as JSON.
+    ///
+    /// # Returns
+    /// - `Ok(FileData)`: The entry.
+    /// - `Err(Error)`: If the revision cannot be serialized.
+    pub fn to_entry(&self) -> Result<FileData, Error> {
+        let content =
+            serde_json::to_vec(self).map_err(make_error_mapper(ErrorKind::ErrorEncoding))?;
+
+        Ok(FileData::new(REVISION_ENTRY, content, 0))
+    }
+}
+
+/// Reads the configuration backups kept by pfSense and OPNsense (`config-<timestamp>.xml`).
+///
+/// Every backup becomes a snapshot holding the backup as `config.xml`
+/// (with the backup timestamp as modification time) and its `#Revision` entry, if any.
+///
+/// # Parameters
+/// - `directory`: The directory holding the backups (`/conf/backup` on a real host).
+///
+/// # Returns
+/// - `Ok(Vec<Snapshot>)`: The backups, from the oldest to the newest.
+/// - `Err(Error)`: If the directory or a backup cannot be read.
+pub async fn read_backups(directory: &Path) -> Result<Vec<Snapshot>, Error> {
+    let mut entries = fs::read_dir(directory)
+        .await
+        .map_err(make_error_mapper(ErrorKind::ErrorReadingFile))?;
+
+    let mut backups = Vec::new();
+
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(make_error_mapper(ErrorKind::ErrorReadingFile))?
+    {
+        let name = entry.file_name();
+
+        if let Some(timestamp) = parse_backup_timestamp(&name.to_string_lossy()) {
+            backups.push((timestamp, entry.path()));
+        }
+    }
+
+    backups.sort();
+
+    let mut snapshots = Vec::with_capacity(backups.len());
+
+    for (timestamp, path) in backups {
+        let content = fs::read(&path)
+            .await
+            .map_err(make_error_mapper(ErrorKind::ErrorReadingFile))?;
+
+        let revision = Revision::from_config(&content);
+        let mut snapshot = vec![FileData::new(CONFIG_XML_FILENAME, content, timestamp)];
+
+        if let Some(revision) = revision {
+            snapshot.push(revision.to_entry()?);
+        }
+
+        snapshots.push(snapshot);
+    }
+
+    Ok(snapshots)
+}
+
+/// Extracts the timestamp (in milliseconds) from the name of a backup, e.g. `config-1700000000.xml`.
+///
+/// Some versions append a fractional part, e.g. `config-1700000000.1234.xml`.
+fn parse_backup_timestamp(filename: &str) -> Option<u128> {
+    let seconds: f64 = filename
+        .strip_prefix("config-")?
+        .strip_suffix(".xml")?
+        .parse()
+        .ok()?;
+
+    (seconds.is_finite() && seconds >= 0.0).then_some((seconds * 1000.0) as u128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Revision, parse_backup_timestamp, read_backups};
+    use crate::{FileData, REVISION_ENTRY};
+
+    const CONFIG: &str = r#"<?xml version="1.0"?>
+<pfsense>
+    <version>23.3</version>
+    <revision>
+        <time>1700000000</time>
+        <description><![CDATA[admin@10.0.0.5 (Local Database): Firewall: Rules - saved/edited a firewall rule.]]></description>
+        <username><![CDATA[admin@10.0.0.5 (Local Database)]]></username>
+    </revision>
+    <filter/>
+</pfsense>
+"#;
+
+    fn revision() -> Revision {
+        Revision {
+            time: 1_700_000_000_000,
+            username: String::from("admin@10.0.0.5 (Local Database)"),
+            description: String::from(
+                "admin@10.0.0.5 (Local Database): Firewall: Rules - saved/edited a firewall rule.",
+            ),
+        }
+    }
+
+    #[test]
+    fn test_revision_from_config() {
+        assert_eq!(Revision::from_config(CONFIG.as_bytes()), Some(revision()));
+
+        let opnsense = "<opnsense><revision><username>root@10.0.0.1</username>\
+            <time>1700000000.25</time><description>/firewall_rules_edit.php made changes</description>\
+            </revision></opnsense>";
+
+        assert_eq!(
+            Revision::from_config(opnsense.as_bytes()),
+            Some(Revision {
+                time: 1_700_000_000_250,
+                username: String::from("root@10.0.0.1"),
+                description: String::from("/firewall_rules_edit.php made changes"),
+            })
+        );
+
+        assert_eq!(Revision::from_config(b"<pfsense><system/></pfsense>"), None);
+        assert_eq!(Revision::from_config(b"not xml"), None);
+    }
+
+    #[test]
+    fn test_revision_from_snapshot() {
+        let config = FileData::new("config.xml", CONFIG.as_bytes().to_vec(), 1);
+        assert_eq!(
+            Revision::from_snapshot(std::slice::from_ref(&config)),
+            Some(revision())
+        );
+
+        // The synthesized entry takes precedence over the configuration file
+        let entry = Revision::default().to_entry().unwrap();
+        assert_eq!(entry.filename, REVISION_ENTRY);
+        assert_eq!(
+            Revision::from_snapshot(&[config, entry]),
+            Some(Revision::default())
+        );
+    }
+
+    #[test]
+    fn test_parse_backup_timestamp() {
+        assert_eq!(
+            parse_backup_timestamp("config-1700000000.xml"),
+            Some(1_700_000_000_000)
+        );
+        assert_eq!(
+            parse_backup_timestamp("config-1700000000.5.xml"),
+            Some(1_700_000_000_500)
+        );
+        assert_eq!(parse_backup_timestamp("config.xml"), None);
+        assert_eq!(parse_backup_timestamp("backup.cache"), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_backups() {
+        let dir = std::env::temp_dir().join(format!("libconfmon-backups-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        std::fs::write(dir.join("config-1700000100.xml"), "<pfsense/>").unwrap();
+        std::fs::write(dir.join("config-1700000000.xml"), CONFIG).unwrap();
+        std::fs::write(dir.join("backup.cache"), "ignored").unwrap();
+
+        let backups = read_backups(&dir).await.unwrap();
+
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0][0].filename, "config.xml");
+        assert_eq!(backups[0][0].mtime, 1_700_000_000_000);
+        assert_eq!(Revision::from_snapshot(&backups[0]), Some(revision()));
+        assert_eq!(backups[1].len(), 1);
+        assert_eq!(backups[1][0].mtime, 1_700_000_100_000);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,188 @@
+pub mod types;
+
+mod xml;
+
+use crate::Snapshot;
+use similar::TextDiff;
+use types::{FileChangeKind, FileDiff, SnapshotDiff};
+
+impl SnapshotDiff {
+    /// Compares two snapshots of the monitored files.
+    ///
+    /// Files are matched by name. For every file that was added, removed or modified:
+    /// - text files get a line-level unified diff;
+    /// - XML files (such as `config.xml`) additionally get a structural diff listing
+    ///   the element paths that were added, removed or changed. If it cannot be computed
+    ///   (e.g. a malformed document), `xml_error` tells why.
+    ///
+    /// # Parameters
+    /// - `old`: The previous snapshot.
+    /// - `new`: The current snapshot.
+    ///
+    /// # Returns
+    /// A `SnapshotDiff` listing the changed files, in the order they appear in the snapshots.
+    pub fn compute(old: &Snapshot, new: &Snapshot) -> SnapshotDiff {
+        let mut files = vec![];
+
+        for old_file in old {
+            match new.iter().find(|file| file.filename == old_file.filename) {
+                Some(new_file) if new_file.hash == old_file.hash => {}
+                Some(new_file) => files.push(diff_file(
+                    &old_file.filename,
+                    FileChangeKind::Modified,
+                    &old_file.content,
+                    &new_file.content,
+                )),
+                None => files.push(diff_file(
+                    &old_file.filename,
+                    FileChangeKind::Removed,
+                    &old_file.content,
+                    &[],
+                )),
+            }
+        }
+
+        for new_file in new {
+            if !old.iter().any(|file| file.filename == new_file.filename) {
+                files.push(diff_file(
+                    &new_file.filename,
+                    FileChangeKind::Added,
+                    &[],
+                    &new_file.content,
+                ));
+            }
+        }
+
+        SnapshotDiff { files }
+    }
+}
+
+/// Computes the textual and structural differences of a single file.
+fn diff_file(filename: &str, kind: FileChangeKind, old: &[u8], new: &[u8]) -> FileDiff {
+    let mut diff = FileDiff {
+        filename: filename.to_string(),
+        kind,
+        unified_diff: None,
+        xml_changes: vec![],
+        xml_error: None,
+    };
+
+    let (Some(old), Some(new)) = (as_text(old), as_text(new)) else {
+        return diff;
+    };
+
+    diff.unified_diff = Some(
+        TextDiff::from_lines(old, new)
+            .unified_diff()
+            .header(&format!("a/{filename}"), &format!("b/{filename}"))
+            .to_string(),
+    );
+
+    if kind == FileChangeKind::Modified && filename.ends_with(".xml") {
+        match xml::diff_xml(old, new) {
+            Ok(changes) => diff.xml_changes = changes,
+            Err(error) => diff.xml_error = Some(error),
+        }
+    }
+
+    diff
+}
+
+/// Interprets file content as text, returning `None` for binary data.
+fn as_text(content: &[u8]) -> Option<&str> {
+    if content.contains(&0) {
+        return None;
+    }
+
+    std::str::from_utf8(content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::types::{FileChangeKind, SnapshotDiff};
+    use crate::FileData;
+
+    #[test]
+    fn test_compute_snapshot_diff() {
+        let old = vec![
+            FileData::new(
+                "config.xml",
+                b"<pfsense>\n<system>\n<hostname>fw</hostname>\n</system>\n</pfsense>\n".to_vec(),
+                0,
+            ),
+            FileData::new("removed.txt", b"bye\n".to_vec(), 0),
+            FileData::new("same.txt", b"same\n".to_vec(), 0),
+        ];
+        let new = vec![
+            FileData::new(
+                "config.xml",
+                b"<pfsense>\n<system>\n<hostname>gw</hostname>\n</system>\n</pfsense>\n".to_vec(),
+                0,
+            ),
+            FileData::new("same.txt", b"same\n".to_vec(), 0),
+            FileData::new("#NetworkInterfaces", vec![0, 1, 2], 0),
+        ];
+
+        let diff = SnapshotDiff::compute(&old, &new);
+
+        assert_eq!(diff.files.len(), 3);
+
+        assert_eq!(diff.files[0].filename, "config.xml");
+        assert_eq!(diff.files[0].kind, FileChangeKind::Modified);
+        assert!(
+            diff.files[0]
+                .unified_diff
+                .as_ref()
+                .unwrap()
+                .contains("-<hostname>fw</hostname>\n+<hostname>gw</hostname>")
+        );
+        assert_eq!(diff.files[0].xml_changes.len(), 1);
+        assert_eq!(
+            diff.files[0].xml_changes[0].path,
+            "/pfsense/system/hostname"
+        );
+
+        assert_eq!(diff.files[1].filename, "removed.txt");
+        assert_eq!(diff.files[1].kind, FileChangeKind::Removed);
+
+        assert_eq!(diff.files[2].filename, "#NetworkInterfaces");
+        assert_eq!(diff.files[2].kind, FileChangeKind::Added);
+        assert_eq!(diff.files[2].unified_diff, None);
+
+        assert_eq!(
+            diff.summary(),
+            "config.xml: modified (1 changes)\n  ~ /pfsense/system/hostname: 'fw' -> 'gw'\nremoved.txt: removed\n#NetworkInterfaces: added\n"
+        );
+    }
+
+    #[test]
+    fn test_malformed_xml_flagged() {
+        let old = vec![FileData::new("config.xml", b"<pfsense/>\n".to_vec(), 0)];
+        let new = vec![FileData::new("config.xml", b"<pfsense>\n".to_vec(), 0)];
+
+        let diff = SnapshotDiff::compute(&old, &new);
+
+        assert_eq!(diff.files.len(), 1);
+        assert!(diff.files[0].unified_diff.is_some());
+        assert!(diff.files[0].xml_changes.is_empty());
+        assert!(
+            diff.files[0]
+                .xml_error
+                .as_ref()
+                .unwrap()
+                .starts_with("new document: ")
+        );
+        assert!(
+            diff.summary()
+                .starts_with("config.xml: modified (structural diff unavailable: new document: ")
+        );
+    }
+
+    #[test]
+    fn test_identical_snapshots() {
+        let old = vec![FileData::new("config.xml", b"<a/>".to_vec(), 1)];
+        let new = vec![FileData::new("config.xml", b"<a/>".to_vec(), 2)];
+
+        assert!(SnapshotDiff::compute(&old, &new).is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result};
+
+/// Describes how a file changed between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FileChangeKind {
+    /// The file is only present in the new snapshot.
+    Added,
+    /// The file is only present in the old snapshot.
+    Removed,
+    /// The file is present in both snapshots with different contents.
+    Modified,
+}
+
+/// Describes how an XML element or attribute changed between two documents.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum XmlChangeKind {
+    /// The element or attribute is only present in the new document.
+    Added,
+    /// The element or attribute is only present in the old document.
+    Removed,
+    /// The text of the element or the value of the attribute changed.
+    Changed,
+}
+
+/// A single structural change of an XML document.
+///
+/// # Fields
+/// - `path`: The path of the element (e.g. `/pfsense/system/hostname`) or attribute
+///   (e.g. `/pfsense/system/@version`). Elements with a stable id are addressed by it, either a
+///   `uuid` attribute (e.g. `/opnsense/filter/rule[@uuid='…']`) or a `<tracker>` child
+///   (e.g. `/pfsense/filter/rule[tracker='…']`). Other repeated sibling elements are addressed
+///   by their 1-based position among them, which is omitted for the first one.
+/// - `kind`: The kind of change.
+/// - `old_value`: The previous text or attribute value, if any.
+/// - `new_value`: The new text or attribute value, if any.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct XmlChange {
+    pub path: String,
+    pub kind: XmlChangeKind,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+/// The changes of a single file between two snapshots.
+///
+/// # Fields
+/// - `filename`: The name of the file.
+/// - `kind`: Whether the file was added, removed or modified.
+/// - `unified_diff`: A line-level unified diff, for text files only.
+/// - `xml_changes`: The structural changes, for XML files only.
+/// - `xml_error`: Why the structural changes could not be computed (e.g. a malformed document),
+///   in which case `xml_changes` is empty.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub filename: String,
+    pub kind: FileChangeKind,
+    pub unified_diff: Option<String>,
+    pub xml_changes: Vec<XmlChange>,
+    #[serde(default)]
+    pub xml_error: Option<String>,
+}
+
+/// The set of differences between two snapshots.
+///
+/// Files whose content is identical in both snapshots are not listed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub files: Vec<FileDiff>,
+}
+
+impl SnapshotDiff {
+    /// Returns `true` if both snapshots had the same files with the same contents.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Builds a human-readable summary of the changes, one line per file
+    /// followed by one line per structural XML change.
+    pub fn summary(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Display for XmlChange {
+    /// Formats the `XmlChange` for display.
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self.kind {
+            XmlChangeKind::Added => match &self.new_value {
+                Some(value) => write!(f, "+ {} = '{}'", self.path, value),
+                None => write!(f, "+ {}", self.path),
+            },
+            XmlChangeKind::Removed => match &self.old_value {
+                Some(value) => write!(f, "- {} = '{}'", self.path, value),
+                None => write!(f, "- {}", self.path),
+            },
+            XmlChangeKind::Changed => write!(
+                f,
+                "~ {}: '{}' -> '{}'",
+                self.path,
+                self.old_value.as_deref().unwrap_or_default(),
+                self.new_value.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+impl Display for SnapshotDiff {
+    /// Formats the `SnapshotDiff` for display.
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        for file in &self.files {
+            match file.kind {
+                FileChangeKind::Added => writeln!(f, "{}: added", file.filename)?,
+                FileChangeKind::Removed => writeln!(f, "{}: removed", file.filename)?,
+                FileChangeKind::Modified if file.xml_error.is_some() => writeln!(
+                    f,
+                    "{}: modified (structural diff unavailable: {})",
+                    file.filename,
+                    file.xml_error.as_deref().unwrap_or_default()
+                )?,
+                FileChangeKind::Modified if file.xml_changes.is_empty() => {
+                    writeln!(f, "{}: modified", file.filename)?
+                }
+                FileChangeKind::Modified => writeln!(
+                    f,
+                    "{}: modified ({} changes)",
+                    file.filename,
+                    file.xml_changes.len()
+                )?,
+            }
+
+            for change in &file.xml_changes {
+                writeln!(f, "  {change}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+use super::types::{XmlChange, XmlChangeKind};
+use roxmltree::{Document, Node};
+use std::collections::{HashMap, HashSet};
+
+/// Computes the structural differences between two XML documents.
+///
+/// Elements are matched by tag name and stable id (a `uuid` attribute or a `<tracker>` child,
+/// as carried by pfSense and OPNsense rules) or, failing that, by position among their siblings
+/// with the same name and no id. An added or removed element is reported once, without listing
+/// its descendants.
+///
+/// # Parameters
+/// - `old`: The previous document.
+/// - `new`: The current document.
+///
+/// # Returns
+/// - `Ok(Vec<XmlChange>)`: The list of changes, in document order.
+/// - `Err(String)`: Why either document is not well-formed XML.
+pub fn diff_xml(old: &str, new: &str) -> Result<Vec<XmlChange>, String> {
+    let old_doc = Document::parse(old).map_err(|e| format!("old document: {e}"))?;
+    let new_doc = Document::parse(new).map_err(|e| format!("new document: {e}"))?;
+
+    let old_root = old_doc.root_element();
+    let new_root = new_doc.root_element();
+
+    let mut changes = vec![];
+
+    if old_root.tag_name() == new_root.tag_name() {
+        let path = format!("/{}", old_root.tag_name().name());
+        diff_nodes(old_root, new_root, &path, &mut changes);
+    } else {
+        changes.push(removed(
+            old_root,
+            format!("/{}", old_root.tag_name().name()),
+        ));
+        changes.push(added(new_root, format!("/{}", new_root.tag_name().name())));
+    }
+
+    Ok(changes)
+}
+
+/// Compares two elements sharing the same path, recursing into their children.
+fn diff_nodes(old: Node, new: Node, path: &str, changes: &mut Vec<XmlChange>) {
+    let old_text = text_of(old);
+    let new_text = text_of(new);
+
+    if old_text != new_text {
+        changes.push(XmlChange {
+            path: path.to_string(),
+            kind: XmlChangeKind::Changed,
+            old_value: old_text,
+            new_value: new_text,
+        });
+    }
+
+    for attr in old.attributes() {
+        let attr_path = format!("{path}/@{}", attr.name());
+
+        match new.attribute(attr.name()) {
+            Some(value) if value == attr.value() => {}
+            Some(value) => changes.push(XmlChange {
+                path: attr_path,
+                kind: XmlChangeKind::Changed,
+                old_value: Some(attr.value().to_string()),
+                new_value: Some(value.to_string()),
+            }),
+            None => changes.push(XmlChange {
+                path: attr_path,
+                kind: XmlChangeKind::Removed,
+                old_value: Some(attr.value().to_string()),
+                new_value: None,
+            }),
+        }
+    }
+
+    for attr in new.attributes() {
+        if old.attribute(attr.name()).is_none() {
+            changes.push(XmlChange {
+                path: format!("{path}/@{}", attr.name()),
+                kind: XmlChangeKind::Added,
+                old_value: None,
+                new_value: Some(attr.value().to_string()),
+            });
+        }
+    }
+
+    let old_children = indexed_children(old, path);
+    let new_children = indexed_children(new, path);
+
+    let new_lookup: HashMap<&str, Node> = new_children
+        .iter()
+        .map(|(child_path, node)| (child_path.as_str(), *node))
+        .collect();
+
+    let old_lookup: HashMap<&str, Node> = old_children
+        .iter()
+        .map(|(child_path, node)| (child_path.as_str(), *node))
+        .collect();
+
+    for (child_path, old_child) in &old_children {
+        match new_lookup.get(child_path.as_str()) {
+            Some(new_child) => diff_nodes(*old_child, *new_child, child_path, changes),
+            None => changes.push(removed(*old_child, child_path.clone())),
+        }
+    }
+
+    for (child_path, new_child) in &new_children {
+        if !old_lookup.contains_key(child_path.as_str()) {
+            changes.push(added(*new_child, child_path.clone()));
+        }
+    }
+}
+
+/// Lists the element children of a node along with their paths.
+///
+/// Children are addressed by their stable id, unless a sibling with the same name shares it.
+fn indexed_children<'a, 'input>(
+    node: Node<'a, 'input>,
+    path: &str,
+) -> Vec<(String, Node<'a, 'input>)> {
+    let mut counters: HashMap<&str, usize> = HashMap::new();
+    let mut seen: HashSet<(&str, String)> = HashSet::new();
+
+    node.children()
+        .filter(|child| child.is_element())
+        .map(|child| {
+            let name = child.tag_name().name();
+
+            if let Some(id) = stable_id(child)
+                && seen.insert((name, id.clone()))
+            {
+                return (format!("{path}/{name}[{id}]"), child);
+            }
+
+            let counter = counters.entry(name).or_default();
+            *counter += 1;
+
+            let child_path = if *counter == 1 {
+                format!("{path}/{name}")
+            } else {
+                format!("{path}/{name}[{counter}]")
+            };
+
+            (child_path, child)
+        })
+        .collect()
+}
+
+/// Returns the stable id of an element, as a path predicate (`@uuid='…'` or `tracker='…'`).
+fn stable_id(node: Node) -> Option<String> {
+    if let Some(uuid) = node.attribute("uuid").map(str::trim)
+        && !uuid.is_empty()
+    {
+        return Some(format!("@uuid='{uuid}'"));
+    }
+
+    let tracker = node
+        .children()
+        .find(|child| child.has_tag_name("tracker"))
+        .and_then(text_of)?;
+
+    Some(format!("tracker='{tracker}'"))
+}
+
+/// Returns the trimmed text directly contained in an element, if any.
+fn text_of(node: Node) -> Option<String> {
+    let text: String = node
+        .children()
+        .filter(|child| child.is_text())
+        .filter_map(|child| child.text())
+        .collect();
+
+    let text = text.trim();
+
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+/// Builds an `Added` change for an element.
+fn added(node: Node, path: String) -> XmlChange {
+    XmlChange {
+        path,
+        kind: XmlChangeKind::Added,
+        old_value: None,
+        new_value: text_of(node),
+    }
+}
+
+/// Builds a `Removed` change for an element.
+fn removed(node: Node, path: String) -> XmlChange {
+    XmlChange {
+        path,
+        kind: XmlChangeKind::Removed,
+        old_value: text_of(node),
+        new_value: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff_xml;
+    use crate::snapshot_diff::types::XmlChangeKind;
+
+    #[test]
+    fn test_identical_documents() {
+        let xml = "<pfsense><system><hostname>fw</hostname></system></pfsense>";
+        assert_eq!(diff_xml(xml, xml), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_changed_text_and_attribute() {
+        let old =
+            r#"<pfsense><system><hostname>fw</hostname></system><alias version="1"/></pfsense>"#;
+        let new =
+            r#"<pfsense><system><hostname>gw</hostname></system><alias version="2"/></pfsense>"#;
+
+        let changes = diff_xml(old, new).unwrap();
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].path, "/pfsense/system/hostname");
+        assert_eq!(changes[0].kind, XmlChangeKind::Changed);
+        assert_eq!(changes[0].old_value.as_deref(), Some("fw"));
+        assert_eq!(changes[0].new_value.as_deref(), Some("gw"));
+        assert_eq!(changes[1].path, "/pfsense/alias/@version");
+        assert_eq!(changes[1].kind, XmlChangeKind::Changed);
+    }
+
+    #[test]
+    fn test_added_and_removed_elements() {
+        let old = r#"<pfsense><filter><rule><descr>A</descr></rule></filter><nat/></pfsense>"#;
+        let new = r#"<pfsense><filter><rule><descr>A</descr></rule><rule><descr>B</descr></rule></filter></pfsense>"#;
+
+        let changes = diff_xml(old, new).unwrap();
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].path, "/pfsense/filter/rule[2]");
+        assert_eq!(changes[0].kind, XmlChangeKind::Added);
+        assert_eq!(changes[1].path, "/pfsense/nat");
+        assert_eq!(changes[1].kind, XmlChangeKind::Removed);
+    }
+
+    #[test]
+    fn test_rules_matched_by_id() {
+        // The first rule is removed: the others keep their identity instead of shifting
+        let old = "<pfsense><filter>\
+                   <rule><tracker>1</tracker><descr>A</descr></rule>\
+                   <rule><tracker>2</tracker><descr>B</descr></rule>\
+                   <rule uuid=\"c\"><descr>C</descr></rule>\
+                   </filter></pfsense>";
+        let new = "<pfsense><filter>\
+                   <rule><tracker>2</tracker><descr>B2</descr></rule>\
+                   <rule uuid=\"c\"><descr>C</descr></rule>\
+                   </filter></pfsense>";
+
+        let changes = diff_xml(old, new).unwrap();
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].path, "/pfsense/filter/rule[tracker='1']");
+        assert_eq!(changes[0].kind, XmlChangeKind::Removed);
+        assert_eq!(changes[1].path, "/pfsense/filter/rule[tracker='2']/descr");
+        assert_eq!(changes[1].kind, XmlChangeKind::Changed);
+        assert_eq!(changes[1].new_value.as_deref(), Some("B2"));
+    }
+
+    #[test]
+    fn test_malformed_document() {
+        assert!(diff_xml("<pfsense>", "<pfsense/>").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use crate::platform::read_platform_version;
+use crate::{Error, FileData, InterfaceSnapshot, Platform, Revision, State};
+use std::path::Path;
+use tokio::{fs, process::Command};
+
+/// Name of the entry holding the network interfaces of the host, serialized
+/// with `InterfaceSnapshot::serialize_snapshot`.
+pub const NETWORK_INTERFACES_ENTRY: &str = "#NetworkInterfaces";
+/// Name of the entry holding the configuration state (`Draft`, `Applied` or `Undefined`) as text.
+pub const STATE_ENTRY: &str = "#State";
+/// Name of the entry holding the hostname of the host as text.
+pub const HOSTNAME_ENTRY: &str = "#Hostname";
+/// Name of the entry holding the version of the platform as text (e.g. `2.7.2-RELEASE`).
+pub const PLATFORM_VERSION_ENTRY: &str = "#PlatformVersion";
+/// Name of the entry holding the attribution of the last change (see `Revision`) as JSON.
+pub const REVISION_ENTRY: &str = "#Revision";
+
+/// Selects the synthesized entries a `Watcher` attaches to its snapshots.
+///
+/// Synthesized entries don't come from a file on disk: their name starts with `#`
+/// and their modification time is `0`.
+///
+/// # Fields
+/// - `network_interfaces`: Attach the `#NetworkInterfaces` entry.
+/// - `state`: Attach the `#State` entry.
+/// - `hostname`: Attach the `#Hostname` entry.
+/// - `platform_version`: Attach the `#PlatformVersion` entry.
+/// - `revision`: Attach the `#Revision` entry (pfSense and OPNsense only).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SynthesizedEntries {
+    pub network_interfaces: bool,
+    pub state: bool,
+    pub hostname: bool,
+    pub platform_version: bool,
+    pub revision: bool,
+}
+
+impl SynthesizedEntries {
+    /// Selects every synthesized entry.
+    pub fn all() -> Self {
+        Self {
+            network_interfaces: true,
+            state: true,
+            hostname: true,
+            platform_version: true,
+            revision: true,
+        }
+    }
+
+    /// Builds the selected entries.
+    ///
+    /// Entries whose value cannot be determined (e.g. an unknown platform version) are omitted.
+    ///
+    /// # Parameters
+    /// - `platform`: The platform the entries are built for.
+    /// - `state`: The current configuration state.
+    /// - `root`: The filesystem root the platform version is read from (`/` on a real host).
+    /// - `files`: The files of the snapshot the entries are attached to.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<FileData>)`: The synthesized entries.
+    /// - `Err(Error)`: If the network interfaces or the revision cannot be serialized.
+    pub async fn build(
+        &self,
+        platform: Platform,
+        state: State,
+        root: &Path,
+        files: &[FileData],
+    ) -> Result<Vec<FileData>, Error> {
+        let mut entries = Vec::new();
+
+        if self.network_interfaces {
+            let interfaces = tokio::task::spawn_blocking(InterfaceSnapshot::take_all)
+                .await
+                .unwrap_or_default();
+
+            entries.push(FileData::new(
+                NETWORK_INTERFACES_ENTRY,
+                InterfaceSnapshot::serialize_snapshot(&interfaces)?,
+                0,
+            ));
+        }
+
+        if self.state {
+            entries.push(FileData::new(
+                STATE_ENTRY,
+                format!("{state:?}").into_bytes(),
+                0,
+            ));
+        }
+
+        if self.hostname
+            && let Some(hostname) = read_hostname().await
+        {
+            entries.push(FileData::new(HOSTNAME_ENTRY, hostname.into_bytes(), 0));
+        }
+
+        if self.platform_version
+            && let Some(version) = read_platform_version(platform, root).await
+        {
+            entries.push(FileData::new(
+                PLATFORM_VERSION_ENTRY,
+                version.into_bytes(),
+                0,
+            ));
+        }
+
+        if self.revision
+            && let Some(revision) = Revision::from_snapshot(files)
+        {
+            entries.push(revision.to_entry()?);
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Reads the hostname of the host.
+async fn read_hostname() -> Option<String> {
+    let hostname = match Command::new("hostname").output().await {
+        Ok(output) if output.status.success() => String::from_utf8(output.stdout).ok(),
+        _ => fs::read_to_string("/etc/hostname").await.ok(),
+    }?;
+
+    Some(hostname.trim().to_string()).filter(|hostname| !hostname.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{STATE_ENTRY, SynthesizedEntries};
+    use crate::{Platform, State};
+    use std::path::Path;
+
+    #[tokio::test]
+    async fn test_build_selected_entries() {
+        let entries = SynthesizedEntries {
+            state: true,
+            ..Default::default()
+        }
+        .build(Platform::Linux, State::Draft, Path::new("/"), &[])
+        .await
+        .unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].filename, STATE_ENTRY);
+        assert_eq!(entries[0].content, b"Draft");
+        assert_eq!(entries[0].mtime, 0);
+    }
+}
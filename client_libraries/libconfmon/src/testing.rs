@@ -0,0 +1,210 @@
+use crate::Platform;
+use crate::platform::{
+    CONFIG_BACKUP_PATH, CONFIG_XML_PATH, LINUX_OS_RELEASE_PATH, NFTABLES_CONFIG_PATH,
+    OPNSENSE_DIRTY_MARKERS_PATH, OPNSENSE_VERSION_PATH, PFSENSE_DIRTY_MARKERS_PATH,
+    PFSENSE_VERSION_PATH, rooted,
+};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// Name of the marker created when the firewall rules have pending changes.
+const FILTER_DIRTY_MARKER: &str = "filter.dirty";
+
+/// Markers identifying each platform (see `Platform::detect`), with their content.
+const PFSENSE_MARKERS: [(&str, &str); 2] = [
+    ("/etc/platform", "pfSense\n"),
+    (PFSENSE_VERSION_PATH, "2.7.2-RELEASE\n"),
+];
+const OPNSENSE_MARKERS: [(&str, &str); 1] = [(
+    OPNSENSE_VERSION_PATH,
+    r#"{"product_name":"OPNsense","product_version":"24.1.1"}"#,
+)];
+const LINUX_MARKERS: [(&str, &str); 2] = [
+    (
+        LINUX_OS_RELEASE_PATH,
+        "NAME=\"Debian GNU/Linux\"\nPRETTY_NAME=\"Debian GNU/Linux 12 (bookworm)\"\n",
+    ),
+    ("/usr/sbin/nft", ""),
+];
+
+/// Counter making the directories of fake platforms unique within a process.
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+/// A fake platform tree built in a temporary directory.
+///
+/// The tree contains the files a `Watcher` and the `Detector` look up, so they can be
+/// exercised without a real firewall or root privileges: pass `root()` to
+/// `Watcher::new_with_root` or `Detector::check_with_root`.
+///
+/// The tree is removed when the `FakePlatform` is dropped.
+///
+/// Only available with the `testing` feature.
+///
+/// Note that on Linux the `Detector` still dumps the live ruleset of the host.
+pub struct FakePlatform {
+    /// Root of the fake tree.
+    root: PathBuf,
+    /// Platform the tree mimics.
+    platform: Platform,
+}
+
+impl FakePlatform {
+    /// Builds a fake platform tree with an empty configuration.
+    ///
+    /// For pfSense and OPNsense this creates `/conf/config.xml` and the directory of the
+    /// `.dirty` markers, for Linux `/etc/nftables.conf`. The files identifying the platform and its version
+    /// are created as well, so the tree is recognized by `Platform::detect_with_root`.
+    ///
+    /// # Parameters
+    /// - `platform`: The platform the tree mimics.
+    ///
+    /// # Returns
+    /// - `Ok(Self)`: The fake platform.
+    /// - `Err(io::Error)`: If the tree cannot be created.
+    pub fn new(platform: Platform) -> io::Result<Self> {
+        let root = std::env::temp_dir().join(format!(
+            "libconfmon-fake-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let _ = fs::remove_dir_all(&root);
+
+        let fake = Self { root, platform };
+
+        let markers: &[(&str, &str)] = match platform {
+            Platform::PfSense => &PFSENSE_MARKERS,
+            Platform::OPNsense => &OPNSENSE_MARKERS,
+            Platform::Linux => &LINUX_MARKERS,
+        };
+
+        for (path, content) in markers {
+            let path = rooted(&fake.root, path);
+
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            fs::write(path, content)?;
+        }
+
+        match platform {
+            Platform::PfSense => {
+                fs::create_dir_all(fake.dirty_markers_path())?;
+                fake.write_config("<?xml version=\"1.0\"?>\n<pfsense></pfsense>\n")?;
+            }
+            Platform::OPNsense => {
+                fs::create_dir_all(fake.dirty_markers_path())?;
+                fake.write_config("<?xml version=\"1.0\"?>\n<opnsense></opnsense>\n")?;
+            }
+            Platform::Linux => fake.write_config("flush ruleset\n")?,
+        }
+
+        Ok(fake)
+    }
+
+    /// Returns the root of the fake tree.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the platform the tree mimics.
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    /// Returns the path of the monitored configuration file in the fake tree.
+    pub fn config_path(&self) -> PathBuf {
+        match self.platform {
+            Platform::PfSense | Platform::OPNsense => rooted(&self.root, CONFIG_XML_PATH),
+            Platform::Linux => rooted(&self.root, NFTABLES_CONFIG_PATH),
+        }
+    }
+
+    /// Simulates an edit of the configuration file.
+    ///
+    /// The modification time is moved forward by at least one second,
+    /// so the change is noticed even on filesystems with a coarse timestamp resolution.
+    ///
+    /// # Parameters
+    /// - `content`: The new content of the configuration file.
+    ///
+    /// # Returns
+    /// - `Ok(())`: If the file was written.
+    /// - `Err(io::Error)`: If the file cannot be written.
+    pub fn write_config(&self, content: &str) -> io::Result<()> {
+        let path = self.config_path();
+
+        let previous = fs::metadata(&path).and_then(|metadata| metadata.modified());
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&path, content)?;
+
+        if let Ok(previous) = previous {
+            let mtime = SystemTime::now().max(previous + Duration::from_secs(1));
+            File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(mtime)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds a configuration backup, as kept by pfSense and OPNsense in `/conf/backup`.
+    ///
+    /// # Parameters
+    /// - `timestamp`: The time of the backup, in seconds since the UNIX epoch.
+    /// - `content`: The content of the backup.
+    ///
+    /// # Returns
+    /// - `Ok(())`: If the backup was written.
+    /// - `Err(io::Error)`: If the backup cannot be written.
+    pub fn write_backup(&self, timestamp: u64, content: &str) -> io::Result<()> {
+        let directory = rooted(&self.root, CONFIG_BACKUP_PATH);
+        fs::create_dir_all(&directory)?;
+        fs::write(directory.join(format!("config-{timestamp}.xml")), content)
+    }
+
+    /// Returns the directory holding the `.dirty` markers in the fake tree.
+    fn dirty_markers_path(&self) -> PathBuf {
+        match self.platform {
+            Platform::OPNsense => rooted(&self.root, OPNSENSE_DIRTY_MARKERS_PATH),
+            Platform::PfSense | Platform::Linux => rooted(&self.root, PFSENSE_DIRTY_MARKERS_PATH),
+        }
+    }
+
+    /// Simulates pending (not yet applied) changes by creating or removing a `.dirty` marker.
+    ///
+    /// # Parameters
+    /// - `dirty`: Whether the marker should exist.
+    ///
+    /// # Returns
+    /// - `Ok(())`: If the marker was created or removed.
+    /// - `Err(io::Error)`: If the marker cannot be created or removed.
+    pub fn set_dirty(&self, dirty: bool) -> io::Result<()> {
+        let marker = self.dirty_markers_path().join(FILTER_DIRTY_MARKER);
+
+        if dirty {
+            fs::create_dir_all(self.dirty_markers_path())?;
+            fs::write(marker, [])
+        } else {
+            match fs::remove_file(marker) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
+impl Drop for FakePlatform {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{
+    Error, ErrorKind, history::utils::write_atomically, watcher::utils::make_error_mapper,
+};
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, time::Duration};
+
+/// Controls how snapshots are dispatched to the handler when it fails.
+///
+/// A detected change stays pending until `on_snapshot` succeeds. Failed dispatches are
+/// retried with an exponential backoff, and changes detected in the meantime are coalesced
+/// into the next attempt, which always captures the latest content of the files.
+///
+/// # Fields
+/// - `initial_backoff`: The delay before the first retry.
+/// - `max_backoff`: The upper bound of the delay between two retries.
+/// - `pending_path`: If set, the pending change is persisted to this file so that it
+///   survives a restart of the watcher.
+#[derive(Debug, Clone)]
+pub struct DispatchPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub pending_path: Option<PathBuf>,
+}
+
+impl Default for DispatchPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            pending_path: None,
+        }
+    }
+}
+
+impl DispatchPolicy {
+    /// Computes the delay before the next retry.
+    ///
+    /// # Parameters
+    /// - `attempts`: The number of failed attempts so far (at least 1).
+    ///
+    /// # Returns
+    /// `initial_backoff` doubled for every failed attempt after the first one, capped at `max_backoff`.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Loads the persisted pending change, if any.
+    ///
+    /// A missing or unreadable file is treated as no pending change.
+    pub(crate) fn load_pending(&self) -> Option<PendingDispatch> {
+        let data = std::fs::read(self.pending_path.as_ref()?).ok()?;
+        bincode::deserialize(&data).ok()
+    }
+
+    /// Persists the pending change, or removes the persisted one if `pending` is `None`.
+    ///
+    /// # Returns
+    /// - `Ok(())`: If the pending state was saved, or if persistence is disabled.
+    /// - `Err(Error)`: If the file cannot be written or removed.
+    pub(crate) async fn save_pending(
+        &self,
+        pending: Option<&PendingDispatch>,
+    ) -> Result<(), Error> {
+        let Some(path) = &self.pending_path else {
+            return Ok(());
+        };
+
+        match pending {
+            Some(pending) => {
+                let data = bincode::serialize(pending)
+                    .map_err(make_error_mapper(ErrorKind::ErrorWritingFile))?;
+
+                write_atomically(path, &data).await
+            }
+            None => match tokio::fs::remove_file(path).await {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    Err(make_error_mapper(ErrorKind::ErrorWritingFile)(err))
+                }
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+/// A change that has been detected but not yet accepted by the handler.
+///
+/// # Fields
+/// - `since`: The time the change was first detected, in milliseconds since the UNIX epoch.
+/// - `attempts`: The number of failed dispatch attempts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingDispatch {
+    pub since: u128,
+    pub attempts: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DispatchPolicy, PendingDispatch};
+    use std::time::Duration;
+
+    #[test]
+    fn test_backoff_doubles_and_is_capped() {
+        let policy = DispatchPolicy {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+            pending_path: None,
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(4), Duration::from_secs(8));
+        assert_eq!(policy.backoff(5), Duration::from_secs(10));
+        assert_eq!(policy.backoff(100), Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_pending_state_is_persisted() {
+        let path = std::env::temp_dir().join(format!("libconfmon-pending-{}", std::process::id()));
+        let policy = DispatchPolicy {
+            pending_path: Some(path.clone()),
+            ..Default::default()
+        };
+
+        let pending = PendingDispatch {
+            since: 42,
+            attempts: 3,
+        };
+
+        policy.save_pending(Some(&pending)).await.unwrap();
+        assert_eq!(policy.load_pending(), Some(pending));
+
+        policy.save_pending(None).await.unwrap();
+        assert_eq!(policy.load_pending(), None);
+        assert!(!path.exists());
+    }
+}
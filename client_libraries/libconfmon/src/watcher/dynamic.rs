@@ -0,0 +1,57 @@
+use super::{r#impl::WatcherHandler, types::Snapshot};
+use crate::{Error, State};
+use std::{future::Future, pin::Pin, sync::Arc};
+
+/// A boxed future returned by `DynWatcherHandler`.
+pub type HandlerFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// An object-safe variant of `WatcherHandler`, returning boxed futures.
+///
+/// Every `WatcherHandler` implements this trait, so any handler can be turned into a
+/// `Box<dyn DynWatcherHandler>` (or an `Arc`). Boxed handlers implement `WatcherHandler`
+/// in turn and can be given to a `Watcher` or combined in a `FanoutHandler`.
+pub trait DynWatcherHandler {
+    /// Same as `WatcherHandler::on_snapshot`.
+    fn on_snapshot_dyn(
+        &self,
+        snapshot: Snapshot,
+        state: State,
+    ) -> HandlerFuture<'_, Result<(), Error>>;
+
+    /// Same as `WatcherHandler::on_error`.
+    fn on_error_dyn(&self, error: Error) -> HandlerFuture<'_, ()>;
+}
+
+impl<H: WatcherHandler> DynWatcherHandler for H {
+    fn on_snapshot_dyn(
+        &self,
+        snapshot: Snapshot,
+        state: State,
+    ) -> HandlerFuture<'_, Result<(), Error>> {
+        Box::pin(self.on_snapshot(snapshot, state))
+    }
+
+    fn on_error_dyn(&self, error: Error) -> HandlerFuture<'_, ()> {
+        Box::pin(self.on_error(error))
+    }
+}
+
+impl<H: DynWatcherHandler + ?Sized> WatcherHandler for Box<H> {
+    async fn on_snapshot(&self, snapshot: Snapshot, state: State) -> Result<(), Error> {
+        self.as_ref().on_snapshot_dyn(snapshot, state).await
+    }
+
+    async fn on_error(&self, error: Error) {
+        self.as_ref().on_error_dyn(error).await;
+    }
+}
+
+impl<H: DynWatcherHandler + ?Sized> WatcherHandler for Arc<H> {
+    async fn on_snapshot(&self, snapshot: Snapshot, state: State) -> Result<(), Error> {
+        self.as_ref().on_snapshot_dyn(snapshot, state).await
+    }
+
+    async fn on_error(&self, error: Error) {
+        self.as_ref().on_error_dyn(error).await;
+    }
+}
@@ -0,0 +1,32 @@
+use super::types::Snapshot;
+use crate::{Error, State, WatcherHandler};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+/// An event emitted by a `Watcher` while monitoring.
+#[derive(Debug, Clone)]
+pub enum WatchEvent {
+    /// The content of a monitored file changed. Holds the name of the file.
+    FileChanged(String),
+    /// The configuration state changed (e.g. from `Draft` to `Applied`).
+    StateChanged { from: State, to: State },
+    /// A snapshot was captured and is about to be dispatched to the handler.
+    SnapshotReady(Snapshot, State),
+    /// An error occurred while monitoring or dispatching.
+    Error(Error),
+}
+
+/// A stream of `WatchEvent`s, obtained with `Watcher::subscribe`.
+pub type WatchEventStream = UnboundedReceiverStream<WatchEvent>;
+
+/// A handler that ignores snapshots and errors.
+///
+/// Useful for watchers that are consumed only through their event stream.
+pub struct NoopHandler;
+
+impl WatcherHandler for NoopHandler {
+    async fn on_snapshot(&self, _snapshot: Snapshot, _state: State) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn on_error(&self, _error: Error) {}
+}
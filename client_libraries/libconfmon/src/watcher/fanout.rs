@@ -0,0 +1,326 @@
+use super::{dynamic::DynWatcherHandler, r#impl::WatcherHandler, types::Snapshot};
+use crate::{Error, ErrorKind, State};
+use futures::{FutureExt, future::join_all};
+use std::{panic::AssertUnwindSafe, time::Duration};
+
+/// A consumer of the snapshots dispatched by a `FanoutHandler`.
+struct Consumer {
+    /// Name of the consumer, used in error messages.
+    name: String,
+    /// Handler receiving the snapshots.
+    handler: Box<dyn DynWatcherHandler + Send>,
+    /// Maximum time the consumer may spend on a snapshot.
+    timeout: Duration,
+    /// Whether a failure of the consumer fails the whole dispatch.
+    required: bool,
+}
+
+/// A handler dispatching every snapshot to several consumers
+/// (e.g. an uploader, a local history and an audit log).
+///
+/// Consumers run concurrently and are isolated from each other: a consumer that fails,
+/// panics or exceeds its timeout has the error reported to its own `on_error` and does not
+/// prevent the others from receiving the snapshot. Errors reported by the `Watcher`
+/// are forwarded to every consumer.
+///
+/// The dispatch is considered failed (and retried by the `Watcher`) only if a
+/// required consumer fails. Note that a retry dispatches the snapshot to all consumers again.
+#[derive(Default)]
+pub struct FanoutHandler {
+    consumers: Vec<Consumer>,
+}
+
+impl FanoutHandler {
+    /// Creates a handler without consumers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a best-effort consumer, whose failures don't fail the dispatch.
+    ///
+    /// # Parameters
+    /// - `name`: The name of the consumer, used in error messages.
+    /// - `handler`: The handler receiving the snapshots.
+    /// - `timeout`: The maximum time the consumer may spend on a snapshot.
+    pub fn with_consumer<H>(self, name: impl Into<String>, handler: H, timeout: Duration) -> Self
+    where
+        H: DynWatcherHandler + Send + 'static,
+    {
+        self.push(name.into(), Box::new(handler), timeout, false)
+    }
+
+    /// Adds a required consumer, whose failures fail the dispatch so that it is retried.
+    ///
+    /// # Parameters
+    /// - `name`: The name of the consumer, used in error messages.
+    /// - `handler`: The handler receiving the snapshots.
+    /// - `timeout`: The maximum time the consumer may spend on a snapshot.
+    pub fn with_required_consumer<H>(
+        self,
+        name: impl Into<String>,
+        handler: H,
+        timeout: Duration,
+    ) -> Self
+    where
+        H: DynWatcherHandler + Send + 'static,
+    {
+        self.push(name.into(), Box::new(handler), timeout, true)
+    }
+
+    fn push(
+        mut self,
+        name: String,
+        handler: Box<dyn DynWatcherHandler + Send>,
+        timeout: Duration,
+        required: bool,
+    ) -> Self {
+        self.consumers.push(Consumer {
+            name,
+            handler,
+            timeout,
+            required,
+        });
+        self
+    }
+}
+
+impl WatcherHandler for FanoutHandler {
+    async fn on_snapshot(&self, snapshot: Snapshot, state: State) -> Result<(), Error> {
+        let deliveries = self.consumers.iter().map(|consumer| {
+            let snapshot = snapshot.clone();
+
+            async move {
+                let delivery = AssertUnwindSafe(consumer.handler.on_snapshot_dyn(snapshot, state))
+                    .catch_unwind();
+
+                let result = match tokio::time::timeout(consumer.timeout, delivery).await {
+                    Ok(Ok(result)) => result,
+                    Ok(Err(_)) => Err(Error {
+                        kind: ErrorKind::ErrorHandlingSnapshot,
+                        message: format!("Consumer {} panicked", consumer.name),
+                    }),
+                    Err(_) => Err(Error {
+                        kind: ErrorKind::ErrorHandlingSnapshot,
+                        message: format!(
+                            "Consumer {} timed out after {:?}",
+                            consumer.name, consumer.timeout
+                        ),
+                    }),
+                };
+
+                match result {
+                    Ok(()) => None,
+                    Err(err) => {
+                        let message = format!("{}: {}", consumer.name, err.message);
+                        notify(consumer, err).await;
+                        consumer.required.then_some(message)
+                    }
+                }
+            }
+        });
+
+        let failures: Vec<String> = join_all(deliveries).await.into_iter().flatten().collect();
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Error {
+                kind: ErrorKind::ErrorHandlingSnapshot,
+                message: format!("Required consumers failed: {}", failures.join("; ")),
+            })
+        }
+    }
+
+    async fn on_error(&self, error: Error) {
+        let notifications = self
+            .consumers
+            .iter()
+            .map(|consumer| notify(consumer, error.clone()));
+
+        join_all(notifications).await;
+    }
+}
+
+/// Reports an error to a consumer, ignoring any panic of its `on_error`.
+async fn notify(consumer: &Consumer, error: Error) {
+    let _ = AssertUnwindSafe(consumer.handler.on_error_dyn(error))
+        .catch_unwind()
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FanoutHandler;
+    use crate::{DynWatcherHandler, Error, ErrorKind, FileData, State, WatcherHandler};
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicU32, Ordering},
+        },
+        time::Duration,
+    };
+
+    /// Records how many snapshots and errors it received.
+    #[derive(Default)]
+    struct Recorder {
+        snapshots: AtomicU32,
+        errors: AtomicU32,
+        fail: bool,
+        panic: bool,
+        delay: Duration,
+    }
+
+    impl WatcherHandler for Recorder {
+        async fn on_snapshot(&self, _snapshot: Vec<FileData>, _state: State) -> Result<(), Error> {
+            tokio::time::sleep(self.delay).await;
+            self.snapshots.fetch_add(1, Ordering::SeqCst);
+
+            if self.panic {
+                panic!("consumer bug");
+            }
+
+            if self.fail {
+                Err(Error {
+                    kind: ErrorKind::ErrorHandlingSnapshot,
+                    message: String::from("rejected"),
+                })
+            } else {
+                Ok(())
+            }
+        }
+
+        async fn on_error(&self, _error: Error) {
+            self.errors.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn snapshot() -> Vec<FileData> {
+        vec![FileData::new("config.xml", b"<a/>".to_vec(), 1)]
+    }
+
+    #[tokio::test]
+    async fn test_consumers_are_isolated() {
+        let uploader = Arc::new(Recorder::default());
+        let history = Arc::new(Recorder {
+            fail: true,
+            ..Default::default()
+        });
+        let audit = Arc::new(Recorder {
+            delay: Duration::from_secs(5),
+            ..Default::default()
+        });
+
+        let handler = FanoutHandler::new()
+            .with_required_consumer("uploader", uploader.clone(), Duration::from_secs(1))
+            .with_consumer("history", history.clone(), Duration::from_secs(1))
+            .with_consumer("audit", audit.clone(), Duration::from_millis(50));
+
+        assert!(
+            handler
+                .on_snapshot(snapshot(), State::Applied)
+                .await
+                .is_ok()
+        );
+
+        assert_eq!(uploader.snapshots.load(Ordering::SeqCst), 1);
+        assert_eq!(uploader.errors.load(Ordering::SeqCst), 0);
+        assert_eq!(history.snapshots.load(Ordering::SeqCst), 1);
+        assert_eq!(history.errors.load(Ordering::SeqCst), 1);
+        // The audit log timed out before recording the snapshot
+        assert_eq!(audit.snapshots.load(Ordering::SeqCst), 0);
+        assert_eq!(audit.errors.load(Ordering::SeqCst), 1);
+
+        handler
+            .on_error(Error {
+                kind: ErrorKind::ErrorWatchingFile,
+                message: String::from("boom"),
+            })
+            .await;
+
+        assert_eq!(uploader.errors.load(Ordering::SeqCst), 1);
+        assert_eq!(history.errors.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_required_consumer_failure_fails_dispatch() {
+        let handler = FanoutHandler::new()
+            .with_consumer("history", Recorder::default(), Duration::from_secs(1))
+            .with_required_consumer(
+                "uploader",
+                Recorder {
+                    fail: true,
+                    ..Default::default()
+                },
+                Duration::from_secs(1),
+            );
+
+        let err = handler
+            .on_snapshot(snapshot(), State::Draft)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err.kind, ErrorKind::ErrorHandlingSnapshot));
+        assert_eq!(err.message, "Required consumers failed: uploader: rejected");
+    }
+
+    #[tokio::test]
+    async fn test_panicking_consumer_is_isolated() {
+        let uploader = Arc::new(Recorder::default());
+        let audit = Arc::new(Recorder {
+            panic: true,
+            ..Default::default()
+        });
+
+        let handler = FanoutHandler::new()
+            .with_required_consumer("uploader", uploader.clone(), Duration::from_secs(1))
+            .with_consumer("audit", audit.clone(), Duration::from_secs(1));
+
+        assert!(
+            handler
+                .on_snapshot(snapshot(), State::Applied)
+                .await
+                .is_ok()
+        );
+
+        assert_eq!(uploader.snapshots.load(Ordering::SeqCst), 1);
+        assert_eq!(uploader.errors.load(Ordering::SeqCst), 0);
+        assert_eq!(audit.errors.load(Ordering::SeqCst), 1);
+
+        let handler = FanoutHandler::new().with_required_consumer(
+            "audit",
+            audit.clone(),
+            Duration::from_secs(1),
+        );
+
+        let err = handler
+            .on_snapshot(snapshot(), State::Applied)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.message,
+            "Required consumers failed: audit: Consumer audit panicked"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_boxed_handlers() {
+        let handlers: Vec<Box<dyn DynWatcherHandler + Send>> = vec![
+            Box::new(Recorder::default()),
+            Box::new(FanoutHandler::new().with_consumer(
+                "nested",
+                Recorder::default(),
+                Duration::from_secs(1),
+            )),
+        ];
+
+        for handler in handlers {
+            assert!(
+                handler
+                    .on_snapshot(snapshot(), State::Applied)
+                    .await
+                    .is_ok()
+            );
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use super::r#impl::{Watcher, WatcherHandler};
+use crate::{Error, ErrorKind};
+use tokio::sync::{mpsc, oneshot, watch};
+
+/// A handle to a `Watcher` running in the background, returned by `Watcher::spawn`.
+///
+/// Dropping the handle stops the watcher as well.
+pub struct WatcherHandle<H: WatcherHandler> {
+    /// Signals the watcher to stop.
+    pub(super) stop: watch::Sender<bool>,
+    /// Receives the watcher back once it has stopped.
+    pub(super) done: oneshot::Receiver<Result<Watcher<H>, Error>>,
+}
+
+impl<H: WatcherHandler> WatcherHandle<H> {
+    /// Requests the watcher to stop.
+    ///
+    /// The watcher stops at the next poll boundary: a snapshot that is being
+    /// dispatched is always fully handled first.
+    pub fn stop(&self) {
+        let _ = self.stop.send(true);
+    }
+
+    /// Waits for the watcher to stop.
+    ///
+    /// # Returns
+    /// - `Ok(Watcher<H>)`: The stopped watcher, which can be started again.
+    /// - `Err(Error)`: If the background task could not be started or terminated abnormally.
+    pub async fn join(self) -> Result<Watcher<H>, Error> {
+        self.done.await.map_err(|_| Error {
+            kind: ErrorKind::ErrorWatchingFile,
+            message: String::from("Watcher task terminated unexpectedly"),
+        })?
+    }
+
+    /// Requests the watcher to stop and waits for it.
+    ///
+    /// # Returns
+    /// - `Ok(Watcher<H>)`: The stopped watcher.
+    /// - `Err(Error)`: If the background task terminated abnormally.
+    pub async fn shutdown(self) -> Result<Watcher<H>, Error> {
+        self.stop();
+        self.join().await
+    }
+}
+
+/// A cloneable trigger requesting a `Watcher` to capture and dispatch a new snapshot,
+/// returned by `Watcher::trigger`.
+#[derive(Clone)]
+pub struct WatchTrigger {
+    pub(crate) sender: mpsc::UnboundedSender<()>,
+}
+
+impl WatchTrigger {
+    /// Requests a new snapshot.
+    ///
+    /// Requests made while the watcher is busy are coalesced into a single snapshot.
+    pub fn fire(&self) {
+        let _ = self.sender.send(());
+    }
+}
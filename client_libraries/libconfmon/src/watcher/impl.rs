@@ -1,9 +1,25 @@
 use super::{
+    dispatch::{DispatchPolicy, PendingDispatch},
+    events::{WatchEvent, WatchEventStream},
+    handle::{WatchTrigger, WatcherHandle},
     types::{FileData, FileInfo, Snapshot},
-    utils::{get_mtime, make_error_mapper},
+    utils::{get_hash, get_mtime, make_error_mapper, read_content},
 };
-use crate::{Detector, Error, ErrorKind, Platform, State};
-use std::{path::PathBuf, time::Duration};
+use crate::{
+    Detector, Error, ErrorKind, Platform, State, SynthesizedEntries,
+    platform::{
+        CONFIG_BACKUP_PATH, CONFIG_XML_PATH, IPTABLES_V4_RULES_PATH, IPTABLES_V6_RULES_PATH,
+        NFTABLES_CONFIG_PATH, rooted,
+    },
+    revision::read_backups,
+};
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio_stream::wrappers::UnboundedReceiverStream;
 
 /// A simple file watcher that monitors changes in a list of files and triggers appropriate handlers.
 #[allow(async_fn_in_trait)]
@@ -35,6 +51,22 @@ pub struct Watcher<H: WatcherHandler> {
     handler: H,
     /// Target platform
     platform: Platform,
+    /// Filesystem root the platform files are looked up in.
+    root: PathBuf,
+    /// Sender of the event stream, if subscribed.
+    events: Option<mpsc::UnboundedSender<WatchEvent>>,
+    /// Policy applied when dispatching snapshots.
+    policy: DispatchPolicy,
+    /// Change waiting to be accepted by the handler, if any.
+    pending: Option<PendingDispatch>,
+    /// Earliest time of the next dispatch attempt of the pending change.
+    retry_at: Option<Instant>,
+    /// Sender handed out to request snapshots, see `trigger`.
+    trigger_sender: mpsc::UnboundedSender<()>,
+    /// Receiver of snapshot requests.
+    triggers: mpsc::UnboundedReceiver<()>,
+    /// Synthesized entries attached to the snapshots.
+    synthesized: SynthesizedEntries,
 }
 
 impl<H: WatcherHandler> Watcher<H> {
@@ -48,42 +80,190 @@ impl<H: WatcherHandler> Watcher<H> {
     /// # Returns
     /// - `Ok(Self)`: A properly initialized `Watcher` instance.
     /// - `Err(Error)`: If any file metadata cannot be retrieved.
+    ///
+    /// Monitored files that do not exist yet are treated as empty, and picked up once created.
     pub async fn new(platform: Platform, poll_interval: u64, handler: H) -> Result<Self, Error> {
+        Watcher::new_with_root(platform, poll_interval, handler, "/").await
+    }
+
+    /// Creates a new `Watcher` instance that looks up the platform files below a filesystem root.
+    ///
+    /// This allows to monitor a fake platform tree (see `testing::FakePlatform`, enabled by
+    /// the `testing` feature) without a real firewall or root privileges.
+    ///
+    /// # Parameters
+    /// - `platform`: The target platform for which the configuration state should be monitored.
+    /// - `poll_interval`: Time interval (in milliseconds) to check for file changes.
+    /// - `handler`: An instance implementing `WatcherHandler` for handling snapshots and errors.
+    /// - `root`: The filesystem root (`/` on a real host).
+    ///
+    /// # Returns
+    /// - `Ok(Self)`: A properly initialized `Watcher` instance.
+    /// - `Err(Error)`: If any file metadata cannot be retrieved.
+    pub async fn new_with_root(
+        platform: Platform,
+        poll_interval: u64,
+        handler: H,
+        root: impl Into<PathBuf>,
+    ) -> Result<Self, Error> {
+        let root = root.into();
         let mut files = Vec::new();
 
-        for path in get_files_to_monitor(platform) {
+        for path in get_files_to_monitor(platform, &root) {
             let mtime = get_mtime(&path)
                 .await
                 .map_err(make_error_mapper(ErrorKind::ErrorInitializingWatcher))?;
 
-            files.push(FileInfo { path, mtime });
+            let hash = get_hash(&path)
+                .await
+                .map_err(make_error_mapper(ErrorKind::ErrorInitializingWatcher))?;
+
+            files.push(FileInfo { path, mtime, hash });
         }
 
+        let (trigger_sender, triggers) = mpsc::unbounded_channel();
+
         Ok(Self {
             files,
             poll_interval,
             handler,
             platform,
+            root,
+            events: None,
+            policy: DispatchPolicy::default(),
+            pending: None,
+            retry_at: None,
+            trigger_sender,
+            triggers,
+            synthesized: SynthesizedEntries::default(),
         })
     }
 
+    /// Sets the synthesized entries attached to every snapshot.
+    ///
+    /// With `SynthesizedEntries::all()`, the snapshots contain everything the libfireparse
+    /// parsers need and can be passed to them as-is.
+    ///
+    /// # Parameters
+    /// - `entries`: The entries to attach.
+    pub fn with_synthesized_entries(mut self, entries: SynthesizedEntries) -> Self {
+        self.synthesized = entries;
+        self
+    }
+
+    /// Sets the policy used to dispatch snapshots to the handler.
+    ///
+    /// If the policy persists the pending state and a pending change was left by a previous
+    /// run, it is dispatched as soon as the watcher starts.
+    ///
+    /// # Parameters
+    /// - `policy`: The dispatch policy.
+    pub fn with_dispatch_policy(mut self, policy: DispatchPolicy) -> Self {
+        self.pending = policy.load_pending().or(self.pending);
+        self.retry_at = None;
+        self.policy = policy;
+        self
+    }
+
+    /// Returns a trigger that can be used to request a new snapshot.
+    ///
+    /// Firing the trigger makes the watcher capture and dispatch a snapshot without
+    /// waiting for the next poll, even if no monitored file changed. This is used
+    /// to report changes happening outside of the monitored files, such as network
+    /// interface changes.
+    pub fn trigger(&self) -> WatchTrigger {
+        WatchTrigger {
+            sender: self.trigger_sender.clone(),
+        }
+    }
+
+    /// Returns the change waiting to be accepted by the handler, if any.
+    pub fn pending(&self) -> Option<&PendingDispatch> {
+        self.pending.as_ref()
+    }
+
+    /// Subscribes to the events emitted by the watcher.
+    ///
+    /// Only one subscriber is supported: subscribing again replaces the previous stream,
+    /// which then ends.
+    ///
+    /// # Returns
+    /// A `WatchEventStream` yielding `WatchEvent`s as they occur.
+    pub fn subscribe(&mut self) -> WatchEventStream {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.events = Some(sender);
+        UnboundedReceiverStream::new(receiver)
+    }
+
+    /// Runs the watcher on a dedicated background thread.
+    ///
+    /// The watcher runs on its own single-threaded runtime, so the handler futures
+    /// are not required to be `Send`.
+    ///
+    /// # Returns
+    /// A `WatcherHandle` that can be used to stop the watcher and wait for it.
+    pub fn spawn(mut self) -> WatcherHandle<H>
+    where
+        H: Send + 'static,
+    {
+        let (stop, mut stop_receiver) = watch::channel(false);
+        let (done_sender, done) = oneshot::channel();
+
+        std::thread::spawn(move || {
+            let result = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(make_error_mapper(ErrorKind::ErrorWatchingFile))
+                .map(|runtime| {
+                    runtime.block_on(self.watch_until(async move {
+                        let _ = stop_receiver.wait_for(|stopped| *stopped).await;
+                    }));
+                    self
+                });
+
+            let _ = done_sender.send(result);
+        });
+
+        WatcherHandle { stop, done }
+    }
+
     /// Starts monitoring the files and system state for changes.
     ///
     /// This function continuously checks the monitored files for modifications
     /// and observes system state transitions. When a file modification or a
     /// relevant state transition (from `Draft` to `Applied`) is detected, it
-    /// triggers the `on_snapshot` method of the handler.
+    /// triggers the `on_snapshot` method of the handler. Failed dispatches are
+    /// retried according to the `DispatchPolicy`.
     ///
-    /// # Returns
-    /// - `Ok(())` if the monitoring process runs smoothly.
-    /// - `Err(Error)` if an unrecoverable error occurs.
+    /// This function never returns; see `watch_until` and `spawn` for cancellable alternatives.
     pub async fn watch(&mut self) {
-        let mut last_state = Detector::check(self.platform).await;
+        self.watch_until(std::future::pending()).await
+    }
+
+    /// Monitors the files and system state for changes until `shutdown` completes.
+    ///
+    /// Behaves like `watch`, but returns once the `shutdown` future resolves.
+    /// Cancellation happens between polls, never while a snapshot is being dispatched.
+    ///
+    /// # Parameters
+    /// - `shutdown`: A future whose completion stops the watcher.
+    pub async fn watch_until<F: Future<Output = ()>>(&mut self, shutdown: F) {
+        tokio::pin!(shutdown);
+
+        let mut last_state = Detector::check_with_root(self.platform, &self.root).await;
+        let mut triggered = false;
 
         loop {
-            let mut should_upload = self.check_files_for_changes().await;
+            let mut should_upload = self.check_files_for_changes().await || triggered;
 
-            let current_state = Detector::check(self.platform).await;
+            let current_state = Detector::check_with_root(self.platform, &self.root).await;
+
+            if current_state != last_state {
+                self.emit(WatchEvent::StateChanged {
+                    from: last_state,
+                    to: current_state,
+                });
+            }
 
             if last_state == State::Draft && current_state == State::Applied {
                 should_upload = true;
@@ -92,27 +272,64 @@ impl<H: WatcherHandler> Watcher<H> {
             last_state = current_state;
 
             if should_upload {
-                self.handle_snapshot().await;
+                self.mark_pending().await;
+            }
+
+            if self.pending.is_some() && self.retry_at.is_none_or(|at| Instant::now() >= at) {
+                self.dispatch_pending().await;
+            }
+
+            tokio::select! {
+                _ = &mut shutdown => return,
+                _ = self.triggers.recv() => triggered = true,
+                _ = tokio::time::sleep(Duration::from_millis(self.poll_interval)) => triggered = false,
             }
 
-            tokio::time::sleep(Duration::from_millis(self.poll_interval)).await;
+            // Requests received in the meantime are coalesced into a single snapshot
+            while self.triggers.try_recv().is_ok() {}
         }
     }
 
     /// Checks the monitored files for modifications.
+    ///
+    /// A file is considered modified only if its content hash changed. The hash is
+    /// recomputed whenever the modification time differs from the last known one
+    /// (including when it goes backwards, e.g. after restoring a backup), so touching
+    /// a file without changing its content does not trigger a dispatch.
     pub async fn check_files_for_changes(&mut self) -> bool {
         let mut should_upload = false;
 
-        for file in &mut self.files {
-            match get_mtime(&file.path).await {
-                Ok(current) if current > file.mtime => {
-                    file.mtime = current;
-                    should_upload = true;
+        for index in 0..self.files.len() {
+            let file = &self.files[index];
+
+            let mtime = match get_mtime(&file.path).await {
+                Ok(mtime) => mtime,
+                Err(err) => {
+                    self.report_error(err).await;
+                    continue;
+                }
+            };
+
+            if mtime == file.mtime {
+                continue;
+            }
+
+            match get_hash(&file.path).await {
+                Ok(hash) => {
+                    let file = &mut self.files[index];
+                    file.mtime = mtime;
+
+                    if hash != file.hash {
+                        file.hash = hash;
+                        should_upload = true;
+
+                        let filename = file_name(&file.path);
+                        self.emit(WatchEvent::FileChanged(filename));
+                    }
                 }
                 Err(err) => {
-                    self.handler.on_error(err).await;
+                    self.report_error(err).await;
                 }
-                _ => {}
             }
         }
 
@@ -120,44 +337,100 @@ impl<H: WatcherHandler> Watcher<H> {
     }
 
     /// Captures and processes a snapshot of the monitored files and system state.
-    pub async fn handle_snapshot(&mut self) {
-        match self.snapshot().await {
-            Ok(snapshot) => {
-                let state = Detector::check(self.platform).await;
-                if let Err(err) = self.handler.on_snapshot(snapshot, state).await {
-                    self.handler.on_error(err).await;
+    ///
+    /// # Returns
+    /// `true` if the snapshot was accepted by the handler, `false` otherwise.
+    pub async fn handle_snapshot(&mut self) -> bool {
+        match self.capture().await {
+            Ok((snapshot, state)) => {
+                self.emit_snapshot(&snapshot, state);
+
+                match self.handler.on_snapshot(snapshot, state).await {
+                    Ok(()) => true,
+                    Err(err) => {
+                        self.report_error(err).await;
+                        false
+                    }
                 }
             }
             Err(err) => {
-                self.handler.on_error(err).await;
+                self.report_error(err).await;
+                false
             }
         }
     }
 
+    /// Records a detected change as pending.
+    ///
+    /// If a change is already pending, the new one is coalesced into it and
+    /// the retry schedule is kept.
+    async fn mark_pending(&mut self) {
+        if self.pending.is_some() {
+            return;
+        }
+
+        let since = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or_default();
+
+        self.pending = Some(PendingDispatch { since, attempts: 0 });
+        self.retry_at = None;
+        self.save_pending().await;
+    }
+
+    /// Dispatches the pending change, scheduling a retry if the handler fails.
+    async fn dispatch_pending(&mut self) {
+        if self.handle_snapshot().await {
+            self.pending = None;
+            self.retry_at = None;
+        } else if let Some(pending) = &mut self.pending {
+            pending.attempts += 1;
+            self.retry_at = Some(Instant::now() + self.policy.backoff(pending.attempts));
+        }
+
+        self.save_pending().await;
+    }
+
+    /// Persists the pending change according to the dispatch policy.
+    async fn save_pending(&self) {
+        if let Err(err) = self.policy.save_pending(self.pending.as_ref()).await {
+            self.report_error(err).await;
+        }
+    }
+
     /// Generates a snapshot of the current state of the monitored files.
     ///
+    /// The synthesized entries selected with `with_synthesized_entries` are appended
+    /// after the files.
+    ///
     /// # Returns
     /// - `Ok(Snapshot)`: A snapshot containing the contents and metadata of monitored files.
     /// - `Err(Error)`: If a file cannot be read.
     pub async fn snapshot(&self) -> Result<Snapshot, Error> {
+        self.capture().await.map(|(snapshot, _)| snapshot)
+    }
+
+    /// Generates a snapshot along with the current configuration state.
+    async fn capture(&self) -> Result<(Snapshot, State), Error> {
+        let state = Detector::check_with_root(self.platform, &self.root).await;
         let mut snapshot = Snapshot::new();
 
         for file in &self.files {
-            let content = tokio::fs::read(&file.path)
-                .await
-                .map_err(make_error_mapper(ErrorKind::ErrorReadingFile))?;
+            let content = read_content(&file.path).await?;
 
-            let filename = file
-                .path
-                .file_name()
-                .unwrap_or(file.path.as_os_str())
-                .to_string_lossy()
-                .into_owned();
+            let mtime = get_mtime(&file.path).await?;
 
-            snapshot.push(FileData { filename, content });
+            snapshot.push(FileData::new(file_name(&file.path), content, mtime));
         }
 
-        Ok(snapshot)
+        snapshot.extend(
+            self.synthesized
+                .build(self.platform, state, &self.root, &snapshot)
+                .await?,
+        );
+
+        Ok((snapshot, state))
     }
 
     /// Forces the capture of a snapshot and dispatches it to the handler.
@@ -166,30 +439,316 @@ impl<H: WatcherHandler> Watcher<H> {
     /// - `Ok(())` if the snapshot was successfully processed by the handler.
     /// - `Err(Error)`: If an error occurs during snapshot creation or handling.
     pub async fn force_capture_and_dispatch(&self) -> Result<(), Error> {
-        let snapshot = self.snapshot().await?;
-        let state = Detector::check(self.platform).await;
+        let (snapshot, state) = self.capture().await?;
+        self.emit_snapshot(&snapshot, state);
 
         let result = self.handler.on_snapshot(snapshot, state).await;
 
-        if result.is_err() {
-            self.handler
-                .on_error(result.as_ref().unwrap_err().clone())
-                .await
+        if let Err(err) = &result {
+            self.report_error(err.clone()).await
         }
 
         result
     }
+
+    /// Reads the configuration backups kept by the platform, from the oldest to the newest.
+    ///
+    /// Every backup is returned as a snapshot holding `config.xml` and its `#Revision` entry
+    /// (see `read_backups`), so the history of changes can be imported into a
+    /// `SnapshotHistory` or dispatched to the server. Linux hosts keep no backups.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Snapshot>)`: The backups.
+    /// - `Err(Error)`: If the backups cannot be read.
+    pub async fn backups(&self) -> Result<Vec<Snapshot>, Error> {
+        match self.platform {
+            Platform::PfSense | Platform::OPNsense => {
+                read_backups(&rooted(&self.root, CONFIG_BACKUP_PATH)).await
+            }
+            Platform::Linux => Ok(Vec::new()),
+        }
+    }
+
+    /// Sends an event to the subscriber, if any.
+    fn emit(&self, event: WatchEvent) {
+        if let Some(events) = &self.events {
+            let _ = events.send(event);
+        }
+    }
+
+    /// Sends a `SnapshotReady` event to the subscriber, if any.
+    fn emit_snapshot(&self, snapshot: &Snapshot, state: State) {
+        if self.events.is_some() {
+            self.emit(WatchEvent::SnapshotReady(snapshot.clone(), state));
+        }
+    }
+
+    /// Reports an error to both the handler and the subscriber.
+    async fn report_error(&self, error: Error) {
+        self.emit(WatchEvent::Error(error.clone()));
+        self.handler.on_error(error).await;
+    }
+}
+
+/// Returns the name of the file at `path`, as reported in snapshots and events.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
 }
 
 /// Returns a list of files that should be monitored based on the given platform.
 ///
 /// # Parameters
 /// - `platform`: The target platform for which files need to be monitored.
+/// - `root`: The filesystem root the files are looked up in.
 ///
 /// # Returns
 /// - `Vec<PathBuf>`: A vector containing paths to the configuration files that need monitoring.
-fn get_files_to_monitor(platform: Platform) -> Vec<PathBuf> {
+///
+/// For `Platform::Linux`, both the nftables and the iptables ruleset files are returned,
+/// whether they exist or not: a host usually runs either nftables or iptables, and the
+/// ruleset file may only be created after the watcher started.
+fn get_files_to_monitor(platform: Platform, root: &Path) -> Vec<PathBuf> {
     match platform {
-        Platform::PfSense | Platform::OPNsense => vec![PathBuf::from("/conf/config.xml")],
+        Platform::PfSense | Platform::OPNsense => vec![rooted(root, CONFIG_XML_PATH)],
+        Platform::Linux => [
+            NFTABLES_CONFIG_PATH,
+            IPTABLES_V4_RULES_PATH,
+            IPTABLES_V6_RULES_PATH,
+        ]
+        .iter()
+        .map(|path| rooted(root, path))
+        .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DispatchPolicy, SynthesizedEntries, Watcher, WatcherHandler};
+    use crate::{
+        Error, ErrorKind, FakePlatform, NoopHandler, Platform, Snapshot, State, WatchEvent,
+        WatchEventStream, watcher::utils::compute_hash,
+    };
+    use std::{
+        path::PathBuf,
+        sync::atomic::{AtomicU32, Ordering},
+        time::Duration,
+    };
+    use tokio_stream::StreamExt;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("libconfmon-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Builds a watcher over a fake pfSense tree, whose state is read from the `.dirty` markers
+    /// so no command dumping the host ruleset is run.
+    async fn watcher<H: WatcherHandler>(fake: &FakePlatform, handler: H) -> Watcher<H> {
+        Watcher::new_with_root(fake.platform(), 10, handler, fake.root())
+            .await
+            .unwrap()
+    }
+
+    /// Waits for the next event matching `filter`, failing after a few seconds.
+    async fn next_event<T>(
+        events: &mut WatchEventStream,
+        filter: impl Fn(WatchEvent) -> Option<T>,
+    ) -> T {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while let Some(event) = events.next().await {
+                if let Some(value) = filter(event) {
+                    return value;
+                }
+            }
+            panic!("event stream closed");
+        })
+        .await
+        .expect("timed out waiting for an event")
+    }
+
+    /// Waits for the next snapshot to be dispatched to the handler.
+    async fn next_snapshot(events: &mut WatchEventStream) {
+        next_event(events, |event| {
+            matches!(event, WatchEvent::SnapshotReady(..)).then_some(())
+        })
+        .await
+    }
+
+    /// A handler failing a given number of times before accepting snapshots.
+    struct FlakyHandler {
+        failures: AtomicU32,
+        accepted: AtomicU32,
+    }
+
+    impl WatcherHandler for FlakyHandler {
+        async fn on_snapshot(&self, _snapshot: Snapshot, _state: State) -> Result<(), Error> {
+            if self.failures.load(Ordering::SeqCst) > 0 {
+                self.failures.fetch_sub(1, Ordering::SeqCst);
+                return Err(Error {
+                    kind: ErrorKind::ErrorWatchingFile,
+                    message: String::from("server unavailable"),
+                });
+            }
+
+            self.accepted.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn on_error(&self, _error: Error) {}
+    }
+
+    #[tokio::test]
+    async fn test_spawned_watcher_emits_events_and_stops() {
+        let fake = FakePlatform::new(Platform::PfSense).unwrap();
+
+        let mut watcher = watcher(&fake, NoopHandler).await;
+
+        let mut events = watcher.subscribe();
+        let handle = watcher.spawn();
+
+        fake.write_config("<pfsense><system/></pfsense>").unwrap();
+
+        let filename = next_event(&mut events, |event| match event {
+            WatchEvent::FileChanged(filename) => Some(filename),
+            _ => None,
+        })
+        .await;
+
+        assert_eq!(filename, "config.xml");
+
+        let watcher = handle.shutdown().await.unwrap();
+        assert_eq!(
+            watcher.files[0].hash,
+            compute_hash(b"<pfsense><system/></pfsense>")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_dispatch_stays_pending_and_is_retried() {
+        let fake = FakePlatform::new(Platform::PfSense).unwrap();
+        let pending_path = fake.root().join("pending");
+
+        let handler = FlakyHandler {
+            failures: AtomicU32::new(2),
+            accepted: AtomicU32::new(0),
+        };
+
+        let policy = DispatchPolicy {
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(20),
+            pending_path: Some(pending_path.clone()),
+        };
+
+        let mut watcher = watcher(&fake, handler)
+            .await
+            .with_dispatch_policy(policy.clone());
+
+        watcher.mark_pending().await;
+        watcher.dispatch_pending().await;
+        assert_eq!(watcher.pending().unwrap().attempts, 1);
+        assert!(watcher.retry_at.is_some());
+
+        // A newer change is coalesced into the pending one
+        watcher.mark_pending().await;
+        assert_eq!(watcher.pending().unwrap().attempts, 1);
+
+        // The pending change survives a restart
+        let restarted = self::watcher(&fake, NoopHandler)
+            .await
+            .with_dispatch_policy(policy.clone());
+        assert_eq!(restarted.pending(), watcher.pending());
+
+        let mut events = watcher.subscribe();
+        let handle = watcher.spawn();
+
+        // The first retry fails, the second one is accepted
+        next_snapshot(&mut events).await;
+        next_event(&mut events, |event| {
+            matches!(event, WatchEvent::Error(_)).then_some(())
+        })
+        .await;
+        next_snapshot(&mut events).await;
+
+        // A dispatch in progress is always completed before the watcher stops
+        let watcher = handle.shutdown().await.unwrap();
+
+        assert!(watcher.pending().is_none());
+        assert_eq!(watcher.handler.accepted.load(Ordering::SeqCst), 1);
+        assert!(!pending_path.exists());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_trigger_dispatches_without_file_change() {
+        let fake = FakePlatform::new(Platform::PfSense).unwrap();
+
+        let handler = FlakyHandler {
+            failures: AtomicU32::new(0),
+            accepted: AtomicU32::new(0),
+        };
+
+        let mut watcher = watcher(&fake, handler).await;
+        watcher.poll_interval = 60_000;
+
+        // Requests made before the watcher gets to them are coalesced
+        let trigger = watcher.trigger();
+        trigger.fire();
+        trigger.fire();
+
+        // The clock is paused: it only advances once the watcher is idle, waiting for the next poll
+        watcher
+            .watch_until(tokio::time::sleep(Duration::from_secs(1)))
+            .await;
+
+        assert_eq!(watcher.handler.accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_missing_ruleset_is_picked_up_once_created() {
+        let root = temp_dir("watcher-missing");
+
+        let mut watcher = Watcher::new_with_root(Platform::Linux, 10, NoopHandler, &root)
+            .await
+            .unwrap();
+
+        assert_eq!(watcher.files.len(), 3);
+        assert!(
+            watcher
+                .files
+                .iter()
+                .all(|file| file.hash == compute_hash(b""))
+        );
+        assert!(!watcher.check_files_for_changes().await);
+
+        std::fs::create_dir_all(root.join("etc")).unwrap();
+        std::fs::write(root.join("etc").join("nftables.conf"), "flush ruleset\n").unwrap();
+
+        assert!(watcher.check_files_for_changes().await);
+        assert_eq!(watcher.files[0].hash, compute_hash(b"flush ruleset\n"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_includes_synthesized_entries() {
+        let fake = FakePlatform::new(Platform::PfSense).unwrap();
+
+        let watcher =
+            watcher(&fake, NoopHandler)
+                .await
+                .with_synthesized_entries(SynthesizedEntries {
+                    network_interfaces: true,
+                    state: true,
+                    ..Default::default()
+                });
+
+        let snapshot = watcher.snapshot().await.unwrap();
+        let names: Vec<&str> = snapshot.iter().map(|file| file.filename.as_str()).collect();
+
+        assert_eq!(names, vec!["config.xml", "#NetworkInterfaces", "#State"]);
+        assert!(crate::InterfaceSnapshot::deserialize_snapshot(&snapshot[1].content).is_ok());
     }
 }
@@ -1,4 +1,9 @@
+pub mod dispatch;
+pub mod dynamic;
+pub mod events;
+pub mod fanout;
+pub mod handle;
 pub mod r#impl;
 pub mod types;
 
-mod utils;
+pub(crate) mod utils;
@@ -1,3 +1,4 @@
+use super::utils::compute_hash;
 use std::path::PathBuf;
 
 /// Represents the data of a file, including its name and content.
@@ -5,22 +6,49 @@ use std::path::PathBuf;
 /// # Fields
 /// - `filename`: The name of the file.
 /// - `content`: The binary content of the file, stored as a vector of bytes.
-#[derive(Debug)]
+/// - `hash`: The hex-encoded SHA-256 digest of `content`.
+/// - `size`: The size of `content` in bytes.
+/// - `mtime`: The modification time of the file, represented as the number of milliseconds
+///   since the UNIX epoch, or `0` if the entry does not come from a file on disk.
+#[derive(Debug, Clone)]
 pub struct FileData {
     pub filename: String,
     pub content: Vec<u8>,
+    pub hash: String,
+    pub size: u64,
+    pub mtime: u128,
+}
+
+impl FileData {
+    /// Creates a new `FileData`, computing the hash and size of the content.
+    ///
+    /// # Parameters
+    /// - `filename`: The name of the file.
+    /// - `content`: The binary content of the file.
+    /// - `mtime`: The modification time of the file in milliseconds since the UNIX epoch.
+    pub fn new(filename: impl Into<String>, content: Vec<u8>, mtime: u128) -> Self {
+        Self {
+            filename: filename.into(),
+            hash: compute_hash(&content),
+            size: content.len() as u64,
+            content,
+            mtime,
+        }
+    }
 }
 
-/// Contains metadata about a file, including its path and modification time.
+/// Contains metadata about a file, including its path, modification time and content hash.
 ///
 /// # Fields
 /// - `path`: The filesystem path to the file.
 /// - `mtime`: The modification time of the file, represented as the number of milliseconds
-///            since the UNIX epoch.
+///   since the UNIX epoch.
+/// - `hash`: The hex-encoded SHA-256 digest of the file content.
 #[derive(Debug)]
 pub struct FileInfo {
     pub path: PathBuf,
     pub mtime: u128,
+    pub hash: String,
 }
 
 /// A snapshot representing a collection of file data.
@@ -28,3 +56,54 @@ pub struct FileInfo {
 /// This is used to store the current state of multiple files, where each file
 /// is represented by its `FileData`.
 pub type Snapshot = Vec<FileData>;
+
+/// Computes a digest identifying the content of a whole snapshot.
+///
+/// The digest covers the name and content hash of every file, in snapshot order,
+/// so two snapshots with the same files and contents share the same digest.
+///
+/// # Parameters
+/// - `snapshot`: The snapshot to digest.
+///
+/// # Returns
+/// The hex-encoded SHA-256 digest of the snapshot.
+pub fn snapshot_digest(snapshot: &Snapshot) -> String {
+    let mut data = Vec::new();
+
+    for file in snapshot {
+        data.extend_from_slice(file.filename.as_bytes());
+        data.push(0);
+        data.extend_from_slice(file.hash.as_bytes());
+        data.push(0);
+    }
+
+    compute_hash(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileData, snapshot_digest};
+
+    #[test]
+    fn test_file_data_new_computes_hash_and_size() {
+        let file = FileData::new("config.xml", b"hello".to_vec(), 42);
+
+        assert_eq!(file.filename, "config.xml");
+        assert_eq!(file.size, 5);
+        assert_eq!(file.mtime, 42);
+        assert_eq!(
+            file.hash,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_digest_depends_on_content_only() {
+        let a = vec![FileData::new("config.xml", b"a".to_vec(), 1)];
+        let b = vec![FileData::new("config.xml", b"a".to_vec(), 2)];
+        let c = vec![FileData::new("config.xml", b"c".to_vec(), 1)];
+
+        assert_eq!(snapshot_digest(&a), snapshot_digest(&b));
+        assert_ne!(snapshot_digest(&a), snapshot_digest(&c));
+    }
+}
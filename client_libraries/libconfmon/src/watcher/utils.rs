@@ -1,4 +1,5 @@
-use std::{path::PathBuf, time::SystemTime};
+use sha2::{Digest, Sha256};
+use std::{io, path::PathBuf, time::SystemTime};
 use tokio::fs;
 
 use crate::{Error, ErrorKind};
@@ -26,16 +27,21 @@ pub fn make_error_mapper<E: std::fmt::Display + 'static>(
 /// - `path`: A reference to a `PathBuf` representing the file path.
 ///
 /// # Returns
-/// - `Ok(u128)`: The modification time in milliseconds since the UNIX epoch if successful.
+/// - `Ok(u128)`: The modification time in milliseconds since the UNIX epoch if successful,
+///   or `0` if the file does not exist.
 /// - `Err(Error)`: An error of type `Error` if retrieving the metadata or modified time fails.
 ///
 /// # Errors
 /// - Returns an error with `ErrorKind::ErrorReadingFile` if the file metadata or modified time
 ///   cannot be read or converted.
 pub async fn get_mtime(path: &PathBuf) -> Result<u128, Error> {
-    let value = fs::metadata(path)
-        .await
-        .map_err(make_error_mapper(ErrorKind::ErrorReadingFile))?
+    let metadata = match fs::metadata(path).await {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(make_error_mapper(ErrorKind::ErrorReadingFile)(err)),
+    };
+
+    let value = metadata
         .modified()
         .map_err(make_error_mapper(ErrorKind::ErrorReadingFile))?
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -43,3 +49,48 @@ pub async fn get_mtime(path: &PathBuf) -> Result<u128, Error> {
 
     Ok(value.as_millis())
 }
+
+/// Computes the SHA-256 digest of the given data.
+///
+/// # Parameters
+/// - `data`: The bytes to hash.
+///
+/// # Returns
+/// The digest as a lowercase hex-encoded string.
+pub fn compute_hash(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Reads the content of a monitored file.
+///
+/// # Parameters
+/// - `path`: A reference to a `PathBuf` representing the file path.
+///
+/// # Returns
+/// - `Ok(Vec<u8>)`: The content of the file, or an empty content if the file does not exist.
+/// - `Err(Error)`: An error of type `Error` if the file cannot be read.
+///
+/// # Errors
+/// - Returns an error with `ErrorKind::ErrorReadingFile` if the file exists but cannot be read.
+pub async fn read_content(path: &PathBuf) -> Result<Vec<u8>, Error> {
+    match fs::read(path).await {
+        Ok(content) => Ok(content),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(make_error_mapper(ErrorKind::ErrorReadingFile)(err)),
+    }
+}
+
+/// Reads a file and computes the SHA-256 digest of its content.
+///
+/// # Parameters
+/// - `path`: A reference to a `PathBuf` representing the file path.
+///
+/// # Returns
+/// - `Ok(String)`: The hex-encoded digest of the file content (a missing file has an empty content).
+/// - `Err(Error)`: An error of type `Error` if the file cannot be read.
+///
+/// # Errors
+/// - Returns an error with `ErrorKind::ErrorReadingFile` if the file exists but cannot be read.
+pub async fn get_hash(path: &PathBuf) -> Result<String, Error> {
+    Ok(compute_hash(&read_content(path).await?))
+}
@@ -0,0 +1,138 @@
+use nullnet_libconfmon::{
+    Detector, FakePlatform, NoopHandler, Platform, REVISION_ENTRY, Revision, SnapshotHistory,
+    State, SynthesizedEntries, WatchEvent, Watcher,
+};
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+#[tokio::test]
+async fn dirty_markers() {
+    for platform in [Platform::PfSense, Platform::OPNsense] {
+        let fake = FakePlatform::new(platform).unwrap();
+
+        assert_eq!(
+            Detector::check_with_root(platform, fake.root()).await,
+            State::Applied
+        );
+
+        fake.set_dirty(true).unwrap();
+        assert_eq!(
+            Detector::check_with_root(platform, fake.root()).await,
+            State::Draft
+        );
+
+        fake.set_dirty(false).unwrap();
+        assert_eq!(
+            Detector::check_with_root(platform, fake.root()).await,
+            State::Applied
+        );
+    }
+}
+
+#[tokio::test]
+async fn watch_edits_and_apply() {
+    for (platform, root) in [
+        (Platform::PfSense, "pfsense"),
+        (Platform::OPNsense, "opnsense"),
+    ] {
+        watch_edits_and_apply_on(platform, root).await;
+    }
+}
+
+async fn watch_edits_and_apply_on(platform: Platform, root: &str) {
+    let fake = FakePlatform::new(platform).unwrap();
+    let initial = std::fs::read_to_string(fake.config_path()).unwrap();
+    assert!(initial.contains(&format!("<{root}></{root}>")));
+
+    let config = format!("<{root}><system/></{root}>");
+
+    let mut watcher = Watcher::new_with_root(platform, 10, NoopHandler, fake.root())
+        .await
+        .unwrap();
+    let mut events = watcher.subscribe();
+
+    let snapshot = watcher.snapshot().await.unwrap();
+    assert_eq!(snapshot.len(), 1);
+    assert_eq!(snapshot[0].filename, "config.xml");
+
+    let handle = watcher.spawn();
+
+    fake.write_config(&config).unwrap();
+
+    loop {
+        match tokio::time::timeout(Duration::from_secs(5), events.next()).await {
+            Ok(Some(WatchEvent::FileChanged(name))) => {
+                assert_eq!(name, "config.xml");
+                break;
+            }
+            Ok(Some(_)) => {}
+            _ => panic!("timed out waiting for the edit"),
+        }
+    }
+
+    fake.set_dirty(true).unwrap();
+
+    loop {
+        match tokio::time::timeout(Duration::from_secs(5), events.next()).await {
+            Ok(Some(WatchEvent::StateChanged {
+                to: State::Draft, ..
+            })) => break,
+            Ok(Some(_)) => {}
+            _ => panic!("timed out waiting for the draft state"),
+        }
+    }
+
+    fake.set_dirty(false).unwrap();
+
+    loop {
+        match tokio::time::timeout(Duration::from_secs(5), events.next()).await {
+            Ok(Some(WatchEvent::SnapshotReady(snapshot, State::Applied))) => {
+                assert_eq!(snapshot[0].content, config.as_bytes());
+                break;
+            }
+            Ok(Some(_)) => {}
+            _ => panic!("timed out waiting for the applied snapshot"),
+        }
+    }
+
+    handle.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn revision_attribution_and_backups() {
+    let fake = FakePlatform::new(Platform::PfSense).unwrap();
+
+    fake.write_backup(1_700_000_000, "<pfsense><revision><time>1700000000</time><username>admin</username><description>Initial setup</description></revision></pfsense>").unwrap();
+    fake.write_config("<pfsense><revision><time>1700000100</time><username>admin@10.0.0.5</username><description>Firewall: Rules - saved</description></revision></pfsense>").unwrap();
+
+    let watcher = Watcher::new_with_root(Platform::PfSense, 10, NoopHandler, fake.root())
+        .await
+        .unwrap()
+        .with_synthesized_entries(SynthesizedEntries {
+            revision: true,
+            ..Default::default()
+        });
+
+    let snapshot = watcher.snapshot().await.unwrap();
+    assert_eq!(snapshot[1].filename, REVISION_ENTRY);
+    assert_eq!(
+        Revision::from_snapshot(&snapshot),
+        Some(Revision {
+            time: 1_700_000_100_000,
+            username: String::from("admin@10.0.0.5"),
+            description: String::from("Firewall: Rules - saved"),
+        })
+    );
+
+    let backups = watcher.backups().await.unwrap();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(
+        Revision::from_snapshot(&backups[0]).unwrap().description,
+        "Initial setup"
+    );
+
+    let store = fake.root().join("history");
+    let mut history = SnapshotHistory::open(&store, 10).await.unwrap();
+    assert_eq!(history.import(&backups).await.unwrap(), vec![1]);
+    assert_eq!(history.latest().unwrap().timestamp, 1_700_000_000_000);
+}
@@ -0,0 +1,738 @@
+use std::{
+    collections::HashSet,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use crate::{Address, Alias, Configuration, Endpoint, FireparseError, NetworkInterface, PortSpec};
+
+/// Separator of the per-member descriptions in pfSense's `<detail>`.
+const DETAIL_SEPARATOR: &str = "||";
+/// Placeholder stored by the parsers for aliases without a value.
+const EMPTY_VALUE: &str = "None";
+
+/// The type of an alias, as found in `Alias.r#type`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AliasKind {
+    Host,
+    /// `network`, or OPNsense's `networkgroup`.
+    Network,
+    Port,
+    /// `url`, or pfSense's `url_ports`.
+    Url,
+    /// `urltable`, or pfSense's `urltable_ports`.
+    UrlTable,
+    GeoIp,
+    /// Any other type, kept as written (e.g. OPNsense's `mac` or `asn`).
+    Other(String),
+}
+
+impl From<&str> for AliasKind {
+    fn from(value: &str) -> Self {
+        match value {
+            "host" => AliasKind::Host,
+            "network" | "networkgroup" => AliasKind::Network,
+            "port" => AliasKind::Port,
+            "url" | "url_ports" => AliasKind::Url,
+            "urltable" | "urltable_ports" => AliasKind::UrlTable,
+            "geoip" => AliasKind::GeoIp,
+            other => AliasKind::Other(other.to_string()),
+        }
+    }
+}
+
+/// An inclusive range of addresses of a single IP version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpRange {
+    pub start: IpAddr,
+    pub end: IpAddr,
+}
+
+impl IpRange {
+    /// Returns the range holding a single address.
+    pub fn single(ip: IpAddr) -> IpRange {
+        IpRange { start: ip, end: ip }
+    }
+
+    /// Returns the range covered by a network, e.g. `10.0.0.0-10.0.0.255` for `10.0.0.1/24`.
+    ///
+    /// # Returns
+    /// The `IpRange` of the network, or `None` if the prefix is too long for the IP version.
+    pub fn from_cidr(ip: IpAddr, prefix: u8) -> Option<IpRange> {
+        match ip {
+            IpAddr::V4(ip) if prefix <= 32 => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+                let start = u32::from(ip) & mask;
+
+                Some(IpRange {
+                    start: IpAddr::V4(Ipv4Addr::from(start)),
+                    end: IpAddr::V4(Ipv4Addr::from(start | !mask)),
+                })
+            }
+            IpAddr::V6(ip) if prefix <= 128 => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+                let start = u128::from(ip) & mask;
+
+                Some(IpRange {
+                    start: IpAddr::V6(Ipv6Addr::from(start)),
+                    end: IpAddr::V6(Ipv6Addr::from(start | !mask)),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the ranges matched by an address, or `None` if it does not stand for concrete addresses.
+    fn from_address(address: &Address) -> Option<Vec<IpRange>> {
+        match address {
+            Address::Ip(ip) => Some(vec![IpRange::single(*ip)]),
+            Address::Cidr(ip, prefix) => IpRange::from_cidr(*ip, *prefix).map(|range| vec![range]),
+            Address::Range(start, end) if start.is_ipv4() == end.is_ipv4() && start <= end => {
+                Some(vec![IpRange {
+                    start: *start,
+                    end: *end,
+                }])
+            }
+            Address::List(addresses) => addresses
+                .iter()
+                .map(IpRange::from_address)
+                .collect::<Option<Vec<_>>>()
+                .map(|ranges| ranges.concat()),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if `ip` is part of the range.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.start <= *ip && *ip <= self.end
+    }
+
+    /// Returns `true` if every address of `other` is part of the range.
+    pub fn contains_range(&self, other: &IpRange) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Returns `true` if both ranges have an address in common.
+    pub fn overlaps(&self, other: &IpRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+/// An inclusive range of ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PortRange {
+    /// Returns the ranges matched by a port specification, or `None` if it does not stand for concrete ports.
+    fn from_spec(port: &PortSpec) -> Option<Vec<PortRange>> {
+        match port {
+            PortSpec::Single(port) => Some(vec![PortRange {
+                start: *port,
+                end: *port,
+            }]),
+            PortSpec::Range(start, end) if start <= end => Some(vec![PortRange {
+                start: *start,
+                end: *end,
+            }]),
+            PortSpec::List(ports) => ports
+                .iter()
+                .map(PortRange::from_spec)
+                .collect::<Option<Vec<_>>>()
+                .map(|ranges| ranges.concat()),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if `port` is part of the range.
+    pub fn contains(&self, port: u16) -> bool {
+        self.start <= port && port <= self.end
+    }
+
+    /// Returns `true` if every port of `other` is part of the range.
+    pub fn contains_range(&self, other: &PortRange) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Returns `true` if both ranges have a port in common.
+    pub fn overlaps(&self, other: &PortRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+/// A member of an alias, as written in `Alias.value`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AliasValue {
+    Addresses(IpRange),
+    Ports(PortRange),
+    /// A reference to another alias.
+    Alias(String),
+    /// A host name, resolved by the firewall at runtime.
+    Hostname(String),
+    /// The URL of a list, fetched by the firewall at runtime.
+    Url(String),
+    /// A country code of a GeoIP alias.
+    Country(String),
+    /// A value that cannot be interpreted for the type of the alias.
+    Unknown(String),
+}
+
+/// A value along with the alias it comes from and the descriptions attached to it.
+///
+/// # Fields
+/// - `value`: The value.
+/// - `alias`: The name of the alias defining the value, or `None` if it is written in the rule itself.
+/// - `description`: The description of that alias.
+/// - `detail`: The description of the value itself, from pfSense's `<detail>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Member<T> {
+    pub value: T,
+    pub alias: Option<String>,
+    pub description: String,
+    pub detail: String,
+}
+
+impl<T> Member<T> {
+    /// Wraps a value written in the rule itself.
+    fn literal(value: T) -> Member<T> {
+        Member {
+            value,
+            alias: None,
+            description: String::new(),
+            detail: String::new(),
+        }
+    }
+
+    /// Replaces the value, keeping where it comes from.
+    fn with<U>(&self, value: U) -> Member<U> {
+        Member {
+            value,
+            alias: self.alias.clone(),
+            description: self.description.clone(),
+            detail: self.detail.clone(),
+        }
+    }
+}
+
+/// A node of the alias graph.
+///
+/// # Fields
+/// - `name`: The name of the alias.
+/// - `kind`: The type of the alias.
+/// - `description`: The description of the alias.
+/// - `members`: The members of the alias, in order, each with its own description.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AliasNode {
+    pub name: String,
+    pub kind: AliasKind,
+    pub description: String,
+    pub members: Vec<Member<AliasValue>>,
+}
+
+/// A set of addresses an endpoint expands to.
+///
+/// # Fields
+/// - `any`: Whether the set holds every address.
+/// - `ranges`: The concrete addresses of the set.
+/// - `unresolved`: The members that cannot be expanded without the firewall (host names, URLs,
+///   countries, interfaces without a known address) or that are not addresses.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct IpSet {
+    pub any: bool,
+    pub ranges: Vec<Member<IpRange>>,
+    pub unresolved: Vec<Member<String>>,
+}
+
+impl IpSet {
+    /// Returns `true` if `ip` is part of the set. Unresolved members never match.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.any || self.ranges.iter().any(|range| range.value.contains(ip))
+    }
+}
+
+/// A set of ports an endpoint expands to.
+///
+/// # Fields
+/// - `any`: Whether the set holds every port.
+/// - `ranges`: The concrete ports of the set.
+/// - `unresolved`: The members that are not ports (e.g. unknown service names).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PortSet {
+    pub any: bool,
+    pub ranges: Vec<Member<PortRange>>,
+    pub unresolved: Vec<Member<String>>,
+}
+
+impl PortSet {
+    /// Returns `true` if `port` is part of the set. Unresolved members never match.
+    pub fn contains(&self, port: u16) -> bool {
+        self.any || self.ranges.iter().any(|range| range.value.contains(port))
+    }
+}
+
+/// A rule endpoint expanded into concrete addresses and ports.
+///
+/// # Fields
+/// - `addresses`: The addresses of the endpoint.
+/// - `ports`: The ports of the endpoint.
+/// - `negated`: Whether the endpoint matches every address but `addresses`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpandedEndpoint {
+    pub addresses: IpSet,
+    pub ports: PortSet,
+    pub negated: bool,
+}
+
+/// A resolver building the alias graph of a configuration and expanding rule endpoints.
+pub struct AliasResolver {
+    aliases: Vec<AliasNode>,
+    interfaces: Vec<NetworkInterface>,
+}
+
+impl AliasResolver {
+    /// Builds the alias graph of a configuration.
+    ///
+    /// # Arguments
+    /// * `configuration` - The configuration holding the aliases, and the interfaces
+    ///   used to expand interface keywords such as `lan`, `lanip` and `(self)`.
+    ///
+    /// # Returns
+    /// * `Ok(AliasResolver)` - The resolver.
+    /// * `Err(FireparseError)` - If aliases reference each other in a cycle.
+    pub fn new(configuration: &Configuration) -> Result<AliasResolver, FireparseError> {
+        let names: HashSet<&str> = configuration
+            .aliases
+            .iter()
+            .map(|alias| alias.name.as_str())
+            .collect();
+
+        let resolver = AliasResolver {
+            aliases: configuration
+                .aliases
+                .iter()
+                .map(|alias| Self::build_node(alias, &names))
+                .collect(),
+            interfaces: configuration.interfaces.clone(),
+        };
+
+        if let Some(cycle) = resolver.find_cycle() {
+            return Err(FireparseError::ResolverError(format!(
+                "AliasResolver: aliases reference each other in a cycle: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        Ok(resolver)
+    }
+
+    /// Returns the nodes of the alias graph, in the order of the configuration.
+    pub fn aliases(&self) -> &[AliasNode] {
+        &self.aliases
+    }
+
+    /// Looks up an alias by name.
+    pub fn alias(&self, name: &str) -> Option<&AliasNode> {
+        self.aliases.iter().find(|alias| alias.name == name)
+    }
+
+    /// Expands a rule endpoint into concrete addresses and ports.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The endpoint to expand, e.g. `TypedRule.source`.
+    ///
+    /// # Returns
+    /// An `ExpandedEndpoint`. Nested aliases are flattened, each value keeping the alias it comes
+    /// from along with its descriptions.
+    pub fn expand(&self, endpoint: &Endpoint) -> ExpandedEndpoint {
+        ExpandedEndpoint {
+            addresses: self.expand_address(&endpoint.address),
+            ports: self.expand_port(&endpoint.port),
+            negated: endpoint.negated,
+        }
+    }
+
+    /// Expands the address of an endpoint into a set of addresses.
+    pub fn expand_address(&self, address: &Address) -> IpSet {
+        let mut set = IpSet::default();
+
+        if let Some(ranges) = IpRange::from_address(address) {
+            set.ranges = ranges.into_iter().map(Member::literal).collect();
+            return set;
+        }
+
+        match address {
+            Address::Any => set.any = true,
+            Address::Network(name) => match self.interface_addresses(name, true) {
+                Some(ranges) => set.ranges = ranges.into_iter().map(Member::literal).collect(),
+                None => set.unresolved.push(Member::literal(name.clone())),
+            },
+            Address::InterfaceAddress(name) => match self.interface_addresses(name, false) {
+                Some(ranges) => set.ranges = ranges.into_iter().map(Member::literal).collect(),
+                None => set.unresolved.push(Member::literal(address.to_string())),
+            },
+            Address::Firewall => {
+                for interface in &self.interfaces {
+                    if let Some(ranges) = self.interface_addresses(&interface.name, false) {
+                        set.ranges.extend(ranges.into_iter().map(Member::literal));
+                    }
+                }
+            }
+            Address::Alias(name) => match self.alias(name) {
+                Some(alias) => self.collect(alias, &mut set, &mut PortSet::default()),
+                None => set.unresolved.push(Member::literal(name.clone())),
+            },
+            _ => set.unresolved.push(Member::literal(address.to_string())),
+        }
+
+        set
+    }
+
+    /// Expands the port of an endpoint into a set of ports.
+    pub fn expand_port(&self, port: &PortSpec) -> PortSet {
+        let mut set = PortSet::default();
+
+        if let Some(ranges) = PortRange::from_spec(port) {
+            set.ranges = ranges.into_iter().map(Member::literal).collect();
+            return set;
+        }
+
+        match port {
+            PortSpec::Any => set.any = true,
+            PortSpec::Alias(name) => match self.alias(name) {
+                Some(alias) => self.collect(alias, &mut IpSet::default(), &mut set),
+                None => set.unresolved.push(Member::literal(name.clone())),
+            },
+            _ => set.unresolved.push(Member::literal(port.to_string())),
+        }
+
+        set
+    }
+
+    /// Adds the members of an alias, and of the aliases it references, to the sets
+    /// matching the type of the alias.
+    fn collect(&self, alias: &AliasNode, addresses: &mut IpSet, ports: &mut PortSet) {
+        let is_port = alias.kind == AliasKind::Port;
+
+        for member in &alias.members {
+            match &member.value {
+                AliasValue::Addresses(range) if !is_port => {
+                    addresses.ranges.push(member.with(*range));
+                }
+                AliasValue::Ports(range) if is_port => ports.ranges.push(member.with(*range)),
+                AliasValue::Alias(name) => {
+                    if let Some(nested) = self.alias(name) {
+                        self.collect(nested, addresses, ports);
+                    }
+                }
+                AliasValue::Addresses(_) | AliasValue::Ports(_) => {}
+                AliasValue::Hostname(value)
+                | AliasValue::Url(value)
+                | AliasValue::Country(value)
+                | AliasValue::Unknown(value) => {
+                    let unresolved = member.with(value.clone());
+
+                    match is_port {
+                        true => ports.unresolved.push(unresolved),
+                        false => addresses.unresolved.push(unresolved),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the subnets (or the addresses) of an interface, looked up by name then by device.
+    ///
+    /// # Returns
+    /// The ranges, or `None` if the interface is unknown or has no usable address.
+    fn interface_addresses(&self, name: &str, subnet: bool) -> Option<Vec<IpRange>> {
+        let interface = self
+            .interfaces
+            .iter()
+            .find(|interface| interface.name == name)
+            .or_else(|| {
+                self.interfaces
+                    .iter()
+                    .find(|interface| interface.device == name)
+            })?;
+
+        let ranges: Vec<IpRange> = interface
+            .addresses
+            .iter()
+            .filter_map(|address| {
+                let (ip, prefix) = match address.address.split_once('/') {
+                    Some((ip, prefix)) => (ip.parse().ok()?, Some(prefix.parse().ok()?)),
+                    None => (address.address.parse().ok()?, None),
+                };
+
+                match (subnet, prefix) {
+                    (true, Some(prefix)) => IpRange::from_cidr(ip, prefix),
+                    (true, None) => None,
+                    (false, _) => Some(IpRange::single(ip)),
+                }
+            })
+            .collect();
+
+        (!ranges.is_empty()).then_some(ranges)
+    }
+
+    /// Builds the node of an alias, interpreting its members according to its type.
+    fn build_node(alias: &Alias, names: &HashSet<&str>) -> AliasNode {
+        let kind = AliasKind::from(alias.r#type.as_str());
+
+        let details: Vec<&str> = match alias.detail.is_empty() {
+            true => vec![],
+            false => alias.detail.split(DETAIL_SEPARATOR).collect(),
+        };
+
+        let members = alias
+            .value
+            .split_whitespace()
+            .filter(|value| *value != EMPTY_VALUE)
+            .enumerate()
+            .map(|(index, value)| Member {
+                value: Self::parse_value(&kind, value, names),
+                alias: Some(alias.name.clone()),
+                description: alias.description.clone(),
+                detail: details.get(index).unwrap_or(&"").trim().to_string(),
+            })
+            .collect();
+
+        AliasNode {
+            name: alias.name.clone(),
+            kind,
+            description: alias.description.clone(),
+            members,
+        }
+    }
+
+    /// Interprets a member of an alias of the given type.
+    fn parse_value(kind: &AliasKind, value: &str, names: &HashSet<&str>) -> AliasValue {
+        match kind {
+            AliasKind::Url | AliasKind::UrlTable => return AliasValue::Url(value.to_string()),
+            AliasKind::GeoIp => return AliasValue::Country(value.to_string()),
+            _ => {}
+        }
+
+        if names.contains(value) {
+            return AliasValue::Alias(value.to_string());
+        }
+
+        match kind {
+            AliasKind::Port => match PortRange::from_spec(&PortSpec::from(value)).as_deref() {
+                Some([range]) => AliasValue::Ports(*range),
+                _ => AliasValue::Unknown(value.to_string()),
+            },
+            AliasKind::Host | AliasKind::Network => {
+                match IpRange::from_address(&Address::parse(value, "address")).as_deref() {
+                    Some([range]) => AliasValue::Addresses(*range),
+                    _ if kind == &AliasKind::Host => AliasValue::Hostname(value.to_string()),
+                    _ => AliasValue::Unknown(value.to_string()),
+                }
+            }
+            _ => AliasValue::Unknown(value.to_string()),
+        }
+    }
+
+    /// Looks for aliases referencing each other in a cycle.
+    ///
+    /// # Returns
+    /// The names of the aliases forming the cycle, starting and ending with the same alias,
+    /// or `None` if the graph is acyclic.
+    fn find_cycle(&self) -> Option<Vec<String>> {
+        let mut done = HashSet::new();
+
+        for alias in &self.aliases {
+            let mut path = vec![];
+
+            if let Some(cycle) = self.visit(alias, &mut path, &mut done) {
+                return Some(cycle);
+            }
+        }
+
+        None
+    }
+
+    /// Walks the references of an alias depth-first, `path` holding the aliases being visited.
+    fn visit<'a>(
+        &'a self,
+        alias: &'a AliasNode,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Option<Vec<String>> {
+        if done.contains(alias.name.as_str()) {
+            return None;
+        }
+
+        if let Some(start) = path.iter().position(|name| *name == alias.name) {
+            let mut cycle: Vec<String> = path[start..].iter().map(ToString::to_string).collect();
+            cycle.push(alias.name.clone());
+            return Some(cycle);
+        }
+
+        path.push(&alias.name);
+
+        for member in &alias.members {
+            if let AliasValue::Alias(name) = &member.value
+                && let Some(nested) = self.alias(name)
+                && let Some(cycle) = self.visit(nested, path, done)
+            {
+                return Some(cycle);
+            }
+        }
+
+        path.pop();
+        done.insert(&alias.name);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AliasResolver, AliasValue, IpRange, PortRange};
+    use crate::{
+        Address, Alias, Configuration, Endpoint, PortSpec,
+        test_utils::{self, lan},
+    };
+    use std::net::IpAddr;
+
+    fn alias(r#type: &str, name: &str, value: &str, detail: &str) -> Alias {
+        Alias {
+            r#type: String::from(r#type),
+            name: String::from(name),
+            value: String::from(value),
+            description: format!("{name} description"),
+            detail: String::from(detail),
+        }
+    }
+
+    fn configuration(aliases: Vec<Alias>) -> Configuration {
+        test_utils::configuration(vec![], aliases, vec![lan()])
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn resolver(aliases: Vec<Alias>) -> AliasResolver {
+        AliasResolver::new(&configuration(aliases)).unwrap_or_else(|_| panic!("Cycle found"))
+    }
+
+    #[test]
+    fn test_build_alias_graph() {
+        let resolver = resolver(vec![
+            alias(
+                "host",
+                "Servers",
+                "10.0.0.10 web.example.com Office",
+                "Web||Remote",
+            ),
+            alias("network", "Office", "192.168.10.0/24", ""),
+            alias("urltable", "Blocklist", "https://example.com/list.txt", ""),
+        ]);
+
+        let servers = resolver.alias("Servers").unwrap();
+        assert_eq!(servers.members.len(), 3);
+        assert_eq!(
+            servers.members[0].value,
+            AliasValue::Addresses(IpRange::single(ip("10.0.0.10")))
+        );
+        assert_eq!(servers.members[0].detail, "Web");
+        assert_eq!(
+            servers.members[1].value,
+            AliasValue::Hostname(String::from("web.example.com"))
+        );
+        assert_eq!(servers.members[1].detail, "Remote");
+        assert_eq!(
+            servers.members[2].value,
+            AliasValue::Alias(String::from("Office"))
+        );
+        assert_eq!(servers.members[2].detail, "");
+
+        assert_eq!(
+            resolver.alias("Blocklist").unwrap().members[0].value,
+            AliasValue::Url(String::from("https://example.com/list.txt"))
+        );
+    }
+
+    #[test]
+    fn test_expand_nested_aliases() {
+        let resolver = resolver(vec![
+            alias("host", "Servers", "10.0.0.10 Office", "Web"),
+            alias("network", "Office", "192.168.10.0/24 Lab", "HQ"),
+            alias("network", "Lab", "2001:db8::/64", ""),
+            alias("port", "Web", "80 8000:8080 Admin", "HTTP"),
+            alias("port", "Admin", "8443", "Console"),
+        ]);
+
+        let expanded = resolver.expand(&Endpoint::from_parts("Servers", "Web", "address", true));
+        assert!(expanded.negated);
+
+        let addresses = &expanded.addresses;
+        assert_eq!(addresses.ranges.len(), 3);
+        assert!(addresses.contains(&ip("10.0.0.10")));
+        assert!(addresses.contains(&ip("192.168.10.200")));
+        assert!(addresses.contains(&ip("2001:db8::1")));
+        assert!(!addresses.contains(&ip("10.0.0.11")));
+        assert_eq!(addresses.ranges[1].alias.as_deref(), Some("Office"));
+        assert_eq!(addresses.ranges[1].description, "Office description");
+        assert_eq!(addresses.ranges[1].detail, "HQ");
+
+        let ports = &expanded.ports;
+        assert!(ports.contains(8080));
+        assert!(ports.contains(8443));
+        assert!(!ports.contains(443));
+        assert_eq!(
+            ports.ranges[1].value,
+            PortRange {
+                start: 8000,
+                end: 8080
+            }
+        );
+        assert_eq!(ports.ranges[2].detail, "Console");
+    }
+
+    #[test]
+    fn test_expand_interface_keywords() {
+        let resolver = resolver(vec![]);
+
+        let lan = resolver.expand_address(&Address::parse("lan", "network"));
+        assert_eq!(
+            lan.ranges[0].value,
+            IpRange::from_cidr(ip("192.168.1.0"), 24).unwrap()
+        );
+
+        let lan_ip = resolver.expand_address(&Address::parse("lanip", "network"));
+        assert_eq!(lan_ip.ranges[0].value, IpRange::single(ip("192.168.1.1")));
+
+        let firewall = resolver.expand_address(&Address::Firewall);
+        assert!(firewall.contains(&ip("192.168.1.1")));
+
+        let unknown = resolver.expand_address(&Address::parse("opt1", "network"));
+        assert!(unknown.ranges.is_empty());
+        assert_eq!(unknown.unresolved[0].value, "opt1");
+
+        assert!(
+            resolver
+                .expand_address(&Address::Any)
+                .contains(&ip("8.8.8.8"))
+        );
+        assert!(resolver.expand_port(&PortSpec::Any).contains(22));
+    }
+
+    #[test]
+    fn test_detect_cycles() {
+        let configuration = configuration(vec![
+            alias("host", "A", "10.0.0.1 B", ""),
+            alias("host", "B", "C", ""),
+            alias("host", "C", "A", ""),
+        ]);
+
+        match AliasResolver::new(&configuration) {
+            Err(crate::FireparseError::ResolverError(message)) => {
+                assert!(message.ends_with("A -> B -> C -> A"));
+            }
+            _ => panic!("The cycle was not detected"),
+        }
+    }
+}
@@ -0,0 +1,517 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Alias, Configuration, NetworkInterface, Rule, SSHConfig, utils};
+
+/// Placeholder stored by the parsers for aliases without a value.
+const EMPTY_VALUE: &str = "None";
+
+/// A change between two configurations.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ConfigurationChange {
+    RuleAdded {
+        rule: Rule,
+    },
+    RuleRemoved {
+        rule: Rule,
+    },
+    /// A rule whose settings changed. `fields` lists the names of the `Rule` fields that differ.
+    RuleModified {
+        before: Rule,
+        after: Rule,
+        fields: Vec<String>,
+    },
+    /// A rule moved relative to the other rules of its type.
+    RuleReordered {
+        rule: Rule,
+        from: u64,
+        to: u64,
+    },
+    AliasAdded {
+        alias: Alias,
+    },
+    AliasRemoved {
+        alias: Alias,
+    },
+    /// An alias whose settings changed, along with the members added to and removed from its value.
+    AliasModified {
+        before: Alias,
+        after: Alias,
+        added_members: Vec<String>,
+        removed_members: Vec<String>,
+    },
+    InterfaceAdded {
+        interface: NetworkInterface,
+    },
+    InterfaceRemoved {
+        interface: NetworkInterface,
+    },
+    /// An interface whose device or addresses changed.
+    InterfaceModified {
+        before: NetworkInterface,
+        after: NetworkInterface,
+        added_addresses: Vec<String>,
+        removed_addresses: Vec<String>,
+    },
+    HostnameChanged {
+        before: String,
+        after: String,
+    },
+    SshChanged {
+        before: SSHConfig,
+        after: SSHConfig,
+    },
+    WebGuiChanged {
+        before: String,
+        after: String,
+    },
+}
+
+/// The changes between two configurations, in a stable order: rules (filter, then NAT), aliases,
+/// interfaces, and the system settings.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConfigurationDiff {
+    pub changes: Vec<ConfigurationChange>,
+}
+
+/// A differ computing the semantic changes between two configurations.
+pub struct Differ {}
+
+impl Differ {
+    /// Compares two configurations, e.g. parsed from consecutive snapshots of the same firewall.
+    ///
+    /// Rules are matched by their stable `id` when the platform records one (`<tracker>`
+    /// on pfSense, the `uuid` attribute on OPNsense), then by identical settings, then by
+    /// identical non-empty description. Matched rules whose settings differ are modified,
+    /// and matched rules that moved relative to the others are reordered (rules shifted by
+    /// insertions or removals are not). Aliases are matched by name, interfaces by name.
+    ///
+    /// # Arguments
+    /// * `before` - The earlier configuration.
+    /// * `after` - The later configuration.
+    ///
+    /// # Returns
+    /// A `ConfigurationDiff` holding the changes, empty if the configurations are equivalent.
+    pub fn diff(before: &Configuration, after: &Configuration) -> ConfigurationDiff {
+        let mut changes = vec![];
+
+        for rule_type in ["filter", "nat"] {
+            changes.append(&mut diff_rules(
+                rules_of(before, rule_type),
+                rules_of(after, rule_type),
+            ));
+        }
+
+        changes.append(&mut diff_aliases(&before.aliases, &after.aliases));
+        changes.append(&mut diff_interfaces(&before.interfaces, &after.interfaces));
+
+        if before.hostname != after.hostname {
+            changes.push(ConfigurationChange::HostnameChanged {
+                before: before.hostname.clone(),
+                after: after.hostname.clone(),
+            });
+        }
+
+        if before.ssh != after.ssh {
+            changes.push(ConfigurationChange::SshChanged {
+                before: before.ssh.clone(),
+                after: after.ssh.clone(),
+            });
+        }
+
+        if before.gui_protocol != after.gui_protocol {
+            changes.push(ConfigurationChange::WebGuiChanged {
+                before: before.gui_protocol.clone(),
+                after: after.gui_protocol.clone(),
+            });
+        }
+
+        ConfigurationDiff { changes }
+    }
+}
+
+/// Returns the rules of a type, by order.
+fn rules_of<'a>(configuration: &'a Configuration, rule_type: &str) -> Vec<&'a Rule> {
+    let mut rules: Vec<&Rule> = configuration
+        .rules
+        .iter()
+        .filter(|rule| rule.r#type == rule_type)
+        .collect();
+
+    rules.sort_by_key(|rule| rule.order);
+    rules
+}
+
+/// Computes the changes between two lists of rules of the same type.
+fn diff_rules(before: Vec<&Rule>, after: Vec<&Rule>) -> Vec<ConfigurationChange> {
+    // For each rule of `after`, the index of the matching rule of `before`
+    let matches = utils::match_rules(
+        &before,
+        &after,
+        &[
+            &utils::same_rule_id,
+            &|a, b| changed_fields(a, b).is_empty(),
+            &|a, b| !a.description.is_empty() && a.description == b.description,
+        ],
+    );
+
+    let mut matched = vec![false; before.len()];
+    for position in matches.iter().flatten() {
+        matched[*position] = true;
+    }
+
+    let mut changes: Vec<ConfigurationChange> = before
+        .iter()
+        .zip(&matched)
+        .filter(|(_, matched)| !**matched)
+        .map(|(rule, _)| ConfigurationChange::RuleRemoved {
+            rule: (*rule).clone(),
+        })
+        .collect();
+
+    let in_place = longest_increasing(&matches.iter().flatten().copied().collect::<Vec<_>>());
+
+    for (index, rule) in after.iter().enumerate() {
+        let Some(position) = matches[index] else {
+            changes.push(ConfigurationChange::RuleAdded {
+                rule: (*rule).clone(),
+            });
+            continue;
+        };
+
+        let original = before[position];
+        let fields = changed_fields(original, rule);
+
+        if !fields.is_empty() {
+            changes.push(ConfigurationChange::RuleModified {
+                before: original.clone(),
+                after: (*rule).clone(),
+                fields: fields.into_iter().map(String::from).collect(),
+            });
+        }
+
+        if !in_place.contains(&position) {
+            changes.push(ConfigurationChange::RuleReordered {
+                rule: (*rule).clone(),
+                from: original.order,
+                to: rule.order,
+            });
+        }
+    }
+
+    changes
+}
+
+/// Lists the settings of two rules that differ, ignoring their order.
+fn changed_fields(a: &Rule, b: &Rule) -> Vec<&'static str> {
+    let fields = [
+        ("disabled", a.disabled != b.disabled),
+        ("policy", a.policy != b.policy),
+        ("protocol", a.protocol != b.protocol),
+        ("interface", a.interface != b.interface),
+        ("direction", a.direction != b.direction),
+        ("source_addr", a.source_addr != b.source_addr),
+        ("source_type", a.source_type != b.source_type),
+        ("source_port", a.source_port != b.source_port),
+        ("source_inversed", a.source_inversed != b.source_inversed),
+        ("destination_addr", a.destination_addr != b.destination_addr),
+        ("destination_type", a.destination_type != b.destination_type),
+        ("destination_port", a.destination_port != b.destination_port),
+        (
+            "destination_inversed",
+            a.destination_inversed != b.destination_inversed,
+        ),
+        ("description", a.description != b.description),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(field, _)| field)
+        .collect()
+}
+
+/// Returns the values of a longest strictly increasing subsequence, i.e. the matched rules
+/// that kept their relative position.
+fn longest_increasing(values: &[usize]) -> Vec<usize> {
+    let mut lengths = vec![1; values.len()];
+    let mut previous: Vec<Option<usize>> = vec![None; values.len()];
+
+    for i in 0..values.len() {
+        for j in 0..i {
+            if values[j] < values[i] && lengths[j] + 1 > lengths[i] {
+                lengths[i] = lengths[j] + 1;
+                previous[i] = Some(j);
+            }
+        }
+    }
+
+    let mut sequence = vec![];
+    let mut current = (0..values.len()).max_by_key(|&i| (lengths[i], std::cmp::Reverse(i)));
+
+    while let Some(index) = current {
+        sequence.push(values[index]);
+        current = previous[index];
+    }
+
+    sequence
+}
+
+/// Computes the changes between two lists of aliases, matched by name.
+fn diff_aliases(before: &[Alias], after: &[Alias]) -> Vec<ConfigurationChange> {
+    let members = |alias: &Alias| -> Vec<String> {
+        alias
+            .value
+            .split_whitespace()
+            .filter(|member| *member != EMPTY_VALUE)
+            .map(String::from)
+            .collect()
+    };
+
+    let mut changes: Vec<ConfigurationChange> = before
+        .iter()
+        .filter(|alias| !after.iter().any(|a| a.name == alias.name))
+        .map(|alias| ConfigurationChange::AliasRemoved {
+            alias: alias.clone(),
+        })
+        .collect();
+
+    for alias in after {
+        match before.iter().find(|a| a.name == alias.name) {
+            None => changes.push(ConfigurationChange::AliasAdded {
+                alias: alias.clone(),
+            }),
+            Some(original) if original != alias => {
+                let (old, new) = (members(original), members(alias));
+
+                changes.push(ConfigurationChange::AliasModified {
+                    before: original.clone(),
+                    after: alias.clone(),
+                    added_members: new.iter().filter(|m| !old.contains(m)).cloned().collect(),
+                    removed_members: old.iter().filter(|m| !new.contains(m)).cloned().collect(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    changes
+}
+
+/// Computes the changes between two lists of interfaces, matched by name.
+fn diff_interfaces(
+    before: &[NetworkInterface],
+    after: &[NetworkInterface],
+) -> Vec<ConfigurationChange> {
+    let addresses = |interface: &NetworkInterface| -> Vec<String> {
+        interface
+            .addresses
+            .iter()
+            .map(|address| address.address.clone())
+            .collect()
+    };
+
+    let mut changes: Vec<ConfigurationChange> = before
+        .iter()
+        .filter(|interface| !after.iter().any(|i| i.name == interface.name))
+        .map(|interface| ConfigurationChange::InterfaceRemoved {
+            interface: interface.clone(),
+        })
+        .collect();
+
+    for interface in after {
+        match before.iter().find(|i| i.name == interface.name) {
+            None => changes.push(ConfigurationChange::InterfaceAdded {
+                interface: interface.clone(),
+            }),
+            Some(original) if original != interface => {
+                let (old, new) = (addresses(original), addresses(interface));
+
+                changes.push(ConfigurationChange::InterfaceModified {
+                    before: original.clone(),
+                    after: interface.clone(),
+                    added_addresses: new.iter().filter(|a| !old.contains(a)).cloned().collect(),
+                    removed_addresses: old.iter().filter(|a| !new.contains(a)).cloned().collect(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigurationChange, Differ};
+    use crate::{
+        Configuration, Rule,
+        test_utils::{self, alias, lan},
+    };
+
+    fn rule(order: u64, policy: &str, description: &str) -> Rule {
+        let mut rule = test_utils::rule(order, policy, "IPv4/tcp", description);
+        rule.destination_port = String::from(match order {
+            0 => "22",
+            1 => "80",
+            2 => "443",
+            _ => "53",
+        });
+        rule
+    }
+
+    fn configuration(rules: Vec<Rule>) -> Configuration {
+        test_utils::configuration(
+            rules,
+            vec![alias("host", "Servers", "10.0.0.10 10.0.0.11", "")],
+            vec![lan()],
+        )
+    }
+
+    fn summary(changes: &[ConfigurationChange]) -> Vec<String> {
+        changes
+            .iter()
+            .map(|change| match change {
+                ConfigurationChange::RuleAdded { rule } => format!("added #{}", rule.order),
+                ConfigurationChange::RuleRemoved { rule } => format!("removed #{}", rule.order),
+                ConfigurationChange::RuleModified {
+                    before,
+                    after,
+                    fields,
+                } => format!(
+                    "modified #{} -> #{} {}",
+                    before.order,
+                    after.order,
+                    fields.join(",")
+                ),
+                ConfigurationChange::RuleReordered { from, to, .. } => {
+                    format!("reordered #{from} -> #{to}")
+                }
+                other => format!("{other:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_rules_by_content() {
+        let before = configuration(vec![
+            rule(0, "pass", "SSH"),
+            rule(1, "pass", "HTTP"),
+            rule(2, "pass", "HTTPS"),
+            rule(3, "pass", "DNS"),
+        ]);
+
+        // SSH moves to the bottom, HTTP is blocked, HTTPS is removed and a rule is inserted at the top
+        let mut ssh = rule(0, "pass", "SSH");
+        ssh.order = 4;
+        let mut http = rule(1, "block", "HTTP");
+        http.order = 1;
+        let mut dns = rule(3, "pass", "DNS");
+        dns.order = 2;
+        let mut new = rule(3, "pass", "");
+        new.order = 0;
+        new.destination_port = String::from("123");
+
+        let after = configuration(vec![new, http, dns, ssh]);
+
+        assert_eq!(
+            summary(&Differ::diff(&before, &after).changes),
+            vec![
+                "removed #2",
+                "added #0",
+                "modified #1 -> #1 policy",
+                "reordered #0 -> #4",
+            ]
+        );
+
+        assert!(Differ::diff(&before, &before).changes.is_empty());
+    }
+
+    #[test]
+    fn test_diff_rules_by_tracker() {
+        let tracked = |order, policy, description, id: &str| {
+            let mut rule = rule(order, policy, description);
+            rule.id = String::from(id);
+            rule
+        };
+
+        let before = configuration(vec![
+            tracked(0, "pass", "SSH", "100"),
+            tracked(1, "pass", "HTTP", "200"),
+        ]);
+
+        // Both rules are rewritten, the tracker tells which is which
+        let mut first = tracked(1, "block", "Web", "200");
+        first.order = 0;
+        let mut second = tracked(0, "block", "Admin", "100");
+        second.order = 1;
+
+        let after = configuration(vec![first, second]);
+
+        assert_eq!(
+            summary(&Differ::diff(&before, &after).changes),
+            vec![
+                "modified #1 -> #0 policy,description",
+                "modified #0 -> #1 policy,description",
+                "reordered #0 -> #1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_rule_direction() {
+        let floating = |order, direction: &str, description| {
+            let mut rule = rule(order, "block", description);
+            rule.direction = String::from(direction);
+            rule
+        };
+
+        let before = configuration(vec![floating(0, "in", "Floating"), floating(1, "in", "")]);
+        let after = configuration(vec![floating(0, "out", "Floating"), floating(1, "out", "")]);
+
+        // Without a description, the flipped rule is no longer identical to the previous one
+        assert_eq!(
+            summary(&Differ::diff(&before, &after).changes),
+            vec!["removed #1", "modified #0 -> #0 direction", "added #1"]
+        );
+    }
+
+    #[test]
+    fn test_diff_aliases_interfaces_and_settings() {
+        let before = configuration(vec![]);
+        let mut after = configuration(vec![]);
+
+        after.aliases[0].value = String::from("10.0.0.10 10.0.0.12");
+        after.aliases.push(alias("port", "Web", "80 443", ""));
+        after.interfaces[0].addresses[0].address = String::from("192.168.2.1/24");
+        after.ssh.enabled = true;
+        after.gui_protocol = String::from("http");
+
+        let changes = Differ::diff(&before, &after).changes;
+        assert_eq!(changes.len(), 5);
+
+        assert!(matches!(
+            &changes[0],
+            ConfigurationChange::AliasModified { added_members, removed_members, .. }
+                if added_members == &["10.0.0.12"] && removed_members == &["10.0.0.11"]
+        ));
+        assert!(matches!(
+            &changes[1],
+            ConfigurationChange::AliasAdded { alias } if alias.name == "Web"
+        ));
+        assert!(matches!(
+            &changes[2],
+            ConfigurationChange::InterfaceModified { added_addresses, removed_addresses, .. }
+                if added_addresses == &["192.168.2.1/24"] && removed_addresses == &["192.168.1.1/24"]
+        ));
+        assert!(matches!(
+            &changes[3],
+            ConfigurationChange::SshChanged { before, after } if !before.enabled && after.enabled
+        ));
+        assert!(matches!(
+            &changes[4],
+            ConfigurationChange::WebGuiChanged { after, .. } if after == "http"
+        ));
+    }
+}
@@ -0,0 +1,457 @@
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+use crate::{
+    Action, AliasResolver, Configuration, Direction, Endpoint, FireparseError, IpVersion, Member,
+    NetworkInterface, Protocol, Rule, TypedRule,
+};
+
+/// Interface values standing for every interface (e.g. rules parsed from Linux rulesets without `-i`).
+const ANY_INTERFACE_VALUES: [&str; 3] = ["none", "any", ""];
+
+/// The traffic to evaluate.
+///
+/// # Fields
+/// - `interface`: The interface the traffic goes through, by name (e.g. `lan`) or device (e.g. `em1`).
+/// - `direction`: The direction of the traffic relative to `interface`.
+/// - `ip_version`: The IP version of the traffic.
+/// - `protocol`: The transport protocol of the traffic.
+/// - `source`: The source address.
+/// - `destination`: The destination address.
+/// - `source_port`: The source port, for protocols carrying ports.
+/// - `destination_port`: The destination port, for protocols carrying ports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packet {
+    pub interface: String,
+    pub direction: Direction,
+    pub ip_version: IpVersion,
+    pub protocol: Protocol,
+    pub source: IpAddr,
+    pub destination: IpAddr,
+    pub source_port: Option<u16>,
+    pub destination_port: Option<u16>,
+}
+
+/// A rule the traffic went past, and why.
+///
+/// # Fields
+/// - `order`: The `order` of the rule.
+/// - `interface`: The interface of the rule.
+/// - `description`: The description of the rule.
+/// - `reason`: Why the rule did not decide the fate of the traffic.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SkippedRule {
+    pub order: u64,
+    pub interface: String,
+    pub description: String,
+    pub reason: String,
+}
+
+/// The result of an evaluation.
+///
+/// # Fields
+/// - `action`: What happens to the traffic.
+/// - `rule`: The rule deciding the fate of the traffic, or `None` if the default policy applies.
+/// - `skipped`: The rules evaluated before, in order.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Evaluation {
+    pub action: String,
+    pub rule: Option<Rule>,
+    pub skipped: Vec<SkippedRule>,
+}
+
+/// An evaluator finding the filter rule of a configuration that matches some traffic.
+pub struct RuleEvaluator {
+    rules: Vec<TypedRule>,
+    interfaces: Vec<NetworkInterface>,
+    resolver: AliasResolver,
+}
+
+impl RuleEvaluator {
+    /// Prepares the filter rules of a configuration for evaluation.
+    ///
+    /// # Arguments
+    /// * `configuration` - The configuration holding the rules, aliases and interfaces.
+    ///
+    /// # Returns
+    /// * `Ok(RuleEvaluator)` - The evaluator.
+    /// * `Err(FireparseError)` - If the aliases of the configuration cannot be resolved.
+    pub fn new(configuration: &Configuration) -> Result<RuleEvaluator, FireparseError> {
+        let mut rules: Vec<TypedRule> = configuration
+            .rules
+            .iter()
+            .filter(|rule| rule.r#type == "filter")
+            .map(TypedRule::from)
+            .collect();
+
+        rules.sort_by_key(|rule| rule.order);
+
+        Ok(RuleEvaluator {
+            rules,
+            interfaces: configuration.interfaces.clone(),
+            resolver: AliasResolver::new(configuration)?,
+        })
+    }
+
+    /// Walks the filter rules in order and returns the first one deciding the fate of the traffic,
+    /// as pf does with the `quick` rules generated by pfSense and OPNsense.
+    ///
+    /// - Disabled rules are skipped.
+    /// - Rules apply to the traffic of their direction: inbound for interface rules, either
+    ///   direction for floating rules, the direction of the chain for Linux rules.
+    /// - Aliases, including nested ones, and interface keywords (`lan`, `wanip`, `(self)`) are
+    ///   expanded. Members that cannot be resolved without the firewall (host names, URL tables)
+    ///   never match, and are mentioned in the trace. Negated endpoints holding such members
+    ///   are undetermined, and skipped as well.
+    /// - Rules whose policy is neither `pass`, `block` nor `reject` (e.g. `log`) do not end the walk.
+    ///
+    /// Without a matching rule, inbound traffic is blocked and outbound traffic passed,
+    /// like pfSense's default rules.
+    ///
+    /// # Arguments
+    /// * `packet` - The traffic to evaluate.
+    ///
+    /// # Returns
+    /// An `Evaluation` holding the outcome, the matching rule and the trace of the rules skipped before it.
+    pub fn evaluate(&self, packet: &Packet) -> Evaluation {
+        let mut skipped = vec![];
+
+        for rule in &self.rules {
+            let reason = match self.mismatch(rule, packet) {
+                Some(reason) => reason,
+                None => match &rule.action {
+                    Action::Other(action) => {
+                        format!("matches, but '{action}' does not decide the fate of the traffic")
+                    }
+                    action => {
+                        return Evaluation {
+                            action: action.to_string(),
+                            rule: Some(Rule::from(rule)),
+                            skipped,
+                        };
+                    }
+                },
+            };
+
+            skipped.push(SkippedRule {
+                order: rule.order,
+                interface: rule.interface.clone(),
+                description: rule.description.clone(),
+                reason,
+            });
+        }
+
+        let action = match packet.direction {
+            Direction::Out => Action::Pass,
+            _ => Action::Block,
+        };
+
+        Evaluation {
+            action: action.to_string(),
+            rule: None,
+            skipped,
+        }
+    }
+
+    /// Checks a rule against the traffic.
+    ///
+    /// # Returns
+    /// `None` if the rule matches, or why it does not.
+    fn mismatch(&self, rule: &TypedRule, packet: &Packet) -> Option<String> {
+        if rule.disabled {
+            return Some(String::from("the rule is disabled"));
+        }
+
+        if rule.direction != Direction::Any
+            && packet.direction != Direction::Any
+            && rule.direction != packet.direction
+        {
+            let direction = match rule.direction {
+                Direction::Out => "outbound",
+                _ => "inbound",
+            };
+
+            return Some(format!("the rule applies to {direction} traffic"));
+        }
+
+        if !self.same_interface(&rule.interface, &packet.interface) {
+            return Some(format!(
+                "the rule applies to interface '{}'",
+                rule.interface
+            ));
+        }
+
+        if !rule.ip_version.overlaps(packet.ip_version) {
+            return Some(format!("the rule applies to {} traffic", rule.ip_version));
+        }
+
+        if !protocol_matches(&rule.protocol, &packet.protocol, packet.ip_version) {
+            return Some(format!("the rule applies to protocol '{}'", rule.protocol));
+        }
+
+        let endpoints = [
+            ("source", &rule.source, packet.source, packet.source_port),
+            (
+                "destination",
+                &rule.destination,
+                packet.destination,
+                packet.destination_port,
+            ),
+        ];
+
+        for (side, endpoint, address, port) in endpoints {
+            if let Some(reason) = self.endpoint_mismatch(endpoint, address) {
+                return Some(format!("the {side} {reason}"));
+            }
+
+            if !rule.protocol.has_ports() {
+                continue;
+            }
+
+            let ports = self.resolver.expand_port(&endpoint.port);
+
+            if !port.is_some_and(|port| ports.contains(port)) && !ports.any {
+                return Some(format!(
+                    "the {side} port does not match '{}'{}",
+                    endpoint.port,
+                    unresolved_note(&ports.unresolved)
+                ));
+            }
+        }
+
+        None
+    }
+
+    /// Checks the address of an endpoint.
+    ///
+    /// # Returns
+    /// `None` if the address matches, or why it does not.
+    fn endpoint_mismatch(&self, endpoint: &Endpoint, address: IpAddr) -> Option<String> {
+        let addresses = self.resolver.expand_address(&endpoint.address);
+
+        match (addresses.contains(&address), endpoint.negated) {
+            (false, true) if !addresses.unresolved.is_empty() => Some(format!(
+                "is undetermined: '{address}' may match '{}', which is negated{}",
+                endpoint.address,
+                unresolved_note(&addresses.unresolved)
+            )),
+            (true, false) | (false, true) => None,
+            (false, false) => Some(format!(
+                "'{address}' does not match '{}'{}",
+                endpoint.address,
+                unresolved_note(&addresses.unresolved)
+            )),
+            (true, true) => Some(format!(
+                "'{address}' matches '{}', which is negated",
+                endpoint.address
+            )),
+        }
+    }
+
+    /// Returns `true` if a rule interface and the traffic interface designate the same interface,
+    /// by name or by device.
+    fn same_interface(&self, rule_interface: &str, interface: &str) -> bool {
+        if ANY_INTERFACE_VALUES.contains(&rule_interface) || rule_interface == interface {
+            return true;
+        }
+
+        self.interfaces.iter().any(|candidate| {
+            let names = [candidate.name.as_str(), candidate.device.as_str()];
+            names.contains(&rule_interface) && names.contains(&interface)
+        })
+    }
+}
+
+/// Returns `true` if the protocol of a rule covers the protocol of the traffic.
+///
+/// pfSense's `icmp` stands for ICMPv6 in IPv6 rules.
+fn protocol_matches(rule: &Protocol, protocol: &Protocol, ip_version: IpVersion) -> bool {
+    match (rule, protocol) {
+        (Protocol::Any, _) => true,
+        (Protocol::TcpUdp, Protocol::Tcp | Protocol::Udp) => true,
+        (Protocol::Icmp, Protocol::Icmpv6) => ip_version == IpVersion::V6,
+        (rule, protocol) => rule == protocol,
+    }
+}
+
+/// Mentions the members that could not be resolved, and may match on the firewall.
+fn unresolved_note<T: ToString>(unresolved: &[Member<T>]) -> String {
+    if unresolved.is_empty() {
+        return String::new();
+    }
+
+    let values: Vec<String> = unresolved.iter().map(|m| m.value.to_string()).collect();
+    format!(" (unresolved: {})", values.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Packet, RuleEvaluator};
+    use crate::{
+        Configuration, Direction, IpVersion, Protocol,
+        test_utils::{self, alias, lan, rule},
+    };
+
+    fn configuration() -> Configuration {
+        let mut disabled = rule(0, "pass", "IPv4/any", "Old allow all");
+        disabled.disabled = true;
+
+        let mut ssh = rule(1, "block", "IPv4/tcp", "Block SSH to servers");
+        ssh.destination_addr = String::from("Servers");
+        ssh.destination_port = String::from("22");
+
+        let mut web = rule(2, "pass", "IPv4/tcp/udp", "Web from LAN");
+        web.source_addr = String::from("lan");
+        web.source_type = String::from("network");
+        web.destination_port = String::from("Web");
+
+        let mut firewall = rule(3, "reject", "IPv4/any", "Not to the firewall");
+        firewall.source_addr = String::from("lan");
+        firewall.source_type = String::from("network");
+        firewall.source_inversed = true;
+        firewall.destination_addr = String::from("(self)");
+        firewall.destination_type = String::from("network");
+
+        test_utils::configuration(
+            vec![web, firewall, disabled, ssh],
+            vec![
+                alias("host", "Servers", "10.0.0.10 backup.example.com", ""),
+                alias("port", "Web", "80 443", ""),
+            ],
+            vec![lan()],
+        )
+    }
+
+    fn packet(source: &str, destination: &str, port: u16) -> Packet {
+        Packet {
+            interface: String::from("em1"),
+            direction: Direction::In,
+            ip_version: IpVersion::V4,
+            protocol: Protocol::Tcp,
+            source: source.parse().unwrap(),
+            destination: destination.parse().unwrap(),
+            source_port: Some(50000),
+            destination_port: Some(port),
+        }
+    }
+
+    fn evaluator() -> RuleEvaluator {
+        RuleEvaluator::new(&configuration()).unwrap_or_else(|_| panic!("Failed to resolve"))
+    }
+
+    #[test]
+    fn test_first_matching_rule() {
+        let evaluation = evaluator().evaluate(&packet("192.168.1.20", "10.0.0.10", 22));
+
+        assert_eq!(evaluation.action, "block");
+        assert_eq!(evaluation.rule.unwrap().description, "Block SSH to servers");
+        assert_eq!(evaluation.skipped.len(), 1);
+        assert_eq!(evaluation.skipped[0].order, 0);
+        assert_eq!(evaluation.skipped[0].reason, "the rule is disabled");
+
+        let evaluation = evaluator().evaluate(&packet("192.168.1.20", "10.0.0.10", 443));
+
+        assert_eq!(evaluation.action, "pass");
+        assert_eq!(evaluation.rule.unwrap().description, "Web from LAN");
+        assert_eq!(
+            evaluation.skipped[1].reason,
+            "the destination port does not match '22'"
+        );
+    }
+
+    #[test]
+    fn test_trace_of_skipped_rules() {
+        let evaluation = evaluator().evaluate(&packet("172.16.0.5", "10.0.0.11", 22));
+
+        assert_eq!(evaluation.action, "block");
+        assert!(evaluation.rule.is_none());
+
+        let reasons: Vec<&str> = evaluation
+            .skipped
+            .iter()
+            .map(|skipped| skipped.reason.as_str())
+            .collect();
+
+        assert_eq!(
+            reasons,
+            vec![
+                "the rule is disabled",
+                "the destination '10.0.0.11' does not match 'Servers' (unresolved: backup.example.com)",
+                "the source '172.16.0.5' does not match 'lan'",
+                "the destination '10.0.0.11' does not match '(self)'",
+            ]
+        );
+
+        let evaluation = evaluator().evaluate(&packet("172.16.0.5", "192.168.1.1", 22));
+        assert_eq!(evaluation.action, "reject");
+
+        let evaluation = evaluator().evaluate(&packet("192.168.1.20", "192.168.1.1", 22));
+        assert_eq!(evaluation.action, "block");
+        assert_eq!(
+            evaluation.skipped[3].reason,
+            "the source '192.168.1.20' matches 'lan', which is negated"
+        );
+    }
+
+    #[test]
+    fn test_interface_and_direction() {
+        let mut outbound = packet("192.168.1.20", "10.0.0.10", 22);
+        outbound.direction = Direction::Out;
+
+        let evaluation = evaluator().evaluate(&outbound);
+        assert_eq!(evaluation.action, "pass");
+        assert!(evaluation.rule.is_none());
+        assert_eq!(
+            evaluation.skipped[1].reason,
+            "the rule applies to inbound traffic"
+        );
+
+        // Outbound rules, e.g. floating rules or rules of the Linux OUTPUT chain
+        let mut configuration = configuration();
+        let mut egress = rule(4, "block", "IPv4/any", "No egress");
+        egress.direction = String::from("out");
+        configuration.rules.push(egress);
+        let with_egress =
+            RuleEvaluator::new(&configuration).unwrap_or_else(|_| panic!("Failed to resolve"));
+
+        let evaluation = with_egress.evaluate(&outbound);
+        assert_eq!(evaluation.action, "block");
+        assert_eq!(evaluation.rule.unwrap().description, "No egress");
+
+        let evaluation = with_egress.evaluate(&packet("172.16.0.5", "10.0.0.11", 22));
+        assert_eq!(
+            evaluation.skipped.last().unwrap().reason,
+            "the rule applies to outbound traffic"
+        );
+
+        let mut wan = packet("192.168.1.20", "10.0.0.10", 22);
+        wan.interface = String::from("wan");
+
+        let evaluation = evaluator().evaluate(&wan);
+        assert!(evaluation.rule.is_none());
+        assert_eq!(
+            evaluation.skipped[1].reason,
+            "the rule applies to interface 'lan'"
+        );
+    }
+
+    #[test]
+    fn test_negated_unresolved_endpoint() {
+        let mut configuration = configuration();
+        let mut not_servers = rule(0, "pass", "IPv4/any", "Not to servers");
+        not_servers.destination_addr = String::from("Servers");
+        not_servers.destination_inversed = true;
+        configuration.rules = vec![not_servers];
+
+        let evaluator =
+            RuleEvaluator::new(&configuration).unwrap_or_else(|_| panic!("Failed to resolve"));
+        let evaluation = evaluator.evaluate(&packet("192.168.1.20", "10.0.0.11", 22));
+
+        assert_eq!(evaluation.action, "block");
+        assert!(evaluation.rule.is_none());
+        assert_eq!(
+            evaluation.skipped[0].reason,
+            "the destination is undetermined: '10.0.0.11' may match 'Servers', which is negated (unresolved: backup.example.com)"
+        );
+    }
+}
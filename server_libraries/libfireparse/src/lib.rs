@@ -1,18 +1,43 @@
+mod alias_resolver;
+mod differ;
+mod evaluator;
+mod linter;
+mod linux;
 mod models;
 mod opnsense;
 mod pfsense;
+mod rule_model;
+#[cfg(test)]
+mod test_utils;
+mod translator;
 mod utils;
+mod xml_editor;
 
+pub use alias_resolver::{
+    AliasKind, AliasNode, AliasResolver, AliasValue, ExpandedEndpoint, IpRange, IpSet, Member,
+    PortRange, PortSet,
+};
+pub use differ::{ConfigurationChange, ConfigurationDiff, Differ};
+pub use evaluator::{Evaluation, Packet, RuleEvaluator, SkippedRule};
+pub use linter::{FindingKind, LintFinding, LintOptions, LintReport, Linter, Severity};
+use linux::LinuxParser;
+pub use linux::{IptablesExport, IptablesExporter, NftablesExport, NftablesExporter};
 pub use models::*;
 pub use nullnet_libconfmon::{FileData, Platform, Snapshot};
-use pfsense::PfSenseParser;
+use pfsense::{PfSenseParser, PfSenseWriter};
+pub use rule_model::{
+    Action, Address, Direction, Endpoint, IpVersion, PortSpec, Protocol, TypedRule,
+};
+pub use translator::{Translation, TranslationIssue, Translator};
 
-use crate::opnsense::OpnSenseParser;
+use crate::opnsense::{OpnSenseParser, OpnSenseWriter};
 
 /// Represents possible errors that can occur while parsing firewall configurations.
 pub enum FireparseError {
     UnsupportedPlatform(String),
     ParserError(String),
+    WriterError(String),
+    ResolverError(String),
 }
 
 /// A generic parser for firewall configuration files.
@@ -34,10 +59,44 @@ impl Parser {
     /// # Supported Platforms
     /// - `Platform::PfSense`: Uses `PfSenseParser` to process pfSense configurations.
     /// - `Platform::OPNsense`:  Uses `OpnSenseParser` to process OPNsense configurations.
+    /// - `Platform::Linux`: Uses `LinuxParser` to process nftables / iptables rulesets.
     pub fn parse(platfom: Platform, snapshot: Snapshot) -> Result<Configuration, FireparseError> {
         match platfom {
             Platform::PfSense => PfSenseParser::parse(snapshot),
             Platform::OPNsense => OpnSenseParser::parse(snapshot),
+            Platform::Linux => LinuxParser::parse(snapshot),
+        }
+    }
+}
+
+/// A generic writer rendering an edited `Configuration` back into the platform's configuration file.
+pub struct Writer {}
+
+impl Writer {
+    /// Renders a configuration, as returned by `Parser::parse` and possibly edited,
+    /// back into the configuration file it was parsed from.
+    ///
+    /// # Arguments
+    /// * `platform` - The firewall platform the configuration belongs to.
+    /// * `configuration` - The configuration to render. Its `raw_content` must hold the original file.
+    ///
+    /// # Returns
+    /// * `Ok(String)` - The updated configuration file.
+    /// * `Err(FireparseError)` - If the platform is unsupported or the configuration cannot be rendered.
+    ///
+    /// # Supported Platforms
+    /// - `Platform::PfSense`: Uses `PfSenseWriter` to render `config.xml`.
+    /// - `Platform::OPNsense`: Uses `OpnSenseWriter` to render `config.xml`.
+    pub fn render(
+        platform: Platform,
+        configuration: &Configuration,
+    ) -> Result<String, FireparseError> {
+        match platform {
+            Platform::PfSense => PfSenseWriter::render(configuration),
+            Platform::OPNsense => OpnSenseWriter::render(configuration),
+            Platform::Linux => Err(FireparseError::UnsupportedPlatform(format!(
+                "Rendering is not supported for {platform:?}"
+            ))),
         }
     }
 }
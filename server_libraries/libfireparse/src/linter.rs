@@ -0,0 +1,654 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    Action, Address, AliasResolver, Configuration, Direction, ExpandedEndpoint, FireparseError,
+    IpRange, IpVersion, PortRange, PortSpec, Protocol, TypedRule,
+};
+
+/// Interface values standing for every interface (e.g. rules parsed from Linux rulesets without `-i`).
+const ANY_INTERFACE_VALUES: [&str; 3] = ["none", "any", ""];
+/// Default age after which a disabled rule is reported.
+const DEFAULT_DISABLED_RULE_MAX_AGE: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+/// How much attention a finding deserves.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// The kind of problem a finding reports.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// The rule never matches: an earlier rule on the same interface matches all of its traffic.
+    Shadowed,
+    /// The rule duplicates an earlier rule.
+    Redundant,
+    /// The rule partly overlaps an earlier rule with the opposite action.
+    Conflict,
+    /// The rule references an alias that does not exist.
+    UndefinedAlias,
+    /// The rule references an interface that does not exist.
+    UndefinedInterface,
+    /// The rule has been disabled for longer than `LintOptions.disabled_rule_max_age`.
+    StaleDisabled,
+}
+
+/// A problem found in a rule.
+///
+/// # Fields
+/// - `kind`: The kind of problem.
+/// - `severity`: How much attention the problem deserves.
+/// - `r#type`: The type of the rule (`"filter"` or `"nat"`).
+/// - `order`: The `order` of the rule.
+/// - `interface`: The interface of the rule.
+/// - `related_order`: The `order` of the earlier rule involved, for shadowed, redundant and conflicting rules.
+/// - `message`: A description of the problem.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LintFinding {
+    pub kind: FindingKind,
+    pub severity: Severity,
+    pub r#type: String,
+    pub order: u64,
+    pub interface: String,
+    pub related_order: Option<u64>,
+    pub message: String,
+}
+
+/// The findings of `Linter::lint`, ordered by rule.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LintReport {
+    pub findings: Vec<LintFinding>,
+}
+
+/// The settings of `Linter::lint`.
+///
+/// # Fields
+/// - `disabled_rule_max_age`: The time since their last update after which disabled rules are reported.
+/// - `now`: The reference time the age of rules is computed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintOptions {
+    pub disabled_rule_max_age: Duration,
+    pub now: SystemTime,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        LintOptions {
+            disabled_rule_max_age: DEFAULT_DISABLED_RULE_MAX_AGE,
+            now: SystemTime::now(),
+        }
+    }
+}
+
+/// A linter reporting the rules of a configuration that are likely mistakes.
+pub struct Linter {}
+
+impl Linter {
+    /// Analyses the rules of a configuration.
+    ///
+    /// - Filter rules are compared with the earlier enabled rules: rules matching a subset of the
+    ///   traffic of an earlier rule on the same interface and direction are shadowed (or redundant when they
+    ///   duplicate it), rules partly overlapping an earlier rule with the opposite action conflict.
+    ///   Aliases and interface keywords are expanded. Rules whose endpoints cannot be fully
+    ///   resolved (host names, URL tables) are not compared.
+    /// - Filter and NAT rules referencing undefined aliases or interfaces are reported.
+    /// - Disabled rules whose last update (`<updated>` or `<created>` in `config.xml`) is older
+    ///   than `options.disabled_rule_max_age` are reported. Linux rulesets carry no such time.
+    ///
+    /// # Arguments
+    /// * `configuration` - The configuration to analyse.
+    /// * `options` - The settings of the analysis.
+    ///
+    /// # Returns
+    /// * `Ok(LintReport)` - The findings.
+    /// * `Err(FireparseError)` - If the aliases of the configuration cannot be resolved.
+    pub fn lint(
+        configuration: &Configuration,
+        options: &LintOptions,
+    ) -> Result<LintReport, FireparseError> {
+        let resolver = AliasResolver::new(configuration)?;
+
+        let mut rules: Vec<TypedRule> = configuration.rules.iter().map(TypedRule::from).collect();
+        rules.sort_by_key(|rule| (rule.r#type != "filter", rule.order));
+
+        // Rules are compared on the interface names, whether they reference interfaces by name or device
+        let comparable: Vec<TypedRule> = rules
+            .iter()
+            .map(|rule| TypedRule {
+                interface: interface_name(configuration, &rule.interface),
+                ..rule.clone()
+            })
+            .collect();
+
+        let expanded: Vec<[ExpandedEndpoint; 2]> = rules
+            .iter()
+            .map(|rule| {
+                [
+                    resolver.expand(&rule.source),
+                    resolver.expand(&rule.destination),
+                ]
+            })
+            .collect();
+
+        let mut findings = vec![];
+
+        for (index, rule) in rules.iter().enumerate() {
+            let finding = |kind, severity, related_order, message| LintFinding {
+                kind,
+                severity,
+                r#type: rule.r#type.clone(),
+                order: rule.order,
+                interface: rule.interface.clone(),
+                related_order,
+                message,
+            };
+
+            for (kind, name) in undefined_references(configuration, rule) {
+                let label = match kind {
+                    FindingKind::UndefinedAlias => "alias",
+                    _ => "interface",
+                };
+
+                findings.push(finding(
+                    kind,
+                    Severity::Error,
+                    None,
+                    format!("The rule references the undefined {label} '{name}'"),
+                ));
+            }
+
+            if rule.disabled
+                && let Some(updated) = rule.updated_at.map(Duration::from_millis)
+                && let Ok(now) = options.now.duration_since(UNIX_EPOCH)
+                && now.saturating_sub(updated) > options.disabled_rule_max_age
+            {
+                findings.push(finding(
+                    FindingKind::StaleDisabled,
+                    Severity::Info,
+                    None,
+                    format!(
+                        "The rule is disabled and was last updated {} days ago",
+                        (now - updated).as_secs() / (24 * 60 * 60)
+                    ),
+                ));
+            }
+
+            if rule.r#type != "filter" || !is_terminating(rule) {
+                continue;
+            }
+
+            let mut conflict = None;
+
+            for earlier in 0..index {
+                let other = &rules[earlier];
+                let (a, b) = (&comparable[earlier], &comparable[index]);
+
+                if other.r#type != "filter" || !is_terminating(other) {
+                    continue;
+                }
+
+                if same_match(a, b) && other.action == rule.action {
+                    findings.push(finding(
+                        FindingKind::Redundant,
+                        Severity::Info,
+                        Some(other.order),
+                        format!("The rule duplicates {}", label(other)),
+                    ));
+                    conflict = None;
+                    break;
+                }
+
+                if covers(a, &expanded[earlier], b, &expanded[index]) == Some(true) {
+                    let severity = match other.action == rule.action {
+                        true => Severity::Warning,
+                        false => Severity::Error,
+                    };
+
+                    findings.push(finding(
+                        FindingKind::Shadowed,
+                        severity,
+                        Some(other.order),
+                        format!(
+                            "The rule never matches: {} matches all of its traffic first",
+                            label(other)
+                        ),
+                    ));
+                    conflict = None;
+                    break;
+                }
+
+                if conflict.is_none()
+                    && opposite(&other.action, &rule.action)
+                    && overlaps(a, &expanded[earlier], b, &expanded[index]) == Some(true)
+                {
+                    conflict = Some(other);
+                }
+            }
+
+            if let Some(other) = conflict {
+                findings.push(finding(
+                    FindingKind::Conflict,
+                    Severity::Warning,
+                    Some(other.order),
+                    format!(
+                        "The rule overlaps {} with the opposite action, which decides the overlapping traffic",
+                        label(other)
+                    ),
+                ));
+            }
+        }
+
+        Ok(LintReport { findings })
+    }
+}
+
+/// Describes a rule in messages, e.g. `rule #3 'Allow DNS'`.
+fn label(rule: &TypedRule) -> String {
+    match rule.description.is_empty() {
+        true => format!("rule #{}", rule.order),
+        false => format!("rule #{} '{}'", rule.order, rule.description),
+    }
+}
+
+/// Returns the name of an interface referenced by name or by device, or `interface` itself if it is unknown.
+fn interface_name(configuration: &Configuration, interface: &str) -> String {
+    configuration
+        .interfaces
+        .iter()
+        .find(|i| i.name == interface || i.device == interface)
+        .map_or_else(|| interface.to_string(), |i| i.name.clone())
+}
+
+/// Returns `true` if the rule decides the fate of the traffic it matches.
+fn is_terminating(rule: &TypedRule) -> bool {
+    !rule.disabled && !matches!(rule.action, Action::Other(_))
+}
+
+/// Returns `true` if one action passes traffic the other one blocks.
+fn opposite(a: &Action, b: &Action) -> bool {
+    (*a == Action::Pass) != (*b == Action::Pass)
+}
+
+/// Returns `true` if both rules match the very same traffic, as written.
+fn same_match(a: &TypedRule, b: &TypedRule) -> bool {
+    a.interface == b.interface
+        && a.direction == b.direction
+        && a.ip_version == b.ip_version
+        && a.protocol == b.protocol
+        && a.source == b.source
+        && a.destination == b.destination
+}
+
+/// Lists the aliases and interfaces a rule references that are not part of the configuration.
+fn undefined_references(
+    configuration: &Configuration,
+    rule: &TypedRule,
+) -> Vec<(FindingKind, String)> {
+    let alias_exists = |name: &str| configuration.aliases.iter().any(|a| a.name == name);
+    let interface_exists = |name: &str| {
+        configuration
+            .interfaces
+            .iter()
+            .any(|i| i.name == name || i.device == name)
+    };
+
+    let mut undefined = vec![];
+
+    if !ANY_INTERFACE_VALUES.contains(&rule.interface.as_str())
+        && !interface_exists(&rule.interface)
+    {
+        undefined.push((FindingKind::UndefinedInterface, rule.interface.clone()));
+    }
+
+    for endpoint in [&rule.source, &rule.destination] {
+        match &endpoint.address {
+            Address::Alias(name) if !alias_exists(name) => {
+                undefined.push((FindingKind::UndefinedAlias, name.clone()));
+            }
+            Address::Network(name) | Address::InterfaceAddress(name) if !interface_exists(name) => {
+                undefined.push((FindingKind::UndefinedInterface, name.clone()));
+            }
+            _ => {}
+        }
+
+        if let PortSpec::Alias(name) = &endpoint.port
+            && !alias_exists(name)
+        {
+            undefined.push((FindingKind::UndefinedAlias, name.clone()));
+        }
+    }
+
+    undefined
+}
+
+/// An inclusive range of values, compared by the set operations of the linter.
+trait Span {
+    fn contains_span(&self, other: &Self) -> bool;
+    fn overlaps_span(&self, other: &Self) -> bool;
+}
+
+impl Span for IpRange {
+    fn contains_span(&self, other: &Self) -> bool {
+        self.contains_range(other)
+    }
+
+    fn overlaps_span(&self, other: &Self) -> bool {
+        self.overlaps(other)
+    }
+}
+
+impl Span for PortRange {
+    fn contains_span(&self, other: &Self) -> bool {
+        self.contains_range(other)
+    }
+
+    fn overlaps_span(&self, other: &Self) -> bool {
+        self.overlaps(other)
+    }
+}
+
+/// Returns `true` if the set `a` holds every value of the set `b`.
+fn set_covers<T: Span>(a_any: bool, a: &[T], b_any: bool, b: &[T]) -> bool {
+    a_any || (!b_any && b.iter().all(|b| a.iter().any(|a| a.contains_span(b))))
+}
+
+/// Returns `true` if the sets `a` and `b` have a value in common.
+fn set_overlaps<T: Span>(a_any: bool, a: &[T], b_any: bool, b: &[T]) -> bool {
+    (a_any && (b_any || !b.is_empty()))
+        || (b_any && !a.is_empty())
+        || a.iter().any(|a| b.iter().any(|b| a.overlaps_span(b)))
+}
+
+/// The concrete addresses of an endpoint, or `None` if some of them cannot be resolved.
+fn addresses(endpoint: &ExpandedEndpoint) -> Option<(bool, Vec<IpRange>)> {
+    let set = &endpoint.addresses;
+
+    set.unresolved.is_empty().then(|| {
+        (
+            set.any,
+            set.ranges.iter().map(|member| member.value).collect(),
+        )
+    })
+}
+
+/// The concrete ports of an endpoint, or `None` if some of them cannot be resolved.
+/// Ports are ignored for protocols that do not carry them.
+fn ports(rule: &TypedRule, endpoint: &ExpandedEndpoint) -> Option<(bool, Vec<PortRange>)> {
+    if !rule.protocol.has_ports() {
+        return Some((true, vec![]));
+    }
+
+    let set = &endpoint.ports;
+
+    set.unresolved.is_empty().then(|| {
+        (
+            set.any,
+            set.ranges.iter().map(|member| member.value).collect(),
+        )
+    })
+}
+
+/// Returns `true` if the addresses of endpoint `a` include every address of endpoint `b`.
+fn address_covers(a: &ExpandedEndpoint, b: &ExpandedEndpoint) -> Option<bool> {
+    let ((a_any, a_ranges), (b_any, b_ranges)) = (addresses(a)?, addresses(b)?);
+
+    Some(match (a.negated, b.negated) {
+        (false, false) => set_covers(a_any, &a_ranges, b_any, &b_ranges),
+        (true, true) => set_covers(b_any, &b_ranges, a_any, &a_ranges),
+        (true, false) => !set_overlaps(a_any, &a_ranges, b_any, &b_ranges),
+        (false, true) => a_any,
+    })
+}
+
+/// Returns `true` if endpoints `a` and `b` have an address in common.
+fn address_overlaps(a: &ExpandedEndpoint, b: &ExpandedEndpoint) -> Option<bool> {
+    let ((a_any, a_ranges), (b_any, b_ranges)) = (addresses(a)?, addresses(b)?);
+
+    Some(match (a.negated, b.negated) {
+        (false, false) => set_overlaps(a_any, &a_ranges, b_any, &b_ranges),
+        (true, false) => !set_covers(a_any, &a_ranges, b_any, &b_ranges),
+        (false, true) => !set_covers(b_any, &b_ranges, a_any, &a_ranges),
+        (true, true) => true,
+    })
+}
+
+/// Returns `true` if rule `a` matches every packet rule `b` matches, or `None` if it cannot be told.
+fn covers(
+    a: &TypedRule,
+    a_endpoints: &[ExpandedEndpoint; 2],
+    b: &TypedRule,
+    b_endpoints: &[ExpandedEndpoint; 2],
+) -> Option<bool> {
+    let same_interface =
+        ANY_INTERFACE_VALUES.contains(&a.interface.as_str()) || a.interface == b.interface;
+
+    if !same_interface
+        || !(a.direction == Direction::Any || a.direction == b.direction)
+        || !(a.ip_version == IpVersion::Any || a.ip_version == b.ip_version)
+        || !protocol_covers(&a.protocol, &b.protocol)
+    {
+        return Some(false);
+    }
+
+    let mut checks = vec![];
+
+    for (a_endpoint, b_endpoint) in a_endpoints.iter().zip(b_endpoints) {
+        checks.push(address_covers(a_endpoint, b_endpoint));
+
+        checks.push(ports(a, a_endpoint).zip(ports(b, b_endpoint)).map(
+            |((a_any, a_ports), (b_any, b_ports))| set_covers(a_any, &a_ports, b_any, &b_ports),
+        ));
+    }
+
+    all(checks)
+}
+
+/// Returns `true` if some packet matches both rules, or `None` if it cannot be told.
+fn overlaps(
+    a: &TypedRule,
+    a_endpoints: &[ExpandedEndpoint; 2],
+    b: &TypedRule,
+    b_endpoints: &[ExpandedEndpoint; 2],
+) -> Option<bool> {
+    let same_interface = ANY_INTERFACE_VALUES.contains(&a.interface.as_str())
+        || ANY_INTERFACE_VALUES.contains(&b.interface.as_str())
+        || a.interface == b.interface;
+
+    let same_direction = a.direction == Direction::Any
+        || b.direction == Direction::Any
+        || a.direction == b.direction;
+
+    if !same_interface
+        || !same_direction
+        || !a.ip_version.overlaps(b.ip_version)
+        || !(protocol_covers(&a.protocol, &b.protocol) || protocol_covers(&b.protocol, &a.protocol))
+    {
+        return Some(false);
+    }
+
+    let mut checks = vec![];
+
+    for (a_endpoint, b_endpoint) in a_endpoints.iter().zip(b_endpoints) {
+        checks.push(address_overlaps(a_endpoint, b_endpoint));
+
+        checks.push(ports(a, a_endpoint).zip(ports(b, b_endpoint)).map(
+            |((a_any, a_ports), (b_any, b_ports))| set_overlaps(a_any, &a_ports, b_any, &b_ports),
+        ));
+    }
+
+    all(checks)
+}
+
+/// Combines checks: `Some(false)` if any check fails, `None` if any cannot be told, `Some(true)` otherwise.
+fn all(checks: Vec<Option<bool>>) -> Option<bool> {
+    if checks.contains(&Some(false)) {
+        return Some(false);
+    }
+
+    checks
+        .into_iter()
+        .all(|check| check == Some(true))
+        .then_some(true)
+}
+
+/// Returns `true` if protocol `a` matches every packet protocol `b` matches.
+fn protocol_covers(a: &Protocol, b: &Protocol) -> bool {
+    match (a, b) {
+        (Protocol::Any, _) => true,
+        (Protocol::TcpUdp, Protocol::Tcp | Protocol::Udp) => true,
+        (a, b) => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FindingKind, LintOptions, Linter, Severity};
+    use crate::{
+        Configuration, Rule,
+        test_utils::{self, alias, lan, rule},
+    };
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn configuration(rules: Vec<Rule>) -> Configuration {
+        test_utils::configuration(
+            rules,
+            vec![alias("host", "Servers", "10.0.0.10 10.0.0.11", "")],
+            vec![lan()],
+        )
+    }
+
+    fn lint(configuration: &Configuration) -> Vec<(FindingKind, Severity, u64, Option<u64>)> {
+        Linter::lint(configuration, &LintOptions::default())
+            .unwrap_or_else(|_| panic!("Failed to lint"))
+            .findings
+            .into_iter()
+            .map(|f| (f.kind, f.severity, f.order, f.related_order))
+            .collect()
+    }
+
+    #[test]
+    fn test_shadowed_redundant_and_conflicting_rules() {
+        let mut web = rule(0, "pass", "IPv4/tcp", "Web");
+        web.destination_addr = String::from("Servers");
+        web.destination_port = String::from("80-443");
+
+        let mut https = rule(1, "block", "IPv4/tcp", "Block HTTPS to a server");
+        https.destination_addr = String::from("10.0.0.11");
+        https.destination_port = String::from("443");
+
+        let mut duplicate = web.clone();
+        duplicate.order = 2;
+        duplicate.description = String::from("Web again");
+
+        let mut lan = rule(3, "block", "IPv4/tcp/udp", "Block LAN");
+        lan.source_addr = String::from("lan");
+        lan.source_type = String::from("network");
+
+        let mut by_device = https.clone();
+        by_device.order = 4;
+        by_device.interface = String::from("em1");
+
+        let allow_all = rule(5, "pass", "IPv4/any", "Allow all");
+
+        let configuration = configuration(vec![web, https, duplicate, lan, by_device, allow_all]);
+
+        assert_eq!(
+            lint(&configuration),
+            vec![
+                (FindingKind::Shadowed, Severity::Error, 1, Some(0)),
+                (FindingKind::Redundant, Severity::Info, 2, Some(0)),
+                (FindingKind::Conflict, Severity::Warning, 3, Some(0)),
+                (FindingKind::Shadowed, Severity::Error, 4, Some(0)),
+                (FindingKind::Conflict, Severity::Warning, 5, Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rules_of_other_directions() {
+        let inbound = rule(0, "pass", "IPv4/any", "Inbound");
+
+        let mut outbound = rule(1, "block", "IPv4/any", "Outbound");
+        outbound.direction = String::from("out");
+
+        let mut outbound_again = outbound.clone();
+        outbound_again.order = 2;
+
+        let mut either = rule(3, "block", "IPv4/tcp", "Either");
+        either.direction = String::from("any");
+
+        assert_eq!(
+            lint(&configuration(vec![
+                inbound,
+                outbound,
+                outbound_again,
+                either
+            ])),
+            vec![
+                (FindingKind::Redundant, Severity::Info, 2, Some(1)),
+                (FindingKind::Conflict, Severity::Warning, 3, Some(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undefined_references() {
+        let mut missing = rule(0, "pass", "IPv4/tcp", "");
+        missing.interface = String::from("opt1");
+        missing.source_addr = String::from("wanip");
+        missing.source_type = String::from("network");
+        missing.destination_addr = String::from("Databases");
+        missing.destination_port = String::from("DbPorts");
+
+        let findings = Linter::lint(&configuration(vec![missing]), &LintOptions::default())
+            .unwrap_or_else(|_| panic!("Failed to lint"))
+            .findings;
+
+        let messages: Vec<&str> = findings.iter().map(|f| f.message.as_str()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "The rule references the undefined interface 'opt1'",
+                "The rule references the undefined interface 'wan'",
+                "The rule references the undefined alias 'Databases'",
+                "The rule references the undefined alias 'DbPorts'",
+            ]
+        );
+        assert!(findings.iter().all(|f| f.interface == "opt1"));
+    }
+
+    #[test]
+    fn test_stale_disabled_rules() {
+        let mut old = rule(0, "pass", "IPv4/any", "Old");
+        old.disabled = true;
+        old.updated_at = Some(1_600_000_000_000);
+        let mut recent = rule(1, "pass", "IPv4/any", "Recent");
+        recent.disabled = true;
+        recent.updated_at = Some(1_700_000_000_500);
+
+        let configuration = configuration(vec![old, recent]);
+
+        let options = LintOptions {
+            disabled_rule_max_age: Duration::from_secs(30 * 24 * 60 * 60),
+            now: UNIX_EPOCH + Duration::from_secs(1700086400),
+        };
+
+        let findings = Linter::lint(&configuration, &options)
+            .unwrap_or_else(|_| panic!("Failed to lint"))
+            .findings;
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::StaleDisabled);
+        assert_eq!(findings[0].order, 0);
+        assert_eq!(
+            findings[0].message,
+            "The rule is disabled and was last updated 1158 days ago"
+        );
+    }
+}
@@ -0,0 +1,402 @@
+use std::{collections::HashMap, net::IpAddr};
+
+use crate::{Alias, Configuration, IpVersion, Rule};
+
+pub(super) const ANY_ADDR_VALUE: &str = "*";
+pub(super) const ANY_PORT_VALUE: &str = "*";
+pub(super) const DEFAULT_INTERFACE_VALUE: &str = "none";
+
+/// An alias, as it can be expressed by the Linux firewalls.
+pub(super) enum ExportedAlias {
+    /// Addresses and networks of a single IP version.
+    Addresses {
+        version: IpVersion,
+        elements: Vec<String>,
+        interval: bool,
+    },
+    /// Ports and port ranges (`1000-2000`).
+    Ports(Vec<String>),
+}
+
+/// Converts an alias into a set of elements the Linux firewalls can match on.
+///
+/// # Returns
+/// * `Ok(ExportedAlias)` - The elements of the alias.
+/// * `Err(String)` - Why the alias cannot be expressed (e.g. URL tables, host names, nested aliases).
+pub(super) fn export_alias(alias: &Alias) -> Result<ExportedAlias, String> {
+    let values: Vec<&str> = alias
+        .value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|value| !value.is_empty() && *value != "None")
+        .collect();
+
+    match alias.r#type.as_str() {
+        "host" | "network" => {
+            let mut version = None;
+
+            for value in &values {
+                let Some(value_version) = IpVersion::of_address(value) else {
+                    return Err(format!(
+                        "'{value}' is not an address (host names and nested aliases are not supported)"
+                    ));
+                };
+
+                if version.is_some_and(|version| version != value_version) {
+                    return Err(String::from("mixes IPv4 and IPv6 addresses"));
+                }
+
+                version = Some(value_version);
+            }
+
+            Ok(ExportedAlias::Addresses {
+                version: version.unwrap_or(IpVersion::V4),
+                interval: values.iter().any(|value| value.contains(['/', '-'])),
+                elements: values.iter().map(|value| value.to_string()).collect(),
+            })
+        }
+        "port" => {
+            let mut ports = vec![];
+
+            for value in values {
+                let port = value.replace(':', "-");
+
+                if !port.split('-').all(|part| part.parse::<u16>().is_ok()) {
+                    return Err(format!("'{value}' is not a port or a port range"));
+                }
+
+                ports.push(port);
+            }
+
+            Ok(ExportedAlias::Ports(ports))
+        }
+        other => Err(format!("aliases of type '{other}' are not supported")),
+    }
+}
+
+/// The addresses matched by a rule endpoint.
+pub(super) enum AddressMatch<'a> {
+    /// An exported alias holding addresses.
+    Alias(&'a str),
+    /// Literal addresses, networks or ranges.
+    Addresses(Vec<String>),
+}
+
+/// Resolves the address of a rule endpoint.
+///
+/// # Arguments
+/// * `configuration` - The configuration the rule belongs to.
+/// * `addr` - The address of the endpoint: `*`, an alias, an interface network or literal addresses.
+/// * `r#type` - The type of the endpoint (`address` or `network`).
+/// * `aliases` - The aliases that could be exported, by name.
+///
+/// # Returns
+/// * `Ok(None)` - If the endpoint matches any address.
+/// * `Ok(Some((IpVersion, AddressMatch)))` - The IP version of the endpoint and what it matches.
+/// * `Err(String)` - If the address cannot be expressed.
+pub(super) fn resolve_address<'a>(
+    configuration: &Configuration,
+    addr: &'a str,
+    r#type: &str,
+    aliases: &HashMap<&str, ExportedAlias>,
+) -> Result<Option<(IpVersion, AddressMatch<'a>)>, String> {
+    if addr == ANY_ADDR_VALUE {
+        return Ok(None);
+    }
+
+    match aliases.get(addr) {
+        Some(ExportedAlias::Addresses { version, .. }) => {
+            return Ok(Some((*version, AddressMatch::Alias(addr))));
+        }
+        Some(ExportedAlias::Ports(_)) => {
+            return Err(format!("alias '{addr}' holds ports, not addresses"));
+        }
+        None if configuration.aliases.iter().any(|alias| alias.name == addr) => {
+            return Err(format!("alias '{addr}' could not be exported"));
+        }
+        None => {}
+    }
+
+    let elements = match r#type {
+        "network" => resolve_network(configuration, addr)?,
+        _ => addr.split(',').map(str::to_string).collect(),
+    };
+
+    let mut version = None;
+
+    for element in &elements {
+        let element_version = IpVersion::of_address(element)
+            .ok_or_else(|| format!("'{element}' is not an address"))?;
+
+        if version.is_some_and(|version| version != element_version) {
+            return Err(format!("'{addr}' mixes IPv4 and IPv6 addresses"));
+        }
+
+        version = Some(element_version);
+    }
+
+    let version = version.ok_or_else(|| format!("'{addr}' has no address"))?;
+
+    Ok(Some((version, AddressMatch::Addresses(elements))))
+}
+
+/// The ports matched by a rule endpoint, ranges written as `1000-2000`.
+pub(super) enum PortMatch<'a> {
+    /// An exported alias holding ports, along with its ports.
+    Alias(&'a str, &'a [String]),
+    /// Literal ports and port ranges.
+    Ports(Vec<String>),
+}
+
+/// Resolves the port of a rule endpoint: `*`, an alias or literal ports (e.g. `22`, `1000:2000` or `80,443`).
+///
+/// # Returns
+/// * `Ok(None)` - If the endpoint matches any port.
+/// * `Ok(Some(PortMatch))` - What the endpoint matches.
+/// * `Err(String)` - If the port cannot be expressed.
+pub(super) fn resolve_port<'a>(
+    port: &'a str,
+    aliases: &'a HashMap<&str, ExportedAlias>,
+) -> Result<Option<PortMatch<'a>>, String> {
+    if port == ANY_PORT_VALUE {
+        return Ok(None);
+    }
+
+    match aliases.get(port) {
+        Some(ExportedAlias::Ports(ports)) => return Ok(Some(PortMatch::Alias(port, ports))),
+        Some(ExportedAlias::Addresses { .. }) => {
+            return Err(format!("alias '{port}' holds addresses, not ports"));
+        }
+        None => {}
+    }
+
+    let ports: Vec<String> = port.split(',').map(|p| p.replace(':', "-")).collect();
+
+    if !ports
+        .iter()
+        .all(|port| port.split('-').all(|part| part.parse::<u16>().is_ok()))
+    {
+        return Err(format!("port '{port}' cannot be resolved"));
+    }
+
+    Ok(Some(PortMatch::Ports(ports)))
+}
+
+/// Splits `Rule.protocol` (e.g. `IPv4/tcp`) into the IP version and the protocols it matches.
+///
+/// pfSense's `tcp/udp` becomes two protocols, `any` none.
+pub(super) fn split_protocol(protocol: &str) -> (IpVersion, Vec<&str>) {
+    let (prefix, protocol) = protocol.split_once('/').unwrap_or(("none", protocol));
+
+    let protocols = match protocol {
+        "any" | "" => vec![],
+        protocol => protocol.split('/').collect(),
+    };
+
+    (IpVersion::from(prefix), protocols)
+}
+
+/// Returns the device of the interface a rule applies to.
+///
+/// Interfaces are looked up by name (e.g. `lan`) and then by device, so that rules
+/// parsed from Linux rulesets, which already use devices, are kept as they are.
+pub(super) fn resolve_interface(configuration: &Configuration, name: &str) -> Option<String> {
+    configuration
+        .interfaces
+        .iter()
+        .find(|interface| interface.name == name)
+        .or_else(|| {
+            configuration
+                .interfaces
+                .iter()
+                .find(|interface| interface.device == name)
+        })
+        .map(|interface| interface.device.clone())
+}
+
+/// Resolves an endpoint of type `network`, which references an interface: `lan` stands for the
+/// subnet of the interface and `lanip` for its address.
+///
+/// # Returns
+/// * `Ok(Vec<String>)` - The addresses or networks matching the endpoint.
+/// * `Err(String)` - Why the endpoint cannot be resolved.
+pub(super) fn resolve_network(
+    configuration: &Configuration,
+    network: &str,
+) -> Result<Vec<String>, String> {
+    let (name, address_only) = match network.strip_suffix("ip") {
+        Some(name) if configuration.interfaces.iter().any(|i| i.name == name) => (name, true),
+        _ => (network, false),
+    };
+
+    let interface = configuration
+        .interfaces
+        .iter()
+        .find(|interface| interface.name == name)
+        .ok_or_else(|| format!("network '{network}' does not match any interface"))?;
+
+    let mut resolved = vec![];
+
+    for address in &interface.addresses {
+        let resolved_address = match address.address.split_once('/') {
+            Some((ip, _)) if address_only => Some(ip.to_string()),
+            Some(_) => subnet_of(&address.address),
+            None if address_only => Some(address.address.clone()),
+            None => None,
+        };
+
+        match resolved_address {
+            Some(resolved_address) => resolved.push(resolved_address),
+            None => {
+                return Err(format!(
+                    "the subnet of interface '{name}' is unknown (address '{}' has no prefix length)",
+                    address.address
+                ));
+            }
+        }
+    }
+
+    if resolved.is_empty() {
+        return Err(format!("interface '{name}' has no address"));
+    }
+
+    Ok(resolved)
+}
+
+/// Returns the subnet of an interface address, e.g. `192.168.1.0/24` for `192.168.1.1/24`.
+fn subnet_of(address: &str) -> Option<String> {
+    let (ip, length) = address.split_once('/')?;
+    let length: u32 = length.parse().ok()?;
+
+    match ip.parse::<IpAddr>().ok()? {
+        IpAddr::V4(ip) if length <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - length).unwrap_or(0);
+            Some(format!(
+                "{}/{length}",
+                std::net::Ipv4Addr::from(u32::from(ip) & mask)
+            ))
+        }
+        IpAddr::V6(ip) if length <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - length).unwrap_or(0);
+            Some(format!(
+                "{}/{length}",
+                std::net::Ipv6Addr::from(u128::from(ip) & mask)
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Describes a rule in warnings, e.g. `filter rule #3 'Allow DNS'`.
+pub(super) fn rule_label(rule: &Rule) -> String {
+    match rule.description.is_empty() {
+        true => format!("{} rule #{}", rule.r#type, rule.order),
+        false => format!(
+            "{} rule #{} '{}'",
+            rule.r#type, rule.order, rule.description
+        ),
+    }
+}
+
+/// Returns the rules of the given type, sorted by order.
+pub(super) fn sorted_rules<'a>(configuration: &'a Configuration, rule_type: &str) -> Vec<&'a Rule> {
+    let mut rules: Vec<&Rule> = configuration
+        .rules
+        .iter()
+        .filter(|rule| rule.r#type == rule_type)
+        .collect();
+
+    rules.sort_by_key(|rule| rule.order);
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ExportedAlias, IpVersion, export_alias, resolve_network, split_protocol, subnet_of,
+    };
+    use crate::{Alias, Configuration, IpAddress, NetworkInterface, SSHConfig};
+
+    fn alias(r#type: &str, value: &str) -> Alias {
+        Alias {
+            r#type: String::from(r#type),
+            name: String::from("test"),
+            value: String::from(value),
+            description: String::new(),
+            detail: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_export_alias() {
+        match export_alias(&alias("network", "10.0.0.0/8 172.16.0.1")) {
+            Ok(ExportedAlias::Addresses {
+                version,
+                elements,
+                interval,
+            }) => {
+                assert_eq!(version, IpVersion::V4);
+                assert_eq!(elements, vec!["10.0.0.0/8", "172.16.0.1"]);
+                assert!(interval);
+            }
+            _ => panic!("Expected addresses"),
+        }
+
+        match export_alias(&alias("port", "80\n443 8000:8080")) {
+            Ok(ExportedAlias::Ports(ports)) => assert_eq!(ports, vec!["80", "443", "8000-8080"]),
+            _ => panic!("Expected ports"),
+        }
+
+        assert!(export_alias(&alias("host", "10.0.0.1 ::1")).is_err());
+        assert!(export_alias(&alias("host", "example.com")).is_err());
+        assert!(export_alias(&alias("urltable", "https://example.com/list.txt")).is_err());
+    }
+
+    #[test]
+    fn test_split_protocol() {
+        assert_eq!(split_protocol("IPv4/tcp"), (IpVersion::V4, vec!["tcp"]));
+        assert_eq!(
+            split_protocol("IPv6/tcp/udp"),
+            (IpVersion::V6, vec!["tcp", "udp"])
+        );
+        assert_eq!(split_protocol("none/any"), (IpVersion::Any, vec![]));
+    }
+
+    #[test]
+    fn test_resolve_network() {
+        let configuration = Configuration {
+            rules: vec![],
+            aliases: vec![],
+            interfaces: vec![NetworkInterface {
+                name: String::from("lan"),
+                device: String::from("eth1"),
+                addresses: vec![IpAddress {
+                    address: String::from("192.168.1.1/24"),
+                    version: 4,
+                }],
+            }],
+            raw_content: String::new(),
+            hostname: String::new(),
+            gui_protocol: String::new(),
+            ssh: SSHConfig {
+                enabled: false,
+                port: 22,
+            },
+        };
+
+        assert_eq!(
+            resolve_network(&configuration, "lan"),
+            Ok(vec![String::from("192.168.1.0/24")])
+        );
+        assert_eq!(
+            resolve_network(&configuration, "lanip"),
+            Ok(vec![String::from("192.168.1.1")])
+        );
+        assert!(resolve_network(&configuration, "opt1").is_err());
+
+        assert_eq!(
+            subnet_of("2001:db8::1/64"),
+            Some(String::from("2001:db8::/64"))
+        );
+        assert_eq!(subnet_of("10.1.2.3/0"), Some(String::from("0.0.0.0/0")));
+    }
+}
@@ -0,0 +1,70 @@
+use nullnet_libconfmon::InterfaceSnapshot;
+
+use crate::models::{IpAddress, NetworkInterface};
+
+/// A parser building the network interfaces list of a Linux host.
+pub struct LinuxInterfacesParser {}
+
+impl LinuxInterfacesParser {
+    /// Converts the interfaces reported by the operating system into `NetworkInterface` entries.
+    ///
+    /// Linux rulesets don't assign names to interfaces, so both the `name` and the `device`
+    /// of each entry are the kernel interface name.
+    ///
+    /// # Arguments
+    /// * `os_interfaces` - The interfaces captured on the host.
+    ///
+    /// # Returns
+    /// A vector of `NetworkInterface` structs, sorted by name.
+    pub fn parse(os_interfaces: Vec<InterfaceSnapshot>) -> Vec<NetworkInterface> {
+        let mut interfaces: Vec<NetworkInterface> = os_interfaces
+            .into_iter()
+            .map(|iface| NetworkInterface {
+                name: iface.name.clone(),
+                device: iface.name,
+                addresses: iface
+                    .ip_addresses
+                    .iter()
+                    .map(|addr| IpAddress {
+                        address: addr.to_string(),
+                        version: if addr.is_ipv4() { 4 } else { 6 },
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+        interfaces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_os_interfaces() {
+        let iface_data = InterfaceSnapshot {
+            name: String::from("eth0"),
+            is_up: true,
+            is_loopback: false,
+            is_multicast: true,
+            is_broadcast: true,
+            mac_address: None,
+            interface_index: None,
+            ip_addresses: vec!["192.168.1.1".parse().unwrap(), "fe80::1".parse().unwrap()],
+            ..Default::default()
+        };
+
+        let interfaces = LinuxInterfacesParser::parse(vec![iface_data]);
+
+        assert_eq!(interfaces.len(), 1);
+        assert_eq!(interfaces[0].name, "eth0");
+        assert_eq!(interfaces[0].device, "eth0");
+        assert_eq!(interfaces[0].addresses.len(), 2);
+        assert_eq!(interfaces[0].addresses[0].address, "192.168.1.1");
+        assert_eq!(interfaces[0].addresses[0].version, 4);
+        assert_eq!(interfaces[0].addresses[1].address, "fe80::1");
+        assert_eq!(interfaces[0].addresses[1].version, 6);
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::HashMap;
+
+use super::export::{
+    AddressMatch, DEFAULT_INTERFACE_VALUE, ExportedAlias, PortMatch, export_alias, resolve_address,
+    resolve_interface, resolve_port, rule_label, sorted_rules, split_protocol,
+};
+use crate::{Configuration, IpVersion, Rule};
+
+/// Name of the chain holding the filter rules, shared by the `INPUT` and `FORWARD` chains.
+const FILTER_CHAIN_NAME: &str = "FILTER_RULES";
+/// Maximum number of ports of a `multiport` match, a range counting as two.
+const MULTIPORT_LIMIT: usize = 15;
+
+/// The output of `IptablesExporter::export`.
+///
+/// # Fields
+/// - `ipv4`: The IPv4 ruleset, loadable with `iptables-restore`.
+/// - `ipv6`: The IPv6 ruleset, loadable with `ip6tables-restore`.
+/// - `ipsets`: The sets referenced by the rulesets, loadable with `ipset restore` (to be loaded first).
+/// - `warnings`: The constructs that could not be expressed, and what was done with them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IptablesExport {
+    pub ipv4: String,
+    pub ipv6: String,
+    pub ipsets: String,
+    pub warnings: Vec<String>,
+}
+
+/// An exporter turning a `Configuration` into `iptables-save` and `ip6tables-save` rulesets.
+pub struct IptablesExporter {}
+
+impl IptablesExporter {
+    /// Exports the rules and aliases of a configuration as `iptables-save` and `ip6tables-save` rulesets.
+    ///
+    /// - Rules are split by the IP version prefix of `Rule.protocol`. Rules for both versions
+    ///   are written to both rulesets, unless their addresses belong to a single version.
+    /// - Host and network aliases become ipsets, matched with `-m set --match-set`.
+    ///   Port aliases are expanded into `multiport` matches.
+    /// - Filter rules are written in order to a dedicated chain, jumped to from `INPUT` and `FORWARD`.
+    ///   Both chains drop by default and accept established connections.
+    ///   Rules matching both `tcp` and `udp` are written once per protocol.
+    /// - NAT rules are written to `PREROUTING`, commented out: their redirect target is not part
+    ///   of `Configuration` and must be completed by hand.
+    /// - Disabled rules are written commented out.
+    ///
+    /// Interfaces are mapped to their `NetworkInterface.device`. Rules and aliases that cannot be
+    /// expressed (e.g. outbound rules) are left out, and reported in the warnings.
+    ///
+    /// # Arguments
+    /// * `configuration` - The configuration to export.
+    ///
+    /// # Returns
+    /// An `IptablesExport` holding the rulesets, the ipsets and the warnings.
+    pub fn export(configuration: &Configuration) -> IptablesExport {
+        let mut warnings = vec![];
+        let mut aliases = HashMap::new();
+        let mut ipsets = String::new();
+
+        for alias in &configuration.aliases {
+            let exported = match export_alias(alias) {
+                Ok(exported) => exported,
+                Err(reason) => {
+                    warnings.push(format!("alias '{}' was skipped: {reason}", alias.name));
+                    continue;
+                }
+            };
+
+            if let ExportedAlias::Addresses {
+                version,
+                elements,
+                interval,
+            } = &exported
+            {
+                let set_type = if *interval { "hash:net" } else { "hash:ip" };
+                let family = match version {
+                    IpVersion::V6 => "inet6",
+                    _ => "inet",
+                };
+
+                ipsets.push_str(&format!(
+                    "create {} {set_type} family {family}\n",
+                    alias.name
+                ));
+
+                for element in elements {
+                    ipsets.push_str(&format!("add {} {element}\n", alias.name));
+                }
+            }
+
+            aliases.insert(alias.name.as_str(), exported);
+        }
+
+        let mut filter = [vec![], vec![]];
+        let mut nat = [vec![], vec![]];
+
+        for rule in sorted_rules(configuration, "filter")
+            .into_iter()
+            .chain(sorted_rules(configuration, "nat"))
+        {
+            let (chain, output) = match rule.r#type.as_str() {
+                "nat" => ("PREROUTING", &mut nat),
+                _ => (FILTER_CHAIN_NAME, &mut filter),
+            };
+
+            let rendered = [IpVersion::V4, IpVersion::V6]
+                .map(|family| render_rule(configuration, rule, chain, family, &aliases));
+
+            if let Some(Err(reason)) = rendered.iter().find(|result| result.is_err()) {
+                warnings.push(format!("{} was skipped: {reason}", rule_label(rule)));
+                continue;
+            }
+
+            let commented = rule.disabled || rule.r#type == "nat";
+
+            if rule.r#type == "nat" && !rule.disabled {
+                warnings.push(format!(
+                    "{} was commented out: the redirect target is not part of the configuration",
+                    rule_label(rule)
+                ));
+            }
+
+            for (lines, rendered) in output.iter_mut().zip(rendered) {
+                for line in rendered.into_iter().flatten().flatten() {
+                    lines.push(match commented {
+                        true => format!("# {line}"),
+                        false => line,
+                    });
+                }
+            }
+        }
+
+        let [filter_v4, filter_v6] = filter;
+        let [nat_v4, nat_v6] = nat;
+
+        IptablesExport {
+            ipv4: render_ruleset(&filter_v4, &nat_v4),
+            ipv6: render_ruleset(&filter_v6, &nat_v6),
+            ipsets,
+            warnings,
+        }
+    }
+}
+
+/// Renders a complete `iptables-save` ruleset around the rule lines of one IP version.
+fn render_ruleset(filter: &[String], nat: &[String]) -> String {
+    let mut ruleset = String::from("*filter\n");
+    ruleset.push_str(":INPUT DROP [0:0]\n");
+    ruleset.push_str(":FORWARD DROP [0:0]\n");
+    ruleset.push_str(":OUTPUT ACCEPT [0:0]\n");
+    ruleset.push_str(&format!(":{FILTER_CHAIN_NAME} - [0:0]\n"));
+    ruleset.push_str("-A INPUT -i lo -j ACCEPT\n");
+    ruleset.push_str("-A INPUT -m conntrack --ctstate RELATED,ESTABLISHED -j ACCEPT\n");
+    ruleset.push_str(&format!("-A INPUT -j {FILTER_CHAIN_NAME}\n"));
+    ruleset.push_str("-A FORWARD -m conntrack --ctstate RELATED,ESTABLISHED -j ACCEPT\n");
+    ruleset.push_str(&format!("-A FORWARD -j {FILTER_CHAIN_NAME}\n"));
+
+    for line in filter {
+        ruleset.push_str(line);
+        ruleset.push('\n');
+    }
+
+    ruleset.push_str("COMMIT\n");
+    ruleset.push_str("*nat\n");
+    ruleset.push_str(":PREROUTING ACCEPT [0:0]\n");
+    ruleset.push_str(":INPUT ACCEPT [0:0]\n");
+    ruleset.push_str(":OUTPUT ACCEPT [0:0]\n");
+    ruleset.push_str(":POSTROUTING ACCEPT [0:0]\n");
+
+    for line in nat {
+        ruleset.push_str(line);
+        ruleset.push('\n');
+    }
+
+    ruleset.push_str("COMMIT\n");
+    ruleset
+}
+
+/// Renders a rule as `-A` lines for one IP version.
+///
+/// # Returns
+/// * `Ok(None)` - If the rule does not apply to `family`.
+/// * `Ok(Some(Vec<String>))` - The lines, one per protocol.
+/// * `Err(String)` - Why the rule cannot be expressed.
+fn render_rule(
+    configuration: &Configuration,
+    rule: &Rule,
+    chain: &str,
+    family: IpVersion,
+    aliases: &HashMap<&str, ExportedAlias>,
+) -> Result<Option<Vec<String>>, String> {
+    if rule.direction == "out" {
+        return Err(String::from(
+            "outbound rules are not exported, rules are applied to inbound traffic",
+        ));
+    }
+
+    let (version, protocols) = split_protocol(&rule.protocol);
+    let mut applies = version.overlaps(family);
+    let mut matches = vec![];
+
+    if rule.interface != DEFAULT_INTERFACE_VALUE {
+        let device = resolve_interface(configuration, &rule.interface)
+            .ok_or_else(|| format!("interface '{}' has no device", rule.interface))?;
+
+        matches.push(format!("-i {device}"));
+    }
+
+    let endpoints = [
+        (
+            "-s",
+            "src",
+            &rule.source_addr,
+            &rule.source_type,
+            rule.source_inversed,
+        ),
+        (
+            "-d",
+            "dst",
+            &rule.destination_addr,
+            &rule.destination_type,
+            rule.destination_inversed,
+        ),
+    ];
+
+    for (option, direction, addr, r#type, inversed) in endpoints {
+        let Some((addr_version, value)) = resolve_address(configuration, addr, r#type, aliases)?
+        else {
+            continue;
+        };
+
+        if !version.overlaps(addr_version) {
+            return Err(format!(
+                "'{addr}' does not match the IP version of the rule"
+            ));
+        }
+
+        applies &= addr_version == family;
+        let negation = if inversed { "! " } else { "" };
+
+        match value {
+            AddressMatch::Alias(name) => {
+                matches.push(format!("-m set {negation}--match-set {name} {direction}"));
+            }
+            AddressMatch::Addresses(elements) if inversed && elements.len() > 1 => {
+                return Err(format!(
+                    "'{addr}' holds several addresses and cannot be negated"
+                ));
+            }
+            AddressMatch::Addresses(elements) => {
+                matches.push(format!("{negation}{option} {}", elements.join(",")));
+            }
+        }
+    }
+
+    let mut ports = vec![];
+
+    for (single, multiple, port) in [
+        ("--sport", "--sports", &rule.source_port),
+        ("--dport", "--dports", &rule.destination_port),
+    ] {
+        let elements: Vec<String> = match resolve_port(port, aliases)? {
+            Some(PortMatch::Alias(_, elements)) => elements.to_vec(),
+            Some(PortMatch::Ports(elements)) => elements,
+            None => continue,
+        };
+
+        let elements: Vec<String> = elements.iter().map(|e| e.replace('-', ":")).collect();
+        let count: usize = elements
+            .iter()
+            .map(|e| if e.contains(':') { 2 } else { 1 })
+            .sum();
+
+        if count > MULTIPORT_LIMIT {
+            return Err(format!(
+                "'{port}' holds more than {MULTIPORT_LIMIT} ports, the limit of a multiport match"
+            ));
+        }
+
+        match elements.as_slice() {
+            [element] => ports.push((false, format!("{single} {element}"))),
+            elements => ports.push((true, format!("{multiple} {}", elements.join(",")))),
+        }
+    }
+
+    if protocols.is_empty() && !ports.is_empty() {
+        return Err(String::from("ports are only supported with a protocol"));
+    }
+
+    let verdict = match (rule.r#type.as_str(), rule.policy.as_str()) {
+        ("nat", _) => "-j DNAT --to-destination <target>",
+        (_, "pass") => "-j ACCEPT",
+        (_, "block") => "-j DROP",
+        (_, "reject") => "-j REJECT",
+        (_, policy) => return Err(format!("policy '{policy}' cannot be expressed")),
+    };
+
+    if !applies {
+        return Ok(None);
+    }
+
+    let comment = match rule.description.is_empty() {
+        true => None,
+        false => Some(format!(
+            "-m comment --comment \"{}\"",
+            rule.description.replace('\\', "\\\\").replace('"', "\\\"")
+        )),
+    };
+
+    let protocols = match protocols.is_empty() {
+        true => vec![None],
+        false => protocols.into_iter().map(Some).collect(),
+    };
+
+    let lines = protocols
+        .into_iter()
+        .map(|protocol| {
+            let mut args = vec![format!("-A {chain}")];
+            args.extend(matches.iter().cloned());
+
+            if let Some(protocol) = protocol {
+                let protocol = match (protocol, family) {
+                    ("icmp", IpVersion::V6) => "ipv6-icmp",
+                    (protocol, _) => protocol,
+                };
+
+                args.push(format!("-p {protocol}"));
+
+                for (multiport, port) in &ports {
+                    let module = if *multiport { "multiport" } else { protocol };
+                    args.push(format!("-m {module} {port}"));
+                }
+            }
+
+            args.extend(comment.iter().cloned());
+            args.push(String::from(verdict));
+            args.join(" ")
+        })
+        .collect();
+
+    Ok(Some(lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FILTER_CHAIN_NAME, IptablesExporter};
+    use crate::{
+        Configuration, Rule,
+        linux::iptables_parser::LinuxIptablesParser,
+        test_utils::{self, alias, interface, rule},
+    };
+
+    fn configuration() -> Configuration {
+        let mut web = rule(0, "pass", "none/tcp/udp", "Web \"quic\"");
+        web.destination_addr = String::from("Servers");
+        web.destination_port = String::from("Ports");
+
+        let mut ssh = rule(1, "block", "IPv4/tcp", "");
+        ssh.source_addr = String::from("lan");
+        ssh.source_type = String::from("network");
+        ssh.source_inversed = true;
+        ssh.destination_port = String::from("22");
+
+        let icmp = rule(2, "pass", "none/icmp", "Ping");
+
+        let mut v6 = rule(3, "reject", "IPv6/any", "Old rule");
+        v6.disabled = true;
+        v6.source_addr = String::from("2001:db8::/32");
+
+        let mut mixed = rule(4, "pass", "IPv4/any", "Mismatch");
+        mixed.destination_addr = String::from("2001:db8::1");
+
+        let mut forward = rule(0, "pass", "IPv4/tcp", "HTTPS");
+        forward.r#type = String::from("nat");
+        forward.interface = String::from("wan");
+        forward.destination_port = String::from("443");
+
+        test_utils::configuration(
+            vec![web, ssh, icmp, v6, mixed, forward],
+            vec![
+                alias("host", "Servers", "10.0.0.10 10.0.0.11", "Web servers"),
+                alias("port", "Ports", "80 443 8000:8080", ""),
+                alias("url", "Feeds", "https://example.com/list.txt", ""),
+            ],
+            vec![
+                interface("wan", "eth0", &[]),
+                interface("lan", "eth1", &["192.168.1.1/24"]),
+            ],
+        )
+    }
+
+    const HEADER: &str = "*filter
+:INPUT DROP [0:0]
+:FORWARD DROP [0:0]
+:OUTPUT ACCEPT [0:0]
+:FILTER_RULES - [0:0]
+-A INPUT -i lo -j ACCEPT
+-A INPUT -m conntrack --ctstate RELATED,ESTABLISHED -j ACCEPT
+-A INPUT -j FILTER_RULES
+-A FORWARD -m conntrack --ctstate RELATED,ESTABLISHED -j ACCEPT
+-A FORWARD -j FILTER_RULES
+";
+
+    const NAT_HEADER: &str = "COMMIT
+*nat
+:PREROUTING ACCEPT [0:0]
+:INPUT ACCEPT [0:0]
+:OUTPUT ACCEPT [0:0]
+:POSTROUTING ACCEPT [0:0]
+";
+
+    #[test]
+    fn test_export_rulesets() {
+        let export = IptablesExporter::export(&configuration());
+
+        assert_eq!(
+            export.ipsets,
+            "create Servers hash:ip family inet\nadd Servers 10.0.0.10\nadd Servers 10.0.0.11\n"
+        );
+
+        assert_eq!(
+            export.ipv4,
+            format!(
+                "{HEADER}\
+-A FILTER_RULES -i eth1 -m set --match-set Servers dst -p tcp -m multiport --dports 80,443,8000:8080 -m comment --comment \"Web \\\"quic\\\"\" -j ACCEPT
+-A FILTER_RULES -i eth1 -m set --match-set Servers dst -p udp -m multiport --dports 80,443,8000:8080 -m comment --comment \"Web \\\"quic\\\"\" -j ACCEPT
+-A FILTER_RULES -i eth1 ! -s 192.168.1.0/24 -p tcp -m tcp --dport 22 -j DROP
+-A FILTER_RULES -i eth1 -p icmp -m comment --comment \"Ping\" -j ACCEPT
+{NAT_HEADER}\
+# -A PREROUTING -i eth0 -p tcp -m tcp --dport 443 -m comment --comment \"HTTPS\" -j DNAT --to-destination <target>
+COMMIT
+"
+            )
+        );
+
+        assert_eq!(
+            export.ipv6,
+            format!(
+                "{HEADER}\
+-A FILTER_RULES -i eth1 -p ipv6-icmp -m comment --comment \"Ping\" -j ACCEPT
+# -A FILTER_RULES -i eth1 -s 2001:db8::/32 -m comment --comment \"Old rule\" -j REJECT
+{NAT_HEADER}\
+COMMIT
+"
+            )
+        );
+
+        assert_eq!(
+            export.warnings,
+            vec![
+                "alias 'Feeds' was skipped: aliases of type 'url' are not supported",
+                "filter rule #4 'Mismatch' was skipped: '2001:db8::1' does not match the IP version of the rule",
+                "nat rule #0 'HTTPS' was commented out: the redirect target is not part of the configuration",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exported_ruleset_can_be_parsed() {
+        let export = IptablesExporter::export(&configuration());
+
+        // Jumps are not followed: move the rules to `INPUT` to parse them
+        let ruleset = export
+            .ipv4
+            .replace(&format!("-A {FILTER_CHAIN_NAME} "), "-A INPUT ");
+        let rules = LinuxIptablesParser::parse(&ruleset, "IPv4");
+
+        let web: Vec<&Rule> = rules
+            .iter()
+            .filter(|rule| rule.description == "Web \"quic\"")
+            .collect();
+
+        assert_eq!(web.len(), 2);
+        assert_eq!(web[0].protocol, "IPv4/tcp");
+        assert_eq!(web[1].protocol, "IPv4/udp");
+        assert_eq!(web[0].interface, "eth1");
+        assert_eq!(web[0].destination_addr, "Servers");
+        assert_eq!(web[0].destination_port, "80,443,8000-8080");
+
+        let ssh = rules.iter().find(|rule| rule.policy == "block").unwrap();
+        assert_eq!(ssh.source_addr, "192.168.1.0/24");
+        assert!(ssh.source_inversed);
+        assert_eq!(ssh.destination_port, "22");
+    }
+}
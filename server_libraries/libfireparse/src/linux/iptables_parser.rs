@@ -0,0 +1,479 @@
+use super::chain_direction;
+use crate::Rule;
+
+const ANY_ADDR_VALUE: &str = "*";
+const ANY_PORT_VALUE: &str = "*";
+const DEFAULT_TYPE_VALUE: &str = "address";
+const DEFAULT_INTERFACE_VALUE: &str = "none";
+
+/// Match extensions whose options are represented by `Rule`.
+const MODELED_MATCHES: [&str; 7] = [
+    "tcp",
+    "udp",
+    "sctp",
+    "udplite",
+    "multiport",
+    "comment",
+    "set",
+];
+
+/// A parser for extracting firewall and NAT rules from `iptables-save` / `ip6tables-save` output.
+pub struct LinuxIptablesParser {}
+
+impl LinuxIptablesParser {
+    /// Parses an `iptables-save` formatted ruleset.
+    ///
+    /// # Arguments
+    /// * `content` - The ruleset text, as found in `/etc/iptables/rules.v4` or `/etc/iptables/rules.v6`.
+    /// * `ip_version` - The IP version prefix of `Rule.protocol` (`"IPv4"` or `"IPv6"`).
+    ///
+    /// # Returns
+    /// A `Vec<Rule>` containing the rules of the built-in chains of the `filter` and `nat` tables.
+    /// Each rule takes the direction of its chain, and the policy of each built-in `filter` chain
+    /// is appended as a rule matching any traffic after the rules of the table.
+    ///
+    /// Jumps are not followed: rules of `FORWARD` and user-defined chains are skipped, as are
+    /// rules whose target does not decide on the traffic (e.g. a user-defined chain, `RETURN`
+    /// or `LOG`). Rules of other tables, rules without a target and rules using matches `Rule` cannot
+    /// represent (e.g. `-m conntrack`, `-m limit` or a negated interface, protocol or port)
+    /// are skipped, since dropping the match would turn them into broader rules.
+    pub fn parse(content: &str, ip_version: &str) -> Vec<Rule> {
+        let mut rules: Vec<Rule> = vec![];
+        let mut table = String::new();
+        let mut policies: Vec<(String, String)> = vec![];
+
+        for line in content.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix('*') {
+                Self::append_policies(&mut rules, &mut policies, ip_version);
+                table = name.to_string();
+                continue;
+            }
+
+            if line == "COMMIT" {
+                Self::append_policies(&mut rules, &mut policies, ip_version);
+                continue;
+            }
+
+            if table != "filter" && table != "nat" {
+                continue;
+            }
+
+            if let Some(declaration) = line.strip_prefix(':') {
+                let mut parts = declaration.split_whitespace();
+
+                if table == "filter"
+                    && let (Some(chain), Some(policy)) = (parts.next(), parts.next())
+                    && policy != "-"
+                {
+                    policies.push((chain.to_string(), policy.to_string()));
+                }
+
+                continue;
+            }
+
+            if let Some(args) = line.strip_prefix("-A ") {
+                let order = rules.iter().filter(|r| r.r#type == table).count() as u64;
+
+                if let Some(mut rule) = Self::parse_rule(&split_args(args), ip_version) {
+                    rule.r#type = table.clone();
+                    rule.order = order;
+                    rules.push(rule);
+                }
+            }
+        }
+
+        Self::append_policies(&mut rules, &mut policies, ip_version);
+
+        rules
+    }
+
+    /// Appends the policies of the built-in chains of a `filter` table as rules matching any traffic.
+    fn append_policies(
+        rules: &mut Vec<Rule>,
+        policies: &mut Vec<(String, String)>,
+        ip_version: &str,
+    ) {
+        for (chain, policy) in policies.drain(..) {
+            let (Some(direction), Some(policy)) =
+                (chain_direction(&chain), Self::map_target(&policy))
+            else {
+                continue;
+            };
+
+            let mut rule = empty_rule(direction);
+            rule.r#type = String::from("filter");
+            rule.policy = policy;
+            rule.protocol = format!("{ip_version}/any");
+            rule.description = format!("Default policy of chain {chain}");
+            rule.order = rules.iter().filter(|r| r.r#type == "filter").count() as u64;
+            rules.push(rule);
+        }
+    }
+
+    /// Parses the arguments of an `-A <chain>` line.
+    ///
+    /// # Returns
+    /// `Some(Rule)` if the rule belongs to a modeled built-in chain, has a verdict as its target
+    /// and only uses matches `Rule` can represent, `None` otherwise.
+    fn parse_rule(args: &[String], ip_version: &str) -> Option<Rule> {
+        let mut rule = empty_rule(chain_direction(args.first()?)?);
+
+        let mut protocol = String::from("any");
+        let mut out_interface = None;
+        let mut policy = None;
+        let mut negated = false;
+
+        // The first argument is the chain name
+        let mut iter = args.iter().skip(1);
+
+        while let Some(arg) = iter.next() {
+            if arg == "!" {
+                negated = true;
+                continue;
+            }
+
+            // `Rule` can only negate addresses
+            if negated
+                && !matches!(
+                    arg.as_str(),
+                    "-s" | "--source" | "-d" | "--destination" | "--match-set"
+                )
+            {
+                return None;
+            }
+
+            match arg.as_str() {
+                "-p" | "--protocol" => protocol = iter.next().cloned().unwrap_or(protocol),
+                "-s" | "--source" => {
+                    rule.source_addr = iter.next().cloned().unwrap_or_default();
+                    rule.source_inversed = negated;
+                }
+                "-d" | "--destination" => {
+                    rule.destination_addr = iter.next().cloned().unwrap_or_default();
+                    rule.destination_inversed = negated;
+                }
+                "-i" | "--in-interface" => {
+                    rule.interface = iter.next().cloned().unwrap_or_default();
+                }
+                "-o" | "--out-interface" => out_interface = iter.next().cloned(),
+                "--sport" | "--source-port" | "--sports" | "--source-ports" => {
+                    rule.source_port = iter.next().map(|p| p.replace(':', "-")).unwrap_or_default();
+                }
+                "--dport" | "--destination-port" | "--dports" | "--destination-ports" => {
+                    rule.destination_port =
+                        iter.next().map(|p| p.replace(':', "-")).unwrap_or_default();
+                }
+                "--comment" => rule.description = iter.next().cloned().unwrap_or_default(),
+                "--match-set" => {
+                    let name = iter.next().cloned().unwrap_or_default();
+                    let direction = iter.next().map(String::as_str).unwrap_or("src");
+
+                    if direction.starts_with("src") {
+                        rule.source_addr = name;
+                        rule.source_inversed = negated;
+                    } else {
+                        rule.destination_addr = name;
+                        rule.destination_inversed = negated;
+                    }
+                }
+                "-j" | "--jump" | "-g" | "--goto" => {
+                    policy = Some(Self::map_target(iter.next()?)?);
+                    // The remaining arguments are options of the target
+                    break;
+                }
+                "-m" | "--match" => {
+                    if !iter
+                        .next()
+                        .is_some_and(|name| MODELED_MATCHES.contains(&name.as_str()))
+                    {
+                        return None;
+                    }
+                }
+                // Any other match (e.g. `--icmp-type`, `--tcp-flags` or `-f`) cannot be represented
+                _ => return None,
+            }
+
+            negated = false;
+        }
+
+        if rule.interface == DEFAULT_INTERFACE_VALUE
+            && let Some(out_interface) = out_interface
+        {
+            rule.interface = out_interface;
+        }
+
+        rule.policy = policy?;
+        rule.protocol = format!("{ip_version}/{protocol}");
+
+        Some(rule)
+    }
+
+    /// Maps an iptables target to the policy naming used by the other parsers.
+    ///
+    /// # Arguments
+    /// * `target` - The value of the `-j` option.
+    ///
+    /// # Returns
+    /// `"pass"`, `"block"` or `"reject"` for the standard verdicts, `"pass"` for NAT targets,
+    /// and `None` otherwise (e.g. user-defined chains, `RETURN` or `LOG`).
+    fn map_target(target: &str) -> Option<String> {
+        match target {
+            "ACCEPT" | "DNAT" | "SNAT" | "MASQUERADE" | "REDIRECT" | "NETMAP" => {
+                Some(String::from("pass"))
+            }
+            "DROP" => Some(String::from("block")),
+            "REJECT" => Some(String::from("reject")),
+            _ => None,
+        }
+    }
+}
+
+/// Builds a rule matching any traffic of a direction, without a type nor a policy.
+fn empty_rule(direction: &str) -> Rule {
+    Rule {
+        disabled: false,
+        r#type: String::new(),
+        policy: String::new(),
+        protocol: String::new(),
+        source_inversed: false,
+        source_port: String::from(ANY_PORT_VALUE),
+        source_addr: String::from(ANY_ADDR_VALUE),
+        source_type: String::from(DEFAULT_TYPE_VALUE),
+        destination_port: String::from(ANY_PORT_VALUE),
+        destination_addr: String::from(ANY_ADDR_VALUE),
+        destination_type: String::from(DEFAULT_TYPE_VALUE),
+        destination_inversed: false,
+        description: String::new(),
+        interface: String::from(DEFAULT_INTERFACE_VALUE),
+        direction: direction.to_string(),
+        id: String::new(),
+        updated_at: None,
+        order: 0,
+    }
+}
+
+/// Splits a rule line into arguments, honouring double quotes and backslash escapes.
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                    has_arg = true;
+                }
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+
+    if has_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LinuxIptablesParser;
+
+    const RULES_V4: &str = r#"# Generated by iptables-save v1.8.7
+*filter
+:INPUT DROP [0:0]
+:FORWARD DROP [0:0]
+:OUTPUT ACCEPT [0:0]
+-A INPUT -i lo -j ACCEPT
+-A INPUT -m conntrack --ctstate RELATED,ESTABLISHED -j ACCEPT
+-A INPUT -s 10.0.0.0/8 ! -d 10.0.0.1/32 -i eth1 -p tcp -m tcp --dport 22 -m comment --comment "SSH from LAN" -j ACCEPT
+-A INPUT -p udp -m multiport --dports 1000:2000,3000 -j REJECT --reject-with icmp-port-unreachable
+-A INPUT -m set --match-set blocked src -j DROP
+-A INPUT -p tcp -j LOG --log-prefix "dropped: "
+-A INPUT -m limit --limit 5/min
+COMMIT
+*nat
+:PREROUTING ACCEPT [0:0]
+-A PREROUTING -i eth0 -p tcp --dport 8080 -j DNAT --to-destination 192.168.1.10:80
+COMMIT
+*mangle
+:PREROUTING ACCEPT [0:0]
+-A PREROUTING -j MARK --set-mark 1
+COMMIT
+"#;
+
+    #[test]
+    fn test_parse_filter_rules() {
+        let rules = LinuxIptablesParser::parse(RULES_V4, "IPv4");
+        let filter: Vec<_> = rules.iter().filter(|r| r.r#type == "filter").collect();
+
+        assert_eq!(filter.len(), 6);
+
+        assert_eq!(filter[0].policy, "pass");
+        assert_eq!(filter[0].interface, "lo");
+        assert_eq!(filter[0].protocol, "IPv4/any");
+        assert_eq!(filter[0].direction, "in");
+
+        assert_eq!(filter[1].source_addr, "10.0.0.0/8");
+        assert!(!filter[1].source_inversed);
+        assert_eq!(filter[1].destination_addr, "10.0.0.1/32");
+        assert!(filter[1].destination_inversed);
+        assert_eq!(filter[1].destination_port, "22");
+        assert_eq!(filter[1].interface, "eth1");
+        assert_eq!(filter[1].protocol, "IPv4/tcp");
+        assert_eq!(filter[1].description, "SSH from LAN");
+        assert_eq!(filter[1].order, 1);
+
+        assert_eq!(filter[2].policy, "reject");
+        assert_eq!(filter[2].destination_port, "1000-2000,3000");
+        assert_eq!(filter[2].protocol, "IPv4/udp");
+
+        assert_eq!(filter[3].policy, "block");
+        assert_eq!(filter[3].source_addr, "blocked");
+    }
+
+    #[test]
+    fn test_skip_unmodeled_matches() {
+        let rules = LinuxIptablesParser::parse(RULES_V4, "IPv4");
+
+        // Neither the conntrack rule nor the rate limited one may become a rule matching any traffic
+        assert!(
+            !rules
+                .iter()
+                .any(|r| r.policy == "pass" && r.interface == "none" && r.direction == "in")
+        );
+
+        let rules = LinuxIptablesParser::parse(
+            "*filter\n-A INPUT -p icmp --icmp-type echo-request -j ACCEPT\nCOMMIT\n",
+            "IPv4",
+        );
+        assert!(rules.is_empty());
+    }
+
+    #[test]
+    fn test_skip_negated_matches() {
+        for rule in [
+            "-A INPUT ! -i lo -p tcp --dport 22 -j ACCEPT",
+            "-A OUTPUT ! -o eth0 -j DROP",
+            "-A INPUT ! -p tcp -j DROP",
+            "-A INPUT -p tcp ! --dport 80 -j ACCEPT",
+            "-A INPUT -p udp ! --sport 53 -j DROP",
+        ] {
+            let rules = LinuxIptablesParser::parse(&format!("*filter\n{rule}\nCOMMIT\n"), "IPv4");
+            assert!(rules.is_empty(), "{rule}");
+        }
+
+        let rules = LinuxIptablesParser::parse(
+            "*filter\n-A INPUT ! -s 10.0.0.0/8 -m set ! --match-set blocked dst -j DROP\nCOMMIT\n",
+            "IPv4",
+        );
+
+        assert_eq!(rules.len(), 1);
+        assert!(rules[0].source_inversed);
+        assert_eq!(rules[0].destination_addr, "blocked");
+        assert!(rules[0].destination_inversed);
+    }
+
+    #[test]
+    fn test_skip_unmodeled_chains_and_targets() {
+        let rules = LinuxIptablesParser::parse(
+            r#"*filter
+:INPUT DROP [0:0]
+:FORWARD ACCEPT [0:0]
+:SSH - [0:0]
+-A INPUT -p tcp --dport 22 -j SSH
+-A INPUT -p tcp --dport 23 -g SSH
+-A INPUT -p tcp --dport 25 -j RETURN
+-A INPUT -p tcp --dport 80 -j ACCEPT
+-A FORWARD -i eth1 -j DROP
+-A SSH -s 10.0.0.0/8 -j ACCEPT
+COMMIT
+"#,
+            "IPv4",
+        );
+
+        let rules: Vec<_> = rules
+            .iter()
+            .map(|r| (r.policy.as_str(), r.destination_port.as_str(), r.order))
+            .collect();
+
+        assert_eq!(rules, vec![("pass", "80", 0), ("block", "*", 1)]);
+    }
+
+    #[test]
+    fn test_chain_policies() {
+        let rules = LinuxIptablesParser::parse(RULES_V4, "IPv4");
+        let filter: Vec<_> = rules.iter().filter(|r| r.r#type == "filter").collect();
+
+        let policies: Vec<_> = filter[4..]
+            .iter()
+            .map(|r| {
+                (
+                    r.policy.as_str(),
+                    r.direction.as_str(),
+                    r.description.as_str(),
+                    r.order,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            policies,
+            vec![
+                ("block", "in", "Default policy of chain INPUT", 4),
+                ("pass", "out", "Default policy of chain OUTPUT", 5),
+            ]
+        );
+
+        // Policies of NAT chains are not filter rules
+        assert_eq!(rules.iter().filter(|r| r.r#type == "nat").count(), 1);
+    }
+
+    #[test]
+    fn test_parse_nat_rules() {
+        let rules = LinuxIptablesParser::parse(RULES_V4, "IPv4");
+        let nat: Vec<_> = rules.iter().filter(|r| r.r#type == "nat").collect();
+
+        assert_eq!(nat.len(), 1);
+        assert_eq!(nat[0].policy, "pass");
+        assert_eq!(nat[0].interface, "eth0");
+        assert_eq!(nat[0].destination_port, "8080");
+        assert_eq!(nat[0].order, 0);
+    }
+
+    #[test]
+    fn test_parse_ipv6_rules() {
+        let rules = LinuxIptablesParser::parse(
+            "*filter\n:INPUT DROP [0:0]\n-A INPUT -p ipv6-icmp -j ACCEPT\nCOMMIT\n",
+            "IPv6",
+        );
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].protocol, "IPv6/ipv6-icmp");
+        assert_eq!(rules[1].protocol, "IPv6/any");
+        assert_eq!(rules[1].policy, "block");
+    }
+
+    #[test]
+    fn test_parse_empty_ruleset() {
+        let rules = LinuxIptablesParser::parse("", "IPv4");
+        assert!(rules.is_empty());
+    }
+}
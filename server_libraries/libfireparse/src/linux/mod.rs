@@ -0,0 +1,140 @@
+use crate::{
+    Configuration, FireparseError, SSHConfig,
+    utils::{self, find_in_snapshot},
+};
+use interfaces_parser::LinuxInterfacesParser;
+use iptables_parser::LinuxIptablesParser;
+use nftables_parser::LinuxNftablesParser;
+use nullnet_libconfmon::{InterfaceSnapshot, NETWORK_INTERFACES_ENTRY, Snapshot};
+
+mod export;
+mod interfaces_parser;
+mod iptables_exporter;
+mod iptables_parser;
+mod nftables_exporter;
+mod nftables_parser;
+
+pub use iptables_exporter::{IptablesExport, IptablesExporter};
+pub use nftables_exporter::{NftablesExport, NftablesExporter};
+
+const NFTABLES_CONFIG_FILENAME: &str = "nftables.conf";
+const IPTABLES_V4_RULES_FILENAME: &str = "rules.v4";
+const IPTABLES_V6_RULES_FILENAME: &str = "rules.v6";
+
+/// Returns the direction of the traffic going through a base chain (`"in"` or `"out"`).
+///
+/// # Arguments
+/// * `chain` - An iptables built-in chain (e.g. `INPUT`) or an nftables hook (e.g. `input`).
+///
+/// # Returns
+/// `None` for chains whose rules are not modeled: forwarded traffic (`FORWARD`) is neither
+/// to nor from the host, and user-defined chains only apply to the traffic jumping to them.
+fn chain_direction(chain: &str) -> Option<&'static str> {
+    match chain.to_ascii_lowercase().as_str() {
+        "input" | "prerouting" | "ingress" => Some("in"),
+        "output" | "postrouting" => Some("out"),
+        _ => None,
+    }
+}
+
+/// A parser for extracting configuration details from the nftables or iptables rulesets of a Linux host.
+pub struct LinuxParser {}
+
+impl LinuxParser {
+    /// Parses a Linux configuration snapshot and extracts firewall settings.
+    ///
+    /// # Arguments
+    /// * `snapshot` - A `Snapshot` containing `nftables.conf` and/or `rules.v4` / `rules.v6`,
+    ///   and network interface details.
+    ///
+    /// # Returns
+    /// * `Ok(Configuration)` - A `Configuration` struct
+    /// * `Err(FireparseError)` - If any part of the parsing process fails.
+    pub fn parse(snapshot: Snapshot) -> Result<Configuration, FireparseError> {
+        let nftables = LinuxParser::read_text_file(&snapshot, NFTABLES_CONFIG_FILENAME)?;
+        let iptables_v4 = LinuxParser::read_text_file(&snapshot, IPTABLES_V4_RULES_FILENAME)?;
+        let iptables_v6 = LinuxParser::read_text_file(&snapshot, IPTABLES_V6_RULES_FILENAME)?;
+
+        if nftables.is_none() && iptables_v4.is_none() && iptables_v6.is_none() {
+            return Err(FireparseError::ParserError(String::from(
+                "LinuxParser: neither nftables nor iptables rulesets are present in the snapshot",
+            )));
+        }
+
+        let mut rules = vec![];
+        let mut aliases = vec![];
+
+        if let Some(content) = nftables {
+            let (mut nft_rules, mut nft_aliases) = LinuxNftablesParser::parse(content);
+            rules.append(&mut nft_rules);
+            aliases.append(&mut nft_aliases);
+        }
+
+        for (content, ip_version) in [(iptables_v4, "IPv4"), (iptables_v6, "IPv6")] {
+            if let Some(content) = content {
+                rules.append(&mut LinuxIptablesParser::parse(content, ip_version));
+            }
+        }
+
+        // Each source numbers its rules from zero, keep the order unique per rule type
+        for rule_type in ["filter", "nat"] {
+            for (index, rule) in (0_u64..).zip(rules.iter_mut().filter(|r| r.r#type == rule_type)) {
+                rule.order = index;
+            }
+        }
+
+        let raw_content = [nftables, iptables_v4, iptables_v6]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .concat();
+
+        let interfaces = LinuxParser::parse_interfaces_info_from_snapshot(&snapshot)?;
+
+        Ok(Configuration {
+            raw_content: utils::encode_base64(raw_content.as_bytes()),
+            aliases,
+            rules,
+            interfaces: LinuxInterfacesParser::parse(interfaces),
+            hostname: utils::hostname_from_snapshot(&snapshot).unwrap_or_default(),
+            gui_protocol: String::from("none"),
+            ssh: SSHConfig {
+                enabled: false,
+                port: 22,
+            },
+        })
+    }
+
+    /// Looks up a ruleset file in the snapshot and decodes it as UTF-8.
+    fn read_text_file<'a>(
+        snapshot: &'a Snapshot,
+        filename: &str,
+    ) -> Result<Option<&'a str>, FireparseError> {
+        let Some(file) = find_in_snapshot(snapshot, filename) else {
+            return Ok(None);
+        };
+
+        std::str::from_utf8(&file.content).map(Some).map_err(|e| {
+            FireparseError::ParserError(format!(
+                "LinuxParser: Failed to parse '{filename}' blob as UTF-8: {e}"
+            ))
+        })
+    }
+
+    /// Extracts and deserializes network interface information from the snapshot.
+    fn parse_interfaces_info_from_snapshot(
+        snapshot: &Snapshot,
+    ) -> Result<Vec<InterfaceSnapshot>, FireparseError> {
+        let ifaces_data = find_in_snapshot(snapshot, NETWORK_INTERFACES_ENTRY).ok_or(
+            FireparseError::ParserError(format!(
+                "LinuxParser: '{NETWORK_INTERFACES_ENTRY}' file is missing in the snapshot"
+            )),
+        )?;
+
+        InterfaceSnapshot::deserialize_snapshot(&ifaces_data.content).map_err(|e| {
+            FireparseError::ParserError(format!(
+                "LinuxParser: Failed to deserialize network interfaces data from the snapshot. {e}"
+            ))
+        })
+    }
+}
@@ -0,0 +1,460 @@
+use std::collections::HashMap;
+
+use super::export::{
+    AddressMatch, DEFAULT_INTERFACE_VALUE, ExportedAlias, PortMatch, export_alias, resolve_address,
+    resolve_interface, resolve_port, rule_label, sorted_rules, split_protocol,
+};
+use crate::{Configuration, IpVersion, Rule};
+
+/// Name of the table holding the exported ruleset.
+const TABLE_NAME: &str = "nullnet";
+/// Name of the regular chain holding the filter rules, shared by the `input` and `forward` hooks.
+const FILTER_CHAIN_NAME: &str = "filter_rules";
+
+/// The output of `NftablesExporter::export`.
+///
+/// # Fields
+/// - `ruleset`: The ruleset, loadable with `nft -f`.
+/// - `warnings`: The constructs that could not be expressed, and what was done with them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NftablesExport {
+    pub ruleset: String,
+    pub warnings: Vec<String>,
+}
+
+/// An exporter turning a `Configuration` into an nftables ruleset.
+pub struct NftablesExporter {}
+
+impl NftablesExporter {
+    /// Exports the rules and aliases of a configuration as an nftables ruleset.
+    ///
+    /// Everything is written to a single `inet` table, which replaces the one written by a previous
+    /// export when loaded. Tables owned by other software are left untouched:
+    /// - Aliases become named sets, referenced by rules as `@name`.
+    /// - Filter rules are written in order to a regular chain, jumped to from the `input`
+    ///   and `forward` hooks. Both hooks drop by default and accept established connections,
+    ///   matching the stateful first-match semantics of pfSense and OPNsense.
+    /// - NAT rules are written to the `prerouting` hook, commented out: their redirect target
+    ///   is not part of `Configuration` and must be completed by hand.
+    /// - Disabled rules are written commented out.
+    ///
+    /// Interfaces are mapped to their `NetworkInterface.device`. Rules and aliases that cannot be
+    /// expressed (e.g. outbound rules) are left out, and reported in the warnings.
+    ///
+    /// # Arguments
+    /// * `configuration` - The configuration to export.
+    ///
+    /// # Returns
+    /// An `NftablesExport` holding the ruleset and the warnings.
+    pub fn export(configuration: &Configuration) -> NftablesExport {
+        let mut warnings = vec![];
+        let mut sets = HashMap::new();
+
+        // Declaring the table first lets it be deleted whether it exists or not
+        let mut ruleset = String::from("#!/usr/sbin/nft -f\n\n");
+        ruleset.push_str(&format!("table inet {TABLE_NAME} {{}}\n"));
+        ruleset.push_str(&format!("delete table inet {TABLE_NAME}\n\n"));
+        ruleset.push_str(&format!("table inet {TABLE_NAME} {{\n"));
+
+        for alias in &configuration.aliases {
+            let exported = match export_alias(alias) {
+                Ok(exported) => exported,
+                Err(reason) => {
+                    warnings.push(format!("alias '{}' was skipped: {reason}", alias.name));
+                    continue;
+                }
+            };
+
+            let (set_type, elements, interval) = match &exported {
+                ExportedAlias::Addresses {
+                    version,
+                    elements,
+                    interval,
+                } => {
+                    let set_type = match version {
+                        IpVersion::V6 => "ipv6_addr",
+                        _ => "ipv4_addr",
+                    };
+                    (set_type, elements, *interval)
+                }
+                ExportedAlias::Ports(ports) => {
+                    let interval = ports.iter().any(|port| port.contains('-'));
+                    ("inet_service", ports, interval)
+                }
+            };
+
+            ruleset.push_str(&format!("    set {} {{\n", alias.name));
+            ruleset.push_str(&format!("        type {set_type}\n"));
+
+            if interval {
+                ruleset.push_str("        flags interval\n");
+            }
+
+            if !elements.is_empty() {
+                ruleset.push_str(&format!(
+                    "        elements = {{ {} }}\n",
+                    elements.join(", ")
+                ));
+            }
+
+            if !alias.description.is_empty() {
+                ruleset.push_str(&format!("        comment {}\n", quote(&alias.description)));
+            }
+
+            ruleset.push_str("    }\n\n");
+            sets.insert(alias.name.as_str(), exported);
+        }
+
+        ruleset.push_str(&format!("    chain {FILTER_CHAIN_NAME} {{\n"));
+
+        for rule in sorted_rules(configuration, "filter") {
+            match render_rule(configuration, rule, &sets) {
+                Ok(line) if rule.disabled => ruleset.push_str(&format!("        # {line}\n")),
+                Ok(line) => ruleset.push_str(&format!("        {line}\n")),
+                Err(reason) => warnings.push(format!("{} was skipped: {reason}", rule_label(rule))),
+            }
+        }
+
+        ruleset.push_str("    }\n\n");
+
+        ruleset.push_str("    chain input {\n");
+        ruleset.push_str("        type filter hook input priority filter; policy drop;\n");
+        ruleset.push_str("        ct state established,related accept\n");
+        ruleset.push_str("        iifname \"lo\" accept\n");
+        ruleset.push_str(&format!("        jump {FILTER_CHAIN_NAME}\n"));
+        ruleset.push_str("    }\n\n");
+
+        ruleset.push_str("    chain forward {\n");
+        ruleset.push_str("        type filter hook forward priority filter; policy drop;\n");
+        ruleset.push_str("        ct state established,related accept\n");
+        ruleset.push_str(&format!("        jump {FILTER_CHAIN_NAME}\n"));
+        ruleset.push_str("    }\n\n");
+
+        ruleset.push_str("    chain prerouting {\n");
+        ruleset.push_str("        type nat hook prerouting priority dstnat; policy accept;\n");
+
+        for rule in sorted_rules(configuration, "nat") {
+            match render_rule(configuration, rule, &sets) {
+                Ok(line) => {
+                    ruleset.push_str(&format!("        # {line}\n"));
+
+                    if !rule.disabled {
+                        warnings.push(format!(
+                            "{} was commented out: the redirect target is not part of the configuration",
+                            rule_label(rule)
+                        ));
+                    }
+                }
+                Err(reason) => warnings.push(format!("{} was skipped: {reason}", rule_label(rule))),
+            }
+        }
+
+        ruleset.push_str("    }\n");
+        ruleset.push_str("}\n");
+
+        NftablesExport { ruleset, warnings }
+    }
+}
+
+/// Renders a rule as an nftables statement.
+///
+/// # Returns
+/// * `Ok(String)` - The statement.
+/// * `Err(String)` - Why the rule cannot be expressed.
+fn render_rule(
+    configuration: &Configuration,
+    rule: &Rule,
+    sets: &HashMap<&str, ExportedAlias>,
+) -> Result<String, String> {
+    let mut matches = vec![];
+    if rule.direction == "out" {
+        return Err(String::from(
+            "outbound rules are not exported, rules are applied to inbound traffic",
+        ));
+    }
+
+    if rule.interface != DEFAULT_INTERFACE_VALUE {
+        let device = resolve_interface(configuration, &rule.interface)
+            .ok_or_else(|| format!("interface '{}' has no device", rule.interface))?;
+
+        matches.push(format!("iifname {}", quote(&device)));
+    }
+
+    let (mut version, protocols) = split_protocol(&rule.protocol);
+
+    let endpoints = [
+        (
+            "saddr",
+            &rule.source_addr,
+            &rule.source_type,
+            rule.source_inversed,
+        ),
+        (
+            "daddr",
+            &rule.destination_addr,
+            &rule.destination_type,
+            rule.destination_inversed,
+        ),
+    ];
+
+    let mut addresses = vec![];
+
+    for (field, addr, r#type, inversed) in endpoints {
+        let Some((addr_version, value)) = resolve_address(configuration, addr, r#type, sets)?
+        else {
+            continue;
+        };
+
+        let value = match value {
+            AddressMatch::Alias(name) => format!("@{name}"),
+            AddressMatch::Addresses(elements) => render_elements(&elements),
+        };
+
+        if !version.overlaps(addr_version) {
+            return Err(format!(
+                "'{addr}' does not match the IP version of the rule"
+            ));
+        }
+
+        version = addr_version;
+        let operator = if inversed { "!= " } else { "" };
+        addresses.push((field, format!("{operator}{value}")));
+    }
+
+    let family = match version {
+        IpVersion::V4 => "ip",
+        IpVersion::V6 => "ip6",
+        IpVersion::Any => "",
+    };
+
+    if addresses.is_empty() {
+        match version {
+            IpVersion::V4 => matches.push(String::from("meta nfproto ipv4")),
+            IpVersion::V6 => matches.push(String::from("meta nfproto ipv6")),
+            IpVersion::Any => {}
+        }
+    }
+
+    for (field, value) in addresses {
+        matches.push(format!("{family} {field} {value}"));
+    }
+
+    let mut ports = vec![];
+
+    for (field, port) in [
+        ("sport", &rule.source_port),
+        ("dport", &rule.destination_port),
+    ] {
+        match resolve_port(port, sets)? {
+            Some(PortMatch::Alias(name, _)) => ports.push((field, format!("@{name}"))),
+            Some(PortMatch::Ports(elements)) => ports.push((field, render_elements(&elements))),
+            None => {}
+        }
+    }
+
+    // pfSense's `icmp` stands for ICMPv6 in IPv6 rules
+    let protocols: Vec<&str> = protocols
+        .into_iter()
+        .map(|protocol| match (protocol, version) {
+            ("icmp", IpVersion::V6) => "icmpv6",
+            (protocol, _) => protocol,
+        })
+        .collect();
+
+    let header = match protocols.as_slice() {
+        [] if !ports.is_empty() => {
+            return Err(String::from("ports are only supported with a protocol"));
+        }
+        [] => None,
+        [protocol] if !ports.is_empty() => Some(protocol.to_string()),
+        [protocol] => {
+            matches.push(format!("meta l4proto {protocol}"));
+            None
+        }
+        protocols => {
+            matches.push(format!("meta l4proto {{ {} }}", protocols.join(", ")));
+            Some(String::from("th"))
+        }
+    };
+
+    if let Some(header) = header {
+        for (field, value) in ports {
+            matches.push(format!("{header} {field} {value}"));
+        }
+    }
+
+    let verdict = match (rule.r#type.as_str(), rule.policy.as_str()) {
+        ("nat", _) => "dnat to <target>",
+        (_, "pass") => "accept",
+        (_, "block") => "drop",
+        (_, "reject") => "reject",
+        (_, policy) => return Err(format!("policy '{policy}' cannot be expressed")),
+    };
+
+    matches.push(String::from(verdict));
+
+    if !rule.description.is_empty() {
+        matches.push(format!("comment {}", quote(&rule.description)));
+    }
+
+    Ok(matches.join(" "))
+}
+
+/// Renders a single value as is, and several values as an anonymous set.
+fn render_elements(elements: &[String]) -> String {
+    match elements {
+        [element] => element.clone(),
+        elements => format!("{{ {} }}", elements.join(", ")),
+    }
+}
+
+/// Quotes a string, replacing the double quotes nftables cannot escape.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FILTER_CHAIN_NAME, NftablesExporter};
+    use crate::{
+        Configuration,
+        linux::nftables_parser::LinuxNftablesParser,
+        test_utils::{self, alias, interface, rule},
+    };
+
+    fn configuration() -> Configuration {
+        let mut ssh = rule(1, "pass", "IPv4/tcp", "SSH to servers");
+        ssh.source_addr = String::from("lan");
+        ssh.source_type = String::from("network");
+        ssh.destination_addr = String::from("Servers");
+        ssh.destination_port = String::from("22");
+
+        let mut web = rule(0, "pass", "IPv4/tcp/udp", "Web \"quic\"");
+        web.destination_port = String::from("Ports");
+        web.destination_addr = String::from("Servers");
+        web.destination_inversed = true;
+
+        let mut blocked = rule(2, "block", "IPv4/any", "");
+        blocked.interface = String::from("wan");
+        blocked.source_addr = String::from("Feeds");
+
+        let mut disabled = rule(3, "reject", "IPv6/icmp", "Old rule");
+        disabled.disabled = true;
+
+        let mut unknown = rule(4, "pass", "IPv4/any", "On opt1");
+        unknown.interface = String::from("opt1");
+
+        let mut forward = rule(0, "pass", "IPv4/tcp", "HTTPS");
+        forward.r#type = String::from("nat");
+        forward.interface = String::from("wan");
+        forward.destination_addr = String::from("wanip");
+        forward.destination_type = String::from("network");
+        forward.destination_port = String::from("443");
+
+        test_utils::configuration(
+            vec![ssh, web, blocked, disabled, unknown, forward],
+            vec![
+                alias("host", "Servers", "10.0.0.10 10.0.0.11", "Web servers"),
+                alias("port", "Ports", "80 443 8000:8080", ""),
+                alias("urltable", "Feeds", "https://example.com/list.txt", ""),
+            ],
+            vec![
+                interface("wan", "eth0", &["203.0.113.10"]),
+                interface("lan", "eth1", &["192.168.1.1/24"]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_export_ruleset() {
+        let export = NftablesExporter::export(&configuration());
+
+        assert_eq!(
+            export.ruleset,
+            r#"#!/usr/sbin/nft -f
+
+table inet nullnet {}
+delete table inet nullnet
+
+table inet nullnet {
+    set Servers {
+        type ipv4_addr
+        elements = { 10.0.0.10, 10.0.0.11 }
+        comment "Web servers"
+    }
+
+    set Ports {
+        type inet_service
+        flags interval
+        elements = { 80, 443, 8000-8080 }
+    }
+
+    chain filter_rules {
+        iifname "eth1" ip daddr != @Servers meta l4proto { tcp, udp } th dport @Ports accept comment "Web 'quic'"
+        iifname "eth1" ip saddr 192.168.1.0/24 ip daddr @Servers tcp dport 22 accept comment "SSH to servers"
+        # iifname "eth1" meta nfproto ipv6 meta l4proto icmpv6 reject comment "Old rule"
+    }
+
+    chain input {
+        type filter hook input priority filter; policy drop;
+        ct state established,related accept
+        iifname "lo" accept
+        jump filter_rules
+    }
+
+    chain forward {
+        type filter hook forward priority filter; policy drop;
+        ct state established,related accept
+        jump filter_rules
+    }
+
+    chain prerouting {
+        type nat hook prerouting priority dstnat; policy accept;
+        # iifname "eth0" ip daddr 203.0.113.10 tcp dport 443 dnat to <target> comment "HTTPS"
+    }
+}
+"#
+        );
+
+        assert_eq!(
+            export.warnings,
+            vec![
+                "alias 'Feeds' was skipped: aliases of type 'urltable' are not supported",
+                "filter rule #2 was skipped: alias 'Feeds' could not be exported",
+                "filter rule #4 'On opt1' was skipped: interface 'opt1' has no device",
+                "nat rule #0 'HTTPS' was commented out: the redirect target is not part of the configuration",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exported_ruleset_can_be_parsed() {
+        let export = NftablesExporter::export(&configuration());
+
+        // Jumps are not followed: hook the rules chain to parse its rules
+        let ruleset = export.ruleset.replace(
+            &format!("chain {FILTER_CHAIN_NAME} {{\n"),
+            &format!(
+                "chain {FILTER_CHAIN_NAME} {{\n        type filter hook input priority filter;\n"
+            ),
+        );
+        let (rules, aliases) = LinuxNftablesParser::parse(&ruleset);
+
+        assert_eq!(aliases.len(), 2);
+        assert_eq!(aliases[0].name, "Servers");
+        assert_eq!(aliases[0].value, "10.0.0.10 10.0.0.11");
+        assert_eq!(aliases[1].value, "80 443 8000:8080");
+
+        let ssh = rules
+            .iter()
+            .find(|rule| rule.description == "SSH to servers")
+            .unwrap();
+
+        assert_eq!(ssh.interface, "eth1");
+        assert_eq!(ssh.protocol, "IPv4/tcp");
+        assert_eq!(ssh.source_addr, "192.168.1.0/24");
+        assert_eq!(ssh.destination_addr, "Servers");
+        assert_eq!(ssh.destination_port, "22");
+        assert_eq!(ssh.policy, "pass");
+    }
+}
@@ -0,0 +1,790 @@
+use std::collections::HashMap;
+
+use super::chain_direction;
+use crate::{Alias, Rule};
+
+const ANY_ADDR_VALUE: &str = "*";
+const ANY_PORT_VALUE: &str = "*";
+const DEFAULT_TYPE_VALUE: &str = "address";
+const DEFAULT_INTERFACE_VALUE: &str = "none";
+
+/// A single element of an nftables statement: either a word or a `{ ... }` group.
+#[derive(Debug, Clone)]
+enum Item {
+    Word(String),
+    Group(Vec<Statement>),
+}
+
+/// A statement is a sequence of items terminated by `;`, a newline or the end of a group.
+type Statement = Vec<Item>;
+
+/// A parser for extracting rules and named sets from an nftables ruleset (`nft -f` syntax).
+pub struct LinuxNftablesParser {}
+
+impl LinuxNftablesParser {
+    /// Parses an nftables ruleset and extracts its rules and named sets.
+    ///
+    /// # Arguments
+    /// * `content` - The ruleset text, as found in `/etc/nftables.conf` or printed by `nft list ruleset`.
+    ///
+    /// # Returns
+    /// A tuple `(Vec<Rule>, Vec<Alias>)` where:
+    /// - Rules come from base chains of `filter` type as `"filter"` rules, and from base chains
+    ///   of `nat` type as `"nat"` rules. Each rule takes the direction of the hook of its chain,
+    ///   and the policy of each base `filter` chain is appended as a rule matching any traffic
+    ///   after the rules of the table.
+    /// - Jumps are not followed: rules of regular chains and of `forward` hooks are skipped,
+    ///   as are rules ending with `jump`, `goto` or `return`.
+    /// - Rules without a verdict, and rules using matches `Rule` cannot represent
+    ///   (e.g. `ct state`, `limit rate` or a negated interface, protocol or port), are skipped,
+    ///   since dropping the match would turn them into broader rules.
+    /// - Aliases are built from named sets, which rules reference as `@name`.
+    pub fn parse(content: &str) -> (Vec<Rule>, Vec<Alias>) {
+        let statements = parse_block(&tokenize(content), &mut 0);
+
+        let mut variables = HashMap::new();
+        let mut rules = vec![];
+        let mut aliases = vec![];
+
+        for statement in &statements {
+            let words = leading_words(statement);
+
+            match words.as_slice() {
+                ["define", name, "=", ..] => {
+                    let value = flatten(&statement[3..]).join(",");
+                    variables.insert(format!("${name}"), value);
+                }
+                ["table", ..] => {
+                    let family = if words.len() >= 3 { words[1] } else { "ip" };
+
+                    if let Some(Item::Group(body)) = statement.last() {
+                        Self::parse_table(family, body, &variables, &mut rules, &mut aliases);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        (rules, aliases)
+    }
+
+    /// Parses the body of a `table` block.
+    fn parse_table(
+        family: &str,
+        body: &[Statement],
+        variables: &HashMap<String, String>,
+        rules: &mut Vec<Rule>,
+        aliases: &mut Vec<Alias>,
+    ) {
+        let mut policies = vec![];
+
+        for statement in body {
+            let words = leading_words(statement);
+            let Some(Item::Group(block)) = statement.last() else {
+                continue;
+            };
+
+            match words.as_slice() {
+                ["chain", name] => {
+                    policies.extend(Self::parse_chain(name, family, block, variables, rules));
+                }
+                ["set", name] => aliases.push(Self::parse_set(name, block)),
+                _ => {}
+            }
+        }
+
+        for mut rule in policies {
+            rule.order = rules.iter().filter(|r| r.r#type == "filter").count() as u64;
+            rules.push(rule);
+        }
+    }
+
+    /// Parses the body of a `chain` block, appending every rule with a verdict to `rules`.
+    ///
+    /// Regular chains and chains of hooks without a modeled direction are skipped.
+    ///
+    /// # Returns
+    /// The policy of the chain as a rule matching any traffic, if it is a base `filter` chain.
+    fn parse_chain(
+        name: &str,
+        family: &str,
+        body: &[Statement],
+        variables: &HashMap<String, String>,
+        rules: &mut Vec<Rule>,
+    ) -> Option<Rule> {
+        let mut chain_type = None;
+        let mut hook = None;
+        let mut chain_policy = None;
+
+        for statement in body {
+            match leading_words(statement).as_slice() {
+                ["type", r#type, "hook", value, ..] => {
+                    chain_type = Some(r#type.to_string());
+                    hook = Some(value.to_string());
+                }
+                ["policy", value, ..] => chain_policy = Some(value.to_string()),
+                _ => {}
+            }
+        }
+
+        let direction = chain_direction(hook.as_deref()?)?;
+        let is_nat = chain_type.as_deref() == Some("nat");
+        let rule_type = if is_nat { "nat" } else { "filter" };
+        let order_base = rules.iter().filter(|r| r.r#type == rule_type).count() as u64;
+
+        let chain_rules = body
+            .iter()
+            .filter(|statement| {
+                !matches!(
+                    leading_words(statement).first(),
+                    Some(&"type") | Some(&"policy") | Some(&"comment")
+                )
+            })
+            .filter_map(|statement| Self::parse_rule(family, statement, variables, is_nat));
+
+        for (index, mut rule) in (order_base..).zip(chain_rules) {
+            rule.r#type = rule_type.to_string();
+            rule.direction = direction.to_string();
+            rule.order = index;
+            rules.push(rule);
+        }
+
+        if chain_type.as_deref() != Some("filter") {
+            return None;
+        }
+
+        // Base chains accept the traffic no rule decided on, unless told otherwise
+        let policy = chain_policy.unwrap_or_else(|| String::from("accept"));
+
+        let mut rule = empty_rule();
+        rule.r#type = String::from("filter");
+        rule.policy = map_verdict(&policy).unwrap_or(policy);
+        rule.protocol = format!("{}/any", map_family(family));
+        rule.description = format!("Default policy of chain {name}");
+        rule.direction = direction.to_string();
+
+        Some(rule)
+    }
+
+    /// Parses a single rule statement.
+    ///
+    /// # Returns
+    /// `Some(Rule)` if the statement carries a verdict (or a NAT action in NAT chains)
+    /// and only uses matches `Rule` can represent, `None` otherwise.
+    fn parse_rule(
+        family: &str,
+        statement: &Statement,
+        variables: &HashMap<String, String>,
+        is_nat: bool,
+    ) -> Option<Rule> {
+        let mut rule = empty_rule();
+
+        let mut ip_version = map_family(family);
+        let mut protocol = "any".to_string();
+        let mut out_interface = None;
+        let mut policy = None;
+
+        let mut index = 0;
+        while index < statement.len() && policy.is_none() {
+            let Item::Word(word) = &statement[index] else {
+                index += 1;
+                continue;
+            };
+
+            let next = statement.get(index + 1).and_then(as_word);
+
+            match (word.as_str(), next) {
+                ("ip" | "ip6", Some(field @ ("saddr" | "daddr"))) => {
+                    ip_version = map_family(word);
+                    let (value, inversed, consumed) =
+                        read_value(&statement[index + 2..], variables);
+                    if field == "saddr" {
+                        rule.source_addr = value.trim_start_matches('@').to_string();
+                        rule.source_inversed = inversed;
+                    } else {
+                        rule.destination_addr = value.trim_start_matches('@').to_string();
+                        rule.destination_inversed = inversed;
+                    }
+                    index += 2 + consumed;
+                }
+                ("ip", Some("protocol")) | ("ip6", Some("nexthdr")) | ("meta", Some("l4proto")) => {
+                    if word != "meta" {
+                        ip_version = map_family(word);
+                    }
+                    let (value, inversed, consumed) =
+                        read_value(&statement[index + 2..], variables);
+                    if inversed {
+                        return None;
+                    }
+                    protocol = value;
+                    index += 2 + consumed;
+                }
+                ("meta", Some("nfproto")) => {
+                    let (value, inversed, consumed) =
+                        read_value(&statement[index + 2..], variables);
+                    if inversed {
+                        return None;
+                    }
+                    ip_version = map_family(&value);
+                    index += 2 + consumed;
+                }
+                (
+                    "tcp" | "udp" | "sctp" | "udplite" | "dccp",
+                    Some(field @ ("sport" | "dport")),
+                ) => {
+                    protocol = word.clone();
+                    let (value, inversed, consumed) =
+                        read_value(&statement[index + 2..], variables);
+                    if inversed {
+                        return None;
+                    }
+                    if field == "sport" {
+                        rule.source_port = value;
+                    } else {
+                        rule.destination_port = value;
+                    }
+                    index += 2 + consumed;
+                }
+                ("iif" | "iifname", _) | ("meta", Some("iif" | "iifname")) => {
+                    let skip = if word == "meta" { 2 } else { 1 };
+                    let (value, inversed, consumed) =
+                        read_value(&statement[index + skip..], variables);
+                    if inversed {
+                        return None;
+                    }
+                    rule.interface = value;
+                    index += skip + consumed;
+                }
+                ("oif" | "oifname", _) | ("meta", Some("oif" | "oifname")) => {
+                    let skip = if word == "meta" { 2 } else { 1 };
+                    let (value, inversed, consumed) =
+                        read_value(&statement[index + skip..], variables);
+                    if inversed {
+                        return None;
+                    }
+                    out_interface = Some(value);
+                    index += skip + consumed;
+                }
+                ("comment", Some(text)) => {
+                    rule.description = text.to_string();
+                    index += 2;
+                }
+                ("counter", _) => {
+                    index += 1;
+                    // `nft list ruleset` prints the values of counters
+                    while let Some("packets" | "bytes") = statement.get(index).and_then(as_word) {
+                        index += 2;
+                    }
+                }
+                ("log", _) => {
+                    index += 1;
+                    while let Some("prefix" | "level" | "flags" | "group" | "snaplen") =
+                        statement.get(index).and_then(as_word)
+                    {
+                        index += 2;
+                    }
+                }
+                // Jumps are not followed
+                ("jump" | "goto" | "return", _) => return None,
+                ("dnat" | "snat" | "masquerade" | "redirect", _) if is_nat => {
+                    policy = Some("pass".to_string())
+                }
+                (verdict, _) if map_verdict(verdict).is_some() => policy = map_verdict(verdict),
+                // Any other match (e.g. `ct state`, `limit rate` or `icmp type`) cannot be represented
+                _ => return None,
+            }
+        }
+
+        // A trailing comment may follow the verdict
+        if let Some(position) = statement
+            .iter()
+            .position(|item| matches!(item, Item::Word(w) if w == "comment"))
+            && let Some(text) = statement.get(position + 1).and_then(as_word)
+        {
+            rule.description = text.to_string();
+        }
+
+        if rule.interface == DEFAULT_INTERFACE_VALUE
+            && let Some(out_interface) = out_interface
+        {
+            rule.interface = out_interface;
+        }
+
+        rule.policy = policy?;
+        rule.protocol = format!("{ip_version}/{protocol}");
+
+        Some(rule)
+    }
+
+    /// Builds an `Alias` from the body of a named `set` block.
+    fn parse_set(name: &str, body: &[Statement]) -> Alias {
+        let mut set_type = String::from("none");
+        let mut interval = false;
+        let mut elements = vec![];
+        let mut description = String::new();
+
+        for statement in body {
+            match leading_words(statement).as_slice() {
+                ["type", value, ..] => set_type = value.to_string(),
+                ["flags", ..] => interval = flatten(statement).contains(&"interval".to_string()),
+                ["elements", "=", ..] => elements = flatten(&statement[2..]),
+                ["comment", value] => description = value.to_string(),
+                _ => {}
+            }
+        }
+
+        let r#type = match set_type.as_str() {
+            "ipv4_addr" | "ipv6_addr" => {
+                if interval || elements.iter().any(|e| e.contains('/') || e.contains('-')) {
+                    "network"
+                } else {
+                    "host"
+                }
+            }
+            "inet_service" => {
+                elements = elements.iter().map(|e| e.replace('-', ":")).collect();
+                "port"
+            }
+            other => other,
+        };
+
+        Alias {
+            r#type: r#type.to_string(),
+            name: name.to_string(),
+            value: elements.join(" "),
+            description,
+            detail: String::new(),
+        }
+    }
+}
+
+/// Maps an nftables verdict to the policy naming used by the other parsers.
+fn map_verdict(verdict: &str) -> Option<String> {
+    match verdict {
+        "accept" => Some(String::from("pass")),
+        "drop" => Some(String::from("block")),
+        "reject" => Some(String::from("reject")),
+        _ => None,
+    }
+}
+
+/// Builds a rule matching any traffic, without a type, a policy nor a protocol.
+fn empty_rule() -> Rule {
+    Rule {
+        disabled: false,
+        r#type: String::new(),
+        policy: String::new(),
+        protocol: String::new(),
+        source_inversed: false,
+        source_port: String::from(ANY_PORT_VALUE),
+        source_addr: String::from(ANY_ADDR_VALUE),
+        source_type: String::from(DEFAULT_TYPE_VALUE),
+        destination_port: String::from(ANY_PORT_VALUE),
+        destination_addr: String::from(ANY_ADDR_VALUE),
+        destination_type: String::from(DEFAULT_TYPE_VALUE),
+        destination_inversed: false,
+        description: String::new(),
+        interface: String::from(DEFAULT_INTERFACE_VALUE),
+        direction: String::from("any"),
+        id: String::new(),
+        updated_at: None,
+        order: 0,
+    }
+}
+
+/// Maps an nftables family (or protocol keyword) to the IP version prefix used in `Rule.protocol`.
+fn map_family(family: &str) -> &'static str {
+    match family {
+        "ip" | "ipv4" => "IPv4",
+        "ip6" | "ipv6" => "IPv6",
+        _ => "none",
+    }
+}
+
+/// Reads a match value: an optional `!=` negation followed by a word or an anonymous set.
+///
+/// # Returns
+/// A tuple `(String, bool, usize)` with the value (set elements joined by `,`, variables expanded),
+/// whether the match is negated and the number of items consumed.
+fn read_value(items: &[Item], variables: &HashMap<String, String>) -> (String, bool, usize) {
+    let mut consumed = 0;
+    let mut inversed = false;
+
+    if let Some(Item::Word(op)) = items.first() {
+        if op == "!=" {
+            inversed = true;
+            consumed += 1;
+        } else if op == "==" {
+            consumed += 1;
+        }
+    }
+
+    let value = match items.get(consumed) {
+        Some(Item::Word(word)) => variables.get(word).cloned().unwrap_or(word.clone()),
+        Some(Item::Group(group)) => flatten_group(group)
+            .iter()
+            .map(|w| variables.get(w).cloned().unwrap_or(w.clone()))
+            .collect::<Vec<_>>()
+            .join(","),
+        None => return (String::new(), inversed, consumed),
+    };
+
+    (value, inversed, consumed + 1)
+}
+
+/// Returns the item as a word, if it is one.
+fn as_word(item: &Item) -> Option<&str> {
+    match item {
+        Item::Word(word) => Some(word.as_str()),
+        Item::Group(_) => None,
+    }
+}
+
+/// Returns the words preceding the first group of a statement.
+fn leading_words(statement: &Statement) -> Vec<&str> {
+    statement.iter().map_while(as_word).collect()
+}
+
+/// Flattens a list of items into plain words, expanding nested groups.
+fn flatten(items: &[Item]) -> Vec<String> {
+    items
+        .iter()
+        .flat_map(|item| match item {
+            Item::Word(word) => vec![word.clone()],
+            Item::Group(group) => flatten_group(group),
+        })
+        .collect()
+}
+
+/// Flattens the statements of a group into plain words.
+fn flatten_group(group: &[Statement]) -> Vec<String> {
+    group.iter().flat_map(|s| flatten(s)).collect()
+}
+
+/// A lexical token of the nftables syntax.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Open,
+    Close,
+    Separator,
+}
+
+/// Splits an nftables ruleset into tokens.
+///
+/// Comments are dropped, quoted strings become a single word without quotes,
+/// and commas are treated as whitespace.
+fn tokenize(content: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = content.chars().peekable();
+    let mut current = String::new();
+
+    let flush = |current: &mut String, tokens: &mut Vec<Token>| {
+        if !current.is_empty() {
+            tokens.push(Token::Word(std::mem::take(current)));
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => {
+                flush(&mut current, &mut tokens);
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        tokens.push(Token::Separator);
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                flush(&mut current, &mut tokens);
+                let mut text = String::new();
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        break;
+                    }
+                    text.push(c);
+                }
+                tokens.push(Token::Word(text));
+            }
+            '{' => {
+                flush(&mut current, &mut tokens);
+                tokens.push(Token::Open);
+            }
+            '}' => {
+                flush(&mut current, &mut tokens);
+                tokens.push(Token::Close);
+            }
+            ';' | '\n' => {
+                flush(&mut current, &mut tokens);
+                tokens.push(Token::Separator);
+            }
+            '\\' if chars.peek() == Some(&'\n') => {
+                chars.next();
+            }
+            ',' => flush(&mut current, &mut tokens),
+            c if c.is_whitespace() => flush(&mut current, &mut tokens),
+            c => current.push(c),
+        }
+    }
+
+    flush(&mut current, &mut tokens);
+    tokens
+}
+
+/// Builds the statement tree of a block, starting at `position` and stopping at the matching `}`.
+fn parse_block(tokens: &[Token], position: &mut usize) -> Vec<Statement> {
+    let mut statements = vec![];
+    let mut current: Statement = vec![];
+
+    while let Some(token) = tokens.get(*position) {
+        *position += 1;
+
+        match token {
+            Token::Word(word) => current.push(Item::Word(word.clone())),
+            Token::Open => current.push(Item::Group(parse_block(tokens, position))),
+            Token::Separator => {
+                if !current.is_empty() {
+                    statements.push(std::mem::take(&mut current));
+                }
+            }
+            Token::Close => break,
+        }
+    }
+
+    if !current.is_empty() {
+        statements.push(current);
+    }
+
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LinuxNftablesParser;
+
+    const RULESET: &str = r#"#!/usr/sbin/nft -f
+flush ruleset
+
+define LAN_NET = 192.168.1.0/24
+
+table inet filter {
+    set blocked {
+        type ipv4_addr
+        flags interval
+        elements = { 10.0.0.0/8, 172.16.0.1 }
+        comment "Blocked networks"
+    }
+
+    set web_ports {
+        type inet_service
+        elements = { 80, 443, 8000-8080 }
+    }
+
+    chain input {
+        type filter hook input priority 0; policy drop;
+
+        iifname "lo" accept
+        ct state established,related accept
+        ip saddr @blocked drop comment "Drop blocked"
+        iifname "eth1" ip saddr $LAN_NET tcp dport { 22, 443 } accept comment "LAN admin"
+        ip6 saddr != fe80::/10 udp sport 53 reject with icmpv6 type port-unreachable
+        counter
+    }
+}
+
+table ip nat {
+    chain prerouting {
+        type nat hook prerouting priority -100;
+        iifname "eth0" tcp dport 8080 dnat to 192.168.1.10:80
+    }
+}
+"#;
+
+    #[test]
+    fn test_parse_filter_rules() {
+        let (rules, _) = LinuxNftablesParser::parse(RULESET);
+        let filter: Vec<_> = rules.iter().filter(|r| r.r#type == "filter").collect();
+
+        assert_eq!(filter.len(), 5);
+
+        assert_eq!(filter[0].policy, "pass");
+        assert_eq!(filter[0].interface, "lo");
+        assert_eq!(filter[0].protocol, "none/any");
+        assert_eq!(filter[0].direction, "in");
+        assert_eq!(filter[0].order, 0);
+
+        assert_eq!(filter[1].policy, "block");
+        assert_eq!(filter[1].source_addr, "blocked");
+        assert_eq!(filter[1].protocol, "IPv4/any");
+        assert_eq!(filter[1].description, "Drop blocked");
+
+        assert_eq!(filter[2].interface, "eth1");
+        assert_eq!(filter[2].source_addr, "192.168.1.0/24");
+        assert_eq!(filter[2].destination_port, "22,443");
+        assert_eq!(filter[2].protocol, "IPv4/tcp");
+        assert_eq!(filter[2].description, "LAN admin");
+        assert_eq!(filter[2].order, 2);
+
+        assert_eq!(filter[3].policy, "reject");
+        assert_eq!(filter[3].source_addr, "fe80::/10");
+        assert!(filter[3].source_inversed);
+        assert_eq!(filter[3].source_port, "53");
+        assert_eq!(filter[3].protocol, "IPv6/udp");
+    }
+
+    #[test]
+    fn test_skip_unmodeled_matches() {
+        let (rules, _) = LinuxNftablesParser::parse(
+            r#"table inet filter {
+    chain input {
+        type filter hook input priority 0;
+        ct state established,related accept
+        tcp dport 22 limit rate 5/minute accept
+        icmp type echo-request accept
+        tcp dport 80 counter packets 12 bytes 720 log prefix "web: " accept
+    }
+}
+"#,
+        );
+
+        // The rule on port 80 is followed by the chain policy
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].destination_port, "80");
+        assert_eq!(rules[0].policy, "pass");
+    }
+
+    #[test]
+    fn test_skip_unmodeled_chains() {
+        let (rules, _) = LinuxNftablesParser::parse(
+            r#"table inet filter {
+    chain input {
+        type filter hook input priority 0; policy drop;
+        tcp dport 22 jump ssh
+        tcp dport 23 goto ssh
+        tcp dport 25 return
+        tcp dport 80 accept
+    }
+    chain forward {
+        type filter hook forward priority 0; policy drop;
+        iifname "eth1" accept
+    }
+    chain ssh {
+        ip saddr 10.0.0.0/8 accept
+    }
+}
+"#,
+        );
+
+        let rules: Vec<_> = rules
+            .iter()
+            .map(|r| (r.policy.as_str(), r.destination_port.as_str(), r.order))
+            .collect();
+
+        assert_eq!(rules, vec![("pass", "80", 0), ("block", "*", 1)]);
+    }
+
+    #[test]
+    fn test_skip_negated_matches() {
+        let (rules, _) = LinuxNftablesParser::parse(
+            r#"table inet filter {
+    chain input {
+        type filter hook input priority 0;
+        iifname != "lo" tcp dport 22 accept
+        meta iifname != { "lo", "eth0" } accept
+        oifname != "eth0" drop
+        meta l4proto != tcp drop
+        ip protocol != udp drop
+        meta nfproto != ipv6 drop
+        tcp dport != 80 accept
+        udp sport != { 53, 123 } drop
+        ip saddr != 10.0.0.0/8 drop
+    }
+}
+"#,
+        );
+
+        // Only the negated address is represented, followed by the chain policy
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].source_addr, "10.0.0.0/8");
+        assert!(rules[0].source_inversed);
+        assert_eq!(rules[0].policy, "block");
+    }
+
+    #[test]
+    fn test_chain_policies() {
+        let (rules, _) = LinuxNftablesParser::parse(
+            r#"table inet filter {
+    chain input {
+        type filter hook input priority 0; policy drop;
+        iifname "lo" accept
+    }
+    chain output {
+        type filter hook output priority 0;
+        oifname "eth0" drop
+    }
+}
+"#,
+        );
+
+        let rules: Vec<_> = rules
+            .iter()
+            .map(|r| {
+                (
+                    r.policy.as_str(),
+                    r.direction.as_str(),
+                    r.description.as_str(),
+                    r.order,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            rules,
+            vec![
+                ("pass", "in", "", 0),
+                ("block", "out", "", 1),
+                ("block", "in", "Default policy of chain input", 2),
+                ("pass", "out", "Default policy of chain output", 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_nat_rules() {
+        let (rules, _) = LinuxNftablesParser::parse(RULESET);
+        let nat: Vec<_> = rules.iter().filter(|r| r.r#type == "nat").collect();
+
+        assert_eq!(nat.len(), 1);
+        assert_eq!(nat[0].policy, "pass");
+        assert_eq!(nat[0].interface, "eth0");
+        assert_eq!(nat[0].protocol, "IPv4/tcp");
+        assert_eq!(nat[0].destination_port, "8080");
+        assert_eq!(nat[0].order, 0);
+    }
+
+    #[test]
+    fn test_parse_sets_as_aliases() {
+        let (_, aliases) = LinuxNftablesParser::parse(RULESET);
+
+        assert_eq!(aliases.len(), 2);
+
+        assert_eq!(aliases[0].name, "blocked");
+        assert_eq!(aliases[0].r#type, "network");
+        assert_eq!(aliases[0].value, "10.0.0.0/8 172.16.0.1");
+        assert_eq!(aliases[0].description, "Blocked networks");
+
+        assert_eq!(aliases[1].name, "web_ports");
+        assert_eq!(aliases[1].r#type, "port");
+        assert_eq!(aliases[1].value, "80 443 8000:8080");
+        assert_eq!(aliases[1].description, "");
+    }
+
+    #[test]
+    fn test_parse_empty_ruleset() {
+        let (rules, aliases) = LinuxNftablesParser::parse("flush ruleset\n");
+
+        assert!(rules.is_empty());
+        assert!(aliases.is_empty());
+    }
+}
@@ -6,6 +6,8 @@ pub struct Alias {
     pub name: String,
     pub value: String,
     pub description: String,
+    #[serde(default)]
+    pub detail: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -24,9 +26,24 @@ pub struct Rule {
     pub destination_inversed: bool,
     pub description: String,
     pub interface: String,
+    #[serde(default = "default_direction")]
+    pub direction: String,
+    /// Stable id of the rule (`<tracker>` on pfSense, the `uuid` attribute on OPNsense),
+    /// empty if the platform has none.
+    #[serde(default)]
+    pub id: String,
+    /// Time of the last update of the rule, in milliseconds since the UNIX epoch,
+    /// if the platform records it.
+    #[serde(default)]
+    pub updated_at: Option<u64>,
     pub order: u64,
 }
 
+/// Rules serialized without a direction are interface rules, which apply to inbound traffic.
+fn default_direction() -> String {
+    String::from("in")
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct NetworkInterface {
     pub name: String,
@@ -49,6 +49,7 @@ impl OpnSenseAliasesParser {
                     name,
                     value,
                     description,
+                    detail: String::new(),
                 });
             }
         }
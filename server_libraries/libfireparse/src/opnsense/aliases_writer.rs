@@ -0,0 +1,220 @@
+use roxmltree::{Document, Node};
+use uuid::Uuid;
+
+use super::aliases_parser::OpnSenseAliasesParser;
+use crate::{
+    Alias, FireparseError,
+    xml_editor::{
+        XmlEdits, append_child, child_indent, find_child, item_indent, parent_element,
+        replace_items, set_child_text, text_element,
+    },
+};
+
+/// Path of the element holding the aliases, below the `<opnsense>` root.
+const ALIASES_PATH: [&str; 4] = ["OPNsense", "Firewall", "Alias", "aliases"];
+
+/// A writer updating the aliases of an OPNsense XML configuration.
+pub struct OpnSenseAliasesWriter {}
+
+impl OpnSenseAliasesWriter {
+    /// Records the edits needed to turn the `OPNsense/Firewall/Alias/aliases` section
+    /// of a configuration into `aliases`.
+    ///
+    /// Aliases are matched by name. Unchanged aliases keep their original text, changed ones
+    /// are updated in place (keeping their `uuid` and the elements not modeled by `Alias`),
+    /// new ones are inserted with a fresh `uuid` and the ones not listed anymore are removed.
+    ///
+    /// # Arguments
+    /// * `edits` - The edits the changes are recorded in.
+    /// * `document` - The parsed configuration.
+    /// * `source` - The original XML text.
+    /// * `aliases` - The desired aliases, in order.
+    /// * `unit` - The indentation unit of the document.
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the edits were recorded.
+    /// * `Err(FireparseError)` - If an existing alias cannot be updated.
+    pub fn write(
+        edits: &mut XmlEdits,
+        document: &Document,
+        source: &str,
+        aliases: &[Alias],
+        unit: &str,
+    ) -> Result<(), FireparseError> {
+        let mut parent = document.root_element();
+        let mut depth = 0;
+
+        while let Some(child) = ALIASES_PATH
+            .get(depth)
+            .and_then(|tag| find_child(parent, tag))
+        {
+            parent = child;
+            depth += 1;
+        }
+
+        let section = (depth == ALIASES_PATH.len()).then_some(parent);
+
+        if section.is_none() && aliases.is_empty() {
+            return Ok(());
+        }
+
+        let (slots, originals): (Vec<Node>, Vec<Alias>) = match section {
+            Some(section) => (
+                section
+                    .children()
+                    .filter(|e| e.has_tag_name("alias"))
+                    .collect(),
+                OpnSenseAliasesParser::parse(document),
+            ),
+            None => (vec![], vec![]),
+        };
+
+        // The missing elements of the path are created below the deepest existing one
+        let base_indent = child_indent(source, parent, unit);
+        let missing = &ALIASES_PATH[depth..];
+
+        let indent = match section {
+            Some(section) => item_indent(source, section, "alias", unit),
+            None => format!("{base_indent}{}", unit.repeat(missing.len())),
+        };
+
+        let items = aliases
+            .iter()
+            .map(
+                |alias| match originals.iter().position(|a| a.name == alias.name) {
+                    Some(index) if &originals[index] == alias => {
+                        Ok(source[slots[index].range()].to_string())
+                    }
+                    Some(index) => {
+                        Self::update_alias(source, slots[index], &originals[index], alias, unit)
+                    }
+                    None => Ok(Self::render_alias(alias, &indent, unit)),
+                },
+            )
+            .collect::<Result<Vec<String>, FireparseError>>()?;
+
+        match section {
+            Some(section) => replace_items(edits, source, section, "alias", &items, unit),
+            None => {
+                let mut element = parent_element(
+                    missing[missing.len() - 1],
+                    &items,
+                    &format!("{base_indent}{}", unit.repeat(missing.len() - 1)),
+                    unit,
+                );
+
+                for (level, tag) in missing.iter().enumerate().rev().skip(1) {
+                    let indent = format!("{base_indent}{}", unit.repeat(level));
+                    element = parent_element(tag, &[element], &indent, unit);
+                }
+
+                append_child(edits, source, parent, element, unit);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renders a new `<alias>` element, identified by a random `uuid`.
+    fn render_alias(alias: &Alias, indent: &str, unit: &str) -> String {
+        let children = vec![
+            text_element("enabled", "1"),
+            text_element("name", &alias.name),
+            text_element("type", &alias.r#type),
+            text_element("content", content(alias)),
+            text_element("description", &alias.description),
+        ];
+
+        let element = parent_element("alias", &children, indent, unit);
+
+        format!(
+            "<alias uuid=\"{}\">{}",
+            Uuid::new_v4(),
+            &element["<alias>".len()..]
+        )
+    }
+
+    /// Renders an existing `<alias>` element with the fields of `alias`,
+    /// changing only the elements whose value differs from `original`.
+    fn update_alias(
+        source: &str,
+        node: Node,
+        original: &Alias,
+        alias: &Alias,
+        unit: &str,
+    ) -> Result<String, FireparseError> {
+        let mut edits = XmlEdits::default();
+
+        if original.r#type != alias.r#type {
+            set_child_text(&mut edits, source, node, "type", Some(&alias.r#type), unit);
+        }
+
+        if original.value != alias.value {
+            set_child_text(
+                &mut edits,
+                source,
+                node,
+                "content",
+                Some(content(alias)),
+                unit,
+            );
+        }
+
+        if original.description != alias.description {
+            let description = Some(alias.description.as_str());
+            set_child_text(&mut edits, source, node, "description", description, unit);
+        }
+
+        edits.apply(source, node.range())
+    }
+}
+
+/// Returns the content of an alias, mapping the `None` placeholder used by the parser
+/// back to an empty `<content>`.
+fn content(alias: &Alias) -> &str {
+    match alias.value.as_str() {
+        "None" => "",
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpnSenseAliasesWriter;
+    use crate::{Alias, xml_editor::XmlEdits};
+    use roxmltree::Document;
+
+    #[test]
+    fn test_create_aliases_section() {
+        let source = "<opnsense>\n  <OPNsense>\n    <Firewall/>\n  </OPNsense>\n</opnsense>";
+        let document = Document::parse(source).unwrap();
+
+        let aliases = vec![Alias {
+            r#type: String::from("network"),
+            name: String::from("Lan"),
+            value: String::from("10.0.0.0/24"),
+            description: String::from("R&D"),
+            detail: String::new(),
+        }];
+
+        let mut edits = XmlEdits::default();
+        OpnSenseAliasesWriter::write(&mut edits, &document, source, &aliases, "  ")
+            .unwrap_or_else(|_| panic!("Failed to write"));
+        let output = edits
+            .apply(source, 0..source.len())
+            .unwrap_or_else(|_| panic!("Failed to apply"));
+
+        let (head, tail) = output.split_once("<alias uuid=\"").unwrap();
+        assert_eq!(
+            head,
+            "<opnsense>\n  <OPNsense>\n    <Firewall>\n      <Alias>\n        <aliases>\n          "
+        );
+
+        let (uuid, tail) = tail.split_once('"').unwrap();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(
+            tail,
+            ">\n            <enabled>1</enabled>\n            <name>Lan</name>\n            <type>network</type>\n            <content>10.0.0.0/24</content>\n            <description>R&amp;D</description>\n          </alias>\n        </aliases>\n      </Alias>\n    </Firewall>\n  </OPNsense>\n</opnsense>"
+        );
+    }
+}
@@ -1,12 +1,16 @@
-use nullnet_libconfmon::{InterfaceSnapshot, Snapshot};
+use nullnet_libconfmon::{InterfaceSnapshot, NETWORK_INTERFACES_ENTRY, Snapshot};
 use roxmltree::Document;
 
 mod aliases_parser;
+mod aliases_writer;
 mod enpoint_parser;
 mod interfaces_parser;
 mod rules_parser;
 mod ssh_parser;
 mod webgui_parser;
+mod writer;
+
+pub use writer::OpnSenseWriter;
 
 use crate::{
     Configuration, FireparseError,
@@ -29,7 +33,7 @@ impl OpnSenseParser {
             rules: OpnSenseRulesParser::parse(&document),
             aliases: OpnSenseAliasesParser::parse(&document),
             interfaces: OpnSenseInterfacesParser::parse(&document, iterfaces),
-            hostname: Default::default(),
+            hostname: utils::hostname_from_snapshot(&snapshot).unwrap_or_default(),
             ssh: OpnSenseSSHParser::parse(&document),
             gui_protocol: OpnSenseWebGuiParser::parse(&document, "https"),
             raw_content: encoded,
@@ -61,9 +65,9 @@ impl OpnSenseParser {
     fn parse_interfaces_info_from_snapshot(
         snapshot: &Snapshot,
     ) -> Result<Vec<InterfaceSnapshot>, FireparseError> {
-        let ifaces_data = find_in_snapshot(snapshot, "#NetworkInterfaces").ok_or(
-            FireparseError::ParserError(String::from(
-                "OpnSenseParser: '#NetworkInterfaces' file is missing in the snapshot",
+        let ifaces_data = find_in_snapshot(snapshot, NETWORK_INTERFACES_ENTRY).ok_or(
+            FireparseError::ParserError(format!(
+                "OpnSenseParser: '{NETWORK_INTERFACES_ENTRY}' file is missing in the snapshot"
             )),
         )?;
 
@@ -1,4 +1,4 @@
-use crate::{Rule, opnsense::enpoint_parser::EndpointParser};
+use crate::{Rule, opnsense::enpoint_parser::EndpointParser, utils};
 use roxmltree::{Document, Node};
 
 pub struct OpnSenseRulesParser {}
@@ -65,6 +65,13 @@ impl OpnSenseRulesParser {
                 .unwrap_or("none")
                 .to_string();
 
+            let direction = rule
+                .children()
+                .find(|e| e.has_tag_name("direction"))
+                .and_then(|e| e.text())
+                .unwrap_or("in")
+                .to_string();
+
             let (source_addr, source_port, source_type, source_inversed) =
                 EndpointParser::parse(rule.children().find(|e| e.has_tag_name("source")));
 
@@ -86,6 +93,9 @@ impl OpnSenseRulesParser {
                 destination_type,
                 destination_inversed,
                 interface,
+                direction,
+                id: utils::rule_id(rule),
+                updated_at: utils::rule_updated_at(rule),
                 order: index,
             });
         }
@@ -0,0 +1,240 @@
+use roxmltree::Document;
+
+use super::aliases_writer::OpnSenseAliasesWriter;
+use crate::{
+    Configuration, FireparseError,
+    pfsense::PfSenseRulesWriter,
+    utils,
+    xml_editor::{XmlEdits, indent_unit},
+};
+
+/// A writer rendering an edited `Configuration` back into an OPNsense `config.xml`.
+pub struct OpnSenseWriter {}
+
+impl OpnSenseWriter {
+    /// Renders a configuration into the `config.xml` it was parsed from.
+    ///
+    /// The original document is taken from `raw_content`. Aliases are written to the
+    /// `OPNsense/Firewall/Alias/aliases` section, keeping the `uuid` of existing aliases,
+    /// and rules to the legacy `filter` and `nat` sections. Every other part of the document
+    /// (including the formatting) is kept byte-for-byte. Rendering an unmodified configuration
+    /// returns the original document.
+    ///
+    /// # Arguments
+    /// * `configuration` - The edited configuration.
+    ///
+    /// # Returns
+    /// * `Ok(String)` - The updated `config.xml`.
+    /// * `Err(FireparseError)` - If the original document is invalid or the configuration cannot be written.
+    pub fn render(configuration: &Configuration) -> Result<String, FireparseError> {
+        let source = utils::decode_raw_content(&configuration.raw_content, "OpnSenseWriter")?;
+
+        let document =
+            Document::parse(&source).map_err(|e| FireparseError::WriterError(e.to_string()))?;
+
+        let root = document.root_element();
+
+        if !root.has_tag_name("opnsense") {
+            return Err(FireparseError::WriterError(String::from(
+                "OpnSenseWriter: 'config.xml' is not an OPNsense configuration",
+            )));
+        }
+
+        let unit = indent_unit(&document, &source);
+        let mut edits = XmlEdits::default();
+
+        OpnSenseAliasesWriter::write(
+            &mut edits,
+            &document,
+            &source,
+            &configuration.aliases,
+            &unit,
+        )?;
+        PfSenseRulesWriter::write(&mut edits, &source, root, &configuration.rules, &unit)?;
+
+        if edits.is_empty() {
+            return Ok(source);
+        }
+
+        edits.apply(&source, 0..source.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpnSenseWriter;
+    use crate::{Alias, Configuration, FileData, Rule, opnsense::OpnSenseParser};
+    use nullnet_libconfmon::{InterfaceSnapshot, NETWORK_INTERFACES_ENTRY};
+
+    const CONFIG: &str = r#"<?xml version="1.0"?>
+<opnsense>
+  <theme>opnsense</theme>
+  <system>
+    <hostname>OPNsense</hostname>
+    <domain>localdomain</domain>
+  </system>
+  <filter>
+    <rule uuid="5e0d3f5a-3b8e-4a53-9a3c-1f0f3a3c4c01">
+      <type>pass</type>
+      <ipprotocol>inet</ipprotocol>
+      <descr>Default allow LAN to any rule</descr>
+      <interface>lan</interface>
+      <source>
+        <network>lan</network>
+      </source>
+      <destination>
+        <any/>
+      </destination>
+    </rule>
+    <rule uuid="5e0d3f5a-3b8e-4a53-9a3c-1f0f3a3c4c02">
+      <type>block</type>
+      <ipprotocol>inet</ipprotocol>
+      <protocol>tcp</protocol>
+      <descr>Block SSH</descr>
+      <interface>wan</interface>
+      <source>
+        <any/>
+      </source>
+      <destination>
+        <address>Servers</address>
+        <port>22</port>
+      </destination>
+      <updated>
+        <username>root@10.0.0.5</username>
+        <time>1700000000.1234</time>
+      </updated>
+    </rule>
+  </filter>
+  <OPNsense>
+    <Firewall>
+      <Alias version="1.0.1">
+        <geoip>
+          <url/>
+        </geoip>
+        <aliases>
+          <alias uuid="b1e3c2a4-6d7f-4e8a-9b0c-1d2e3f4a5b01">
+            <enabled>1</enabled>
+            <name>Servers</name>
+            <type>host</type>
+            <proto/>
+            <counters>0</counters>
+            <updatefreq/>
+            <content>10.0.0.10
+10.0.0.11</content>
+            <categories/>
+            <description>Web servers</description>
+          </alias>
+          <alias uuid="b1e3c2a4-6d7f-4e8a-9b0c-1d2e3f4a5b02">
+            <enabled>1</enabled>
+            <name>Ports</name>
+            <type>port</type>
+            <proto/>
+            <counters>0</counters>
+            <updatefreq/>
+            <content>80
+443</content>
+            <categories/>
+            <description/>
+          </alias>
+        </aliases>
+      </Alias>
+    </Firewall>
+    <IDS version="1.0.9">
+      <general>
+        <enabled>0</enabled>
+      </general>
+    </IDS>
+  </OPNsense>
+</opnsense>
+"#;
+
+    fn parse(config: &str) -> Configuration {
+        let interfaces = InterfaceSnapshot::serialize_snapshot(&vec![]).unwrap();
+
+        let snapshot = vec![
+            FileData::new("config.xml", config.as_bytes().to_vec(), 0),
+            FileData::new(NETWORK_INTERFACES_ENTRY, interfaces, 0),
+        ];
+
+        OpnSenseParser::parse(snapshot).unwrap_or_else(|_| panic!("Failed to parse"))
+    }
+
+    fn render(configuration: &Configuration) -> String {
+        OpnSenseWriter::render(configuration).unwrap_or_else(|_| panic!("Failed to render"))
+    }
+
+    #[test]
+    fn test_roundtrip_without_edits_is_lossless() {
+        assert_eq!(render(&parse(CONFIG)), CONFIG);
+    }
+
+    #[test]
+    fn test_update_rules_and_aliases() {
+        let mut configuration = parse(CONFIG);
+
+        configuration.aliases[0].value = String::from("10.0.0.10\n10.0.0.12");
+        configuration.aliases[1].description = String::from("Web ports");
+        configuration.aliases.push(Alias {
+            r#type: String::from("network"),
+            name: String::from("Office"),
+            value: String::from("192.168.10.0/24"),
+            description: String::new(),
+            detail: String::new(),
+        });
+
+        configuration.rules.remove(0);
+        configuration.rules[0].order = 0;
+        configuration.rules[0].disabled = true;
+        configuration.rules[0].description = String::from("Block SSH to servers");
+        configuration.rules.push(Rule {
+            disabled: false,
+            r#type: String::from("nat"),
+            policy: String::from("pass"),
+            protocol: String::from("IPv4/tcp"),
+            source_inversed: false,
+            source_port: String::from("*"),
+            source_addr: String::from("*"),
+            source_type: String::from("address"),
+            destination_port: String::from("443"),
+            destination_addr: String::from("wanip"),
+            destination_type: String::from("network"),
+            destination_inversed: false,
+            description: String::from("HTTPS"),
+            interface: String::from("wan"),
+            direction: String::from("in"),
+            id: String::new(),
+            updated_at: None,
+            order: 0,
+        });
+
+        let output = render(&configuration);
+        let reparsed = parse(&output);
+
+        // New rules are given a fresh uuid
+        assert_eq!(reparsed.rules[1].id.len(), 36);
+        configuration.rules[1].id = reparsed.rules[1].id.clone();
+
+        assert_eq!(reparsed.aliases, configuration.aliases);
+        assert_eq!(reparsed.rules, configuration.rules);
+
+        // Existing aliases and rules keep their uuid, along with the elements not modeled by libfireparse
+        assert!(output.contains("<alias uuid=\"b1e3c2a4-6d7f-4e8a-9b0c-1d2e3f4a5b01\">"));
+        assert!(output.contains("<alias uuid=\"b1e3c2a4-6d7f-4e8a-9b0c-1d2e3f4a5b02\">"));
+        assert!(output.contains("<rule uuid=\"5e0d3f5a-3b8e-4a53-9a3c-1f0f3a3c4c02\">"));
+        assert!(!output.contains("5e0d3f5a-3b8e-4a53-9a3c-1f0f3a3c4c01"));
+        assert!(output.contains("<time>1700000000.1234</time>"));
+        assert!(output.contains("<IDS version=\"1.0.9\">"));
+        assert!(output.contains("<content>10.0.0.10\n10.0.0.12</content>"));
+        assert!(output.contains("<description>Web ports</description>"));
+        assert_eq!(output.matches("<alias uuid=").count(), 3);
+        assert_eq!(output.matches("<rule uuid=").count(), 2);
+    }
+
+    #[test]
+    fn test_reject_other_platforms() {
+        let mut configuration = parse(CONFIG);
+        configuration.raw_content = crate::utils::encode_base64(b"<pfsense/>");
+
+        assert!(OpnSenseWriter::render(&configuration).is_err());
+    }
+}
@@ -55,11 +55,19 @@ impl PfSenseAliasesParser {
                     .unwrap_or("")
                     .to_string();
 
+                let detail = alias
+                    .children()
+                    .find(|e| e.has_tag_name("detail"))
+                    .and_then(|e| e.text())
+                    .unwrap_or("")
+                    .to_string();
+
                 aliases.push(Alias {
                     r#type,
                     name,
                     value,
                     description,
+                    detail,
                 });
             }
         }
@@ -104,6 +112,7 @@ mod tests {
         assert_eq!(aliases[0].r#type, "port");
         assert_eq!(aliases[0].value, "1 2 3");
         assert_eq!(aliases[0].description, "Description");
+        assert_eq!(aliases[0].detail, "Desc 1||Desc 2||Desc 3");
 
         assert_eq!(aliases[1].name, "Addresses");
         assert_eq!(aliases[1].r#type, "host");
@@ -135,6 +144,7 @@ mod tests {
         assert_eq!(aliases[0].r#type, "network");
         assert_eq!(aliases[0].value, "1.1.1.0/24 2.2.2.0/24");
         assert_eq!(aliases[0].description, "Description");
+        assert_eq!(aliases[0].detail, "Network 1||Network 2");
     }
 
     #[test]
@@ -0,0 +1,192 @@
+use roxmltree::{Document, Node};
+
+use super::aliases_parser::PfSenseAliasesParser;
+use crate::{
+    Alias, FireparseError,
+    xml_editor::{
+        XmlEdits, append_child, cdata_element, child_indent, find_child, item_indent,
+        parent_element, replace_items, set_child, set_child_text, text_element,
+    },
+};
+
+/// A writer updating the aliases of a pfSense XML configuration.
+pub struct PfSenseAliasesWriter {}
+
+impl PfSenseAliasesWriter {
+    /// Records the edits needed to turn the `<aliases>` section of a configuration into `aliases`.
+    ///
+    /// Aliases are matched by name. Unchanged aliases keep their original text, changed ones
+    /// are updated in place (keeping the elements not modeled by `Alias`),
+    /// new ones are inserted and the ones not listed anymore are removed.
+    ///
+    /// # Arguments
+    /// * `edits` - The edits the changes are recorded in.
+    /// * `document` - The parsed configuration.
+    /// * `source` - The original XML text.
+    /// * `aliases` - The desired aliases, in order.
+    /// * `unit` - The indentation unit of the document.
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the edits were recorded.
+    /// * `Err(FireparseError)` - If an existing alias cannot be updated.
+    pub fn write(
+        edits: &mut XmlEdits,
+        document: &Document,
+        source: &str,
+        aliases: &[Alias],
+        unit: &str,
+    ) -> Result<(), FireparseError> {
+        let root = document.root_element();
+        let section = find_child(root, "aliases");
+
+        if section.is_none() && aliases.is_empty() {
+            return Ok(());
+        }
+
+        let (slots, originals): (Vec<Node>, Vec<Alias>) = match section {
+            Some(section) => (
+                section
+                    .children()
+                    .filter(|e| e.has_tag_name("alias"))
+                    .collect(),
+                PfSenseAliasesParser::parse(document),
+            ),
+            None => (vec![], vec![]),
+        };
+
+        let indent = match section {
+            Some(section) => item_indent(source, section, "alias", unit),
+            None => format!("{}{unit}", child_indent(source, root, unit)),
+        };
+
+        let items = aliases
+            .iter()
+            .map(
+                |alias| match originals.iter().position(|a| a.name == alias.name) {
+                    Some(index) if &originals[index] == alias => {
+                        Ok(source[slots[index].range()].to_string())
+                    }
+                    Some(index) => {
+                        Self::update_alias(source, slots[index], &originals[index], alias, unit)
+                    }
+                    None => Ok(Self::render_alias(alias, &indent, unit)),
+                },
+            )
+            .collect::<Result<Vec<String>, FireparseError>>()?;
+
+        match section {
+            Some(section) => replace_items(edits, source, section, "alias", &items, unit),
+            None => append_child(
+                edits,
+                source,
+                root,
+                parent_element("aliases", &items, &child_indent(source, root, unit), unit),
+                unit,
+            ),
+        }
+
+        Ok(())
+    }
+
+    /// Renders a new `<alias>` element.
+    fn render_alias(alias: &Alias, indent: &str, unit: &str) -> String {
+        let mut children = vec![
+            text_element("name", &alias.name),
+            text_element("type", &alias.r#type),
+        ];
+
+        if alias.value != "None" {
+            children.push(text_element(value_tag(alias), &alias.value));
+        }
+
+        if !alias.description.is_empty() {
+            children.push(cdata_element("descr", &alias.description));
+        }
+
+        if !alias.detail.is_empty() {
+            children.push(cdata_element("detail", &alias.detail));
+        }
+
+        parent_element("alias", &children, indent, unit)
+    }
+
+    /// Renders an existing `<alias>` element with the fields of `alias`,
+    /// changing only the elements whose value differs from `original`.
+    fn update_alias(
+        source: &str,
+        node: Node,
+        original: &Alias,
+        alias: &Alias,
+        unit: &str,
+    ) -> Result<String, FireparseError> {
+        let mut edits = XmlEdits::default();
+
+        if original.r#type != alias.r#type {
+            set_child_text(&mut edits, source, node, "type", Some(&alias.r#type), unit);
+        }
+
+        if original.value != alias.value {
+            let tag = value_tag(alias);
+            let value = (alias.value != "None").then_some(alias.value.as_str());
+
+            // The parser prefers `<address>` over `<url>`, so it must be emptied for URL aliases
+            if tag == "url"
+                && find_child(node, "address").is_some_and(|address| address.text().is_some())
+            {
+                set_child(
+                    &mut edits,
+                    source,
+                    node,
+                    "address",
+                    Some(String::from("<address></address>")),
+                    unit,
+                );
+            }
+
+            set_child_text(&mut edits, source, node, tag, value, unit);
+        }
+
+        if original.description != alias.description {
+            set_cdata_child(&mut edits, source, node, "descr", &alias.description, unit);
+        }
+
+        if original.detail != alias.detail {
+            set_cdata_child(&mut edits, source, node, "detail", &alias.detail, unit);
+        }
+
+        edits.apply(source, node.range())
+    }
+}
+
+/// Sets the text of a description element, creating it as CDATA if missing and removing it if `text` is empty.
+fn set_cdata_child(
+    edits: &mut XmlEdits,
+    source: &str,
+    node: Node,
+    tag: &str,
+    text: &str,
+    unit: &str,
+) {
+    let text = (!text.is_empty()).then_some(text);
+
+    match find_child(node, tag) {
+        Some(_) => set_child_text(edits, source, node, tag, text, unit),
+        None => set_child(
+            edits,
+            source,
+            node,
+            tag,
+            text.map(|text| cdata_element(tag, text)),
+            unit,
+        ),
+    }
+}
+
+/// Returns the element holding the value of an alias: `<url>` for URL-based aliases,
+/// `<address>` otherwise.
+fn value_tag(alias: &Alias) -> &'static str {
+    match alias.r#type.contains("url") {
+        true => "url",
+        false => "address",
+    }
+}
@@ -170,8 +170,7 @@ mod tests {
             mac_address: None,
             interface_index: None,
             ip_addresses: vec!["8.8.8.8".parse().unwrap(), "8.8.4.4".parse().unwrap()],
-            subnet_mask: None,
-            gateway: None,
+            ..Default::default()
         };
 
         let interfaces = PfSenseInterfacesParser::parse(&doc, vec![iface_data]);
@@ -5,19 +5,25 @@ use crate::{
 use aliases_parser::PfSenseAliasesParser;
 use hostname_parser::PfSenseHostnameParser;
 use interfaces_parser::PfSenseInterfacesParser;
-use nullnet_libconfmon::{InterfaceSnapshot, Snapshot};
+use nullnet_libconfmon::{InterfaceSnapshot, NETWORK_INTERFACES_ENTRY, Snapshot};
 use roxmltree::Document;
 use rules_parser::PfSenseRulesParser;
 use ssh_parser::PfSenseSSHParser;
 use webgui_parser::PfSenseWebGuiParser;
 
 mod aliases_parser;
+mod aliases_writer;
 mod endpoint_parser;
 mod hostname_parser;
 mod interfaces_parser;
 mod rules_parser;
+mod rules_writer;
 mod ssh_parser;
 mod webgui_parser;
+mod writer;
+
+pub(crate) use rules_writer::PfSenseRulesWriter;
+pub use writer::PfSenseWriter;
 
 /// A parser for extracting configuration details from a pfSense XML configuration.
 pub struct PfSenseParser {}
@@ -73,10 +79,11 @@ impl PfSenseParser {
     fn parse_interfaces_info_from_snapshot(
         snapshot: &Snapshot,
     ) -> Result<Vec<InterfaceSnapshot>, FireparseError> {
-        let ifaces_data =
-            find_in_snapshot(snapshot, "#NetworkInterfaces").ok_or(FireparseError::ParserError(
-                String::from("PfSenseParser: '#NetworkInterfaces' file is missing in the snapshot"),
-            ))?;
+        let ifaces_data = find_in_snapshot(snapshot, NETWORK_INTERFACES_ENTRY).ok_or(
+            FireparseError::ParserError(format!(
+                "PfSenseParser: '{NETWORK_INTERFACES_ENTRY}' file is missing in the snapshot"
+            )),
+        )?;
 
         InterfaceSnapshot::deserialize_snapshot(&ifaces_data.content)
             .map_err(|e| FireparseError::ParserError(format!("PfSenseParser: Failed to deserialize network interfaces data from the snapshot. {e}")))
@@ -1,7 +1,7 @@
 use roxmltree::{Document, Node};
 
 use super::endpoint_parser::EndpointParser;
-use crate::Rule;
+use crate::{Rule, utils};
 
 /// A parser for extracting firewall and NAT rules from a pfSense XML configuration.
 pub struct PfSenseRulesParser {}
@@ -46,7 +46,7 @@ impl PfSenseRulesParser {
     ///
     /// # Returns
     /// A `Vec<Rule>` containing extracted rules.
-    fn parse_rules(node: Node<'_, '_>, rule_type: &str) -> Vec<Rule> {
+    pub(super) fn parse_rules(node: Node<'_, '_>, rule_type: &str) -> Vec<Rule> {
         let mut rules = Vec::new();
 
         for (index, rule) in (0_u64..).zip(node.children().filter(|e| e.has_tag_name("rule"))) {
@@ -85,6 +85,13 @@ impl PfSenseRulesParser {
                 .unwrap_or("none")
                 .to_string();
 
+            let direction = rule
+                .children()
+                .find(|e| e.has_tag_name("direction"))
+                .and_then(|e| e.text())
+                .unwrap_or("in")
+                .to_string();
+
             let (source_addr, source_port, source_type, source_inversed) =
                 EndpointParser::parse(rule.children().find(|e| e.has_tag_name("source")));
 
@@ -106,6 +113,9 @@ impl PfSenseRulesParser {
                 destination_type,
                 destination_inversed,
                 interface,
+                direction,
+                id: utils::rule_id(rule),
+                updated_at: utils::rule_updated_at(rule),
                 order: index,
             });
         }
@@ -151,6 +161,13 @@ mod tests {
                     <destination>
                         <any/>
                     </destination>
+                    <tracker>0100000101</tracker>
+                    <created>
+                        <time>1600000000</time>
+                    </created>
+                    <updated>
+                        <time>1700000000.5</time>
+                    </updated>
                 </rule>
             </filter>
         </pfsense>
@@ -174,6 +191,8 @@ mod tests {
         assert_eq!(rules[0].destination_type, "address");
         assert_eq!(rules[0].destination_inversed, false);
         assert_eq!(rules[0].interface, "lan");
+        assert_eq!(rules[0].id, "0100000101");
+        assert_eq!(rules[0].updated_at, Some(1_700_000_000_500));
         assert_eq!(rules[0].order, 0);
     }
 
@@ -0,0 +1,435 @@
+use roxmltree::Node;
+use uuid::Uuid;
+
+use super::rules_parser::PfSenseRulesParser;
+use crate::{
+    FireparseError, Rule, utils,
+    xml_editor::{
+        XmlEdits, append_child, cdata_element, child_indent, empty_element, find_child, indent_of,
+        item_indent, parent_element, replace_items, set_child, set_child_text, text_element,
+    },
+};
+
+const ANY_ADDR_VALUE: &str = "*";
+const ANY_PORT_VALUE: &str = "*";
+const ANY_PROTOCOL_VALUE: &str = "any";
+
+/// Sections holding rules, along with the rule type they map to.
+const RULE_SECTIONS: [&str; 2] = ["filter", "nat"];
+
+/// A writer updating the `filter` and `nat` rules of a pfSense-style XML configuration.
+///
+/// OPNsense keeps its legacy rules in the same format, so this writer is shared by both platforms.
+pub struct PfSenseRulesWriter {}
+
+impl PfSenseRulesWriter {
+    /// Records the edits needed to turn the rules of a configuration into `rules`.
+    ///
+    /// Rules of each type are written sorted by their `order`. A rule identical to an existing one
+    /// keeps its original text. Otherwise, it updates the existing rule with the same `id`
+    /// (or, failing that, the same description, then the same `order`), whose elements not modeled
+    /// by `Rule` (e.g. `<tracker>`) are kept. Remaining rules are inserted (with a fresh `uuid` on
+    /// OPNsense), and existing rules left without a match are removed.
+    ///
+    /// # Arguments
+    /// * `edits` - The edits the changes are recorded in.
+    /// * `source` - The original XML text.
+    /// * `root` - The root element of the configuration (`<pfsense>` or `<opnsense>`).
+    /// * `rules` - The desired rules.
+    /// * `unit` - The indentation unit of the document.
+    ///
+    /// # Returns
+    /// * `Ok(())` - If the edits were recorded.
+    /// * `Err(FireparseError)` - If a rule has an unsupported type or an existing rule cannot be updated.
+    pub fn write(
+        edits: &mut XmlEdits,
+        source: &str,
+        root: Node,
+        rules: &[Rule],
+        unit: &str,
+    ) -> Result<(), FireparseError> {
+        if let Some(rule) = rules
+            .iter()
+            .find(|rule| !RULE_SECTIONS.contains(&rule.r#type.as_str()))
+        {
+            return Err(FireparseError::WriterError(format!(
+                "PfSenseRulesWriter: Unsupported rule type '{}'",
+                rule.r#type
+            )));
+        }
+
+        let identified = root.has_tag_name("opnsense");
+
+        for rule_type in RULE_SECTIONS {
+            let mut desired: Vec<&Rule> = rules.iter().filter(|r| r.r#type == rule_type).collect();
+            desired.sort_by_key(|rule| rule.order);
+
+            let section = find_child(root, rule_type);
+
+            if section.is_none() && desired.is_empty() {
+                continue;
+            }
+
+            let (slots, originals) = match section {
+                Some(section) => (
+                    section
+                        .children()
+                        .filter(|e| e.has_tag_name("rule"))
+                        .collect(),
+                    PfSenseRulesParser::parse_rules(section, rule_type),
+                ),
+                None => (vec![], vec![]),
+            };
+
+            let indent = match section {
+                Some(section) => item_indent(source, section, "rule", unit),
+                None => format!("{}{unit}", child_indent(source, root, unit)),
+            };
+
+            let items = match_rules(&originals, &desired)
+                .into_iter()
+                .zip(&desired)
+                .map(|(matched, rule)| match matched {
+                    Some(index) if same_rule(&originals[index], rule) => {
+                        Ok(source[slots[index].range()].to_string())
+                    }
+                    Some(index) => {
+                        Self::update_rule(source, slots[index], &originals[index], rule, unit)
+                    }
+                    None => Ok(Self::render_rule(rule, &indent, unit, identified)),
+                })
+                .collect::<Result<Vec<String>, FireparseError>>()?;
+
+            match section {
+                Some(section) => replace_items(edits, source, section, "rule", &items, unit),
+                None => append_child(
+                    edits,
+                    source,
+                    root,
+                    parent_element(rule_type, &items, &child_indent(source, root, unit), unit),
+                    unit,
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renders a new `<rule>` element, identified by a random `uuid` if `identified` is set.
+    fn render_rule(rule: &Rule, indent: &str, unit: &str, identified: bool) -> String {
+        let (ipprotocol, protocol) = split_protocol(&rule.protocol);
+        let inner = format!("{indent}{unit}");
+        let mut children = vec![];
+
+        if rule.r#type == "filter" {
+            children.push(text_element("type", &rule.policy));
+        }
+
+        if rule.interface != "none" {
+            children.push(text_element("interface", &rule.interface));
+        }
+
+        if rule.direction != "in" {
+            children.push(text_element("direction", &rule.direction));
+        }
+
+        children.push(text_element("ipprotocol", ipprotocol));
+
+        if protocol != ANY_PROTOCOL_VALUE {
+            children.push(text_element("protocol", protocol));
+        }
+
+        children.push(render_endpoint(
+            "source",
+            &rule.source_addr,
+            &rule.source_port,
+            &rule.source_type,
+            rule.source_inversed,
+            &inner,
+            unit,
+        ));
+
+        children.push(render_endpoint(
+            "destination",
+            &rule.destination_addr,
+            &rule.destination_port,
+            &rule.destination_type,
+            rule.destination_inversed,
+            &inner,
+            unit,
+        ));
+
+        if rule.disabled {
+            children.push(empty_element("disabled"));
+        }
+
+        if !rule.description.is_empty() {
+            children.push(cdata_element("descr", &rule.description));
+        }
+
+        let element = parent_element("rule", &children, indent, unit);
+
+        if !identified {
+            return element;
+        }
+
+        format!(
+            "<rule uuid=\"{}\">{}",
+            Uuid::new_v4(),
+            &element["<rule>".len()..]
+        )
+    }
+
+    /// Renders an existing `<rule>` element with the fields of `rule`,
+    /// changing only the elements whose value differs from `original`.
+    fn update_rule(
+        source: &str,
+        node: Node,
+        original: &Rule,
+        rule: &Rule,
+        unit: &str,
+    ) -> Result<String, FireparseError> {
+        let mut edits = XmlEdits::default();
+
+        if original.disabled != rule.disabled {
+            let element = rule.disabled.then(|| empty_element("disabled"));
+            set_child(&mut edits, source, node, "disabled", element, unit);
+        }
+
+        if original.policy != rule.policy {
+            set_child_text(&mut edits, source, node, "type", Some(&rule.policy), unit);
+        }
+
+        if original.protocol != rule.protocol {
+            let (old_ipprotocol, old_protocol) = split_protocol(&original.protocol);
+            let (ipprotocol, protocol) = split_protocol(&rule.protocol);
+
+            if old_ipprotocol != ipprotocol {
+                set_child_text(
+                    &mut edits,
+                    source,
+                    node,
+                    "ipprotocol",
+                    Some(ipprotocol),
+                    unit,
+                );
+            }
+
+            if old_protocol != protocol {
+                let protocol = (protocol != ANY_PROTOCOL_VALUE).then_some(protocol);
+                set_child_text(&mut edits, source, node, "protocol", protocol, unit);
+            }
+        }
+
+        if original.interface != rule.interface {
+            let interface = (rule.interface != "none").then_some(rule.interface.as_str());
+            set_child_text(&mut edits, source, node, "interface", interface, unit);
+        }
+
+        if original.direction != rule.direction {
+            let direction = (rule.direction != "in").then_some(rule.direction.as_str());
+            set_child_text(&mut edits, source, node, "direction", direction, unit);
+        }
+
+        if original.description != rule.description {
+            let description = (!rule.description.is_empty()).then_some(rule.description.as_str());
+
+            match find_child(node, "descr") {
+                Some(_) => set_child_text(&mut edits, source, node, "descr", description, unit),
+                None => set_child(
+                    &mut edits,
+                    source,
+                    node,
+                    "descr",
+                    description.map(|description| cdata_element("descr", description)),
+                    unit,
+                ),
+            }
+        }
+
+        let endpoints = [
+            (
+                "source",
+                (
+                    &original.source_addr,
+                    &original.source_port,
+                    &original.source_type,
+                    original.source_inversed,
+                ),
+                (
+                    &rule.source_addr,
+                    &rule.source_port,
+                    &rule.source_type,
+                    rule.source_inversed,
+                ),
+            ),
+            (
+                "destination",
+                (
+                    &original.destination_addr,
+                    &original.destination_port,
+                    &original.destination_type,
+                    original.destination_inversed,
+                ),
+                (
+                    &rule.destination_addr,
+                    &rule.destination_port,
+                    &rule.destination_type,
+                    rule.destination_inversed,
+                ),
+            ),
+        ];
+
+        for (tag, old, new) in endpoints {
+            if old == new {
+                continue;
+            }
+
+            let indent = match find_child(node, tag) {
+                Some(endpoint) => indent_of(source, endpoint).to_string(),
+                None => child_indent(source, node, unit),
+            };
+
+            let (addr, port, r#type, inversed) = new;
+            let element = render_endpoint(tag, addr, port, r#type, inversed, &indent, unit);
+            set_child(&mut edits, source, node, tag, Some(element), unit);
+        }
+
+        edits.apply(source, node.range())
+    }
+}
+
+/// Renders a `<source>` or `<destination>` element.
+fn render_endpoint(
+    tag: &str,
+    addr: &str,
+    port: &str,
+    r#type: &str,
+    inversed: bool,
+    indent: &str,
+    unit: &str,
+) -> String {
+    let mut children = vec![];
+
+    if addr == ANY_ADDR_VALUE {
+        children.push(empty_element("any"));
+    } else if r#type == "network" {
+        children.push(text_element("network", addr));
+    } else {
+        children.push(text_element("address", addr));
+    }
+
+    if port != ANY_PORT_VALUE {
+        children.push(text_element("port", port));
+    }
+
+    if inversed {
+        children.push(empty_element("not"));
+    }
+
+    parent_element(tag, &children, indent, unit)
+}
+
+/// Splits a protocol as reported by the parsers (e.g. `IPv4/tcp`)
+/// into the values of `<ipprotocol>` and `<protocol>`.
+fn split_protocol(protocol: &str) -> (&str, &str) {
+    let (ipprotocol, protocol) = protocol.split_once('/').unwrap_or(("none", protocol));
+
+    let ipprotocol = match ipprotocol {
+        "IPv4" => "inet",
+        "IPv6" => "inet6",
+        _ => "inet46",
+    };
+
+    (ipprotocol, protocol)
+}
+
+/// Compares the settings of two rules, ignoring their order, id and update time.
+fn same_rule(a: &Rule, b: &Rule) -> bool {
+    let settings = |rule: &Rule| Rule {
+        order: 0,
+        id: String::new(),
+        updated_at: None,
+        ..rule.clone()
+    };
+
+    settings(a) == settings(b)
+}
+
+/// Matches the desired rules to the existing ones.
+///
+/// Rules sharing the same id are matched first, then identical rules (preferring the same
+/// position), then rules sharing the same description and finally rules sharing the same order,
+/// which are considered updates.
+///
+/// # Returns
+/// For each desired rule, the index of the matching existing rule, if any.
+fn match_rules(originals: &[Rule], desired: &[&Rule]) -> Vec<Option<usize>> {
+    let originals: Vec<&Rule> = originals.iter().collect();
+
+    utils::match_rules(
+        &originals,
+        desired,
+        &[
+            &utils::same_rule_id,
+            &|a, b| a == b,
+            &same_rule,
+            &|a, b| !a.description.is_empty() && a.description == b.description,
+            &|a, b| a.order == b.order,
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{match_rules, split_protocol};
+    use crate::{Rule, test_utils};
+
+    fn rule(order: u64, description: &str) -> Rule {
+        test_utils::rule(order, "pass", "IPv4/any", description)
+    }
+
+    #[test]
+    fn test_split_protocol() {
+        assert_eq!(split_protocol("IPv4/tcp"), ("inet", "tcp"));
+        assert_eq!(split_protocol("IPv6/any"), ("inet6", "any"));
+        assert_eq!(split_protocol("none/udp"), ("inet46", "udp"));
+    }
+
+    #[test]
+    fn test_match_rules() {
+        let originals = vec![rule(0, "a"), rule(1, "b"), rule(2, "c")];
+
+        // Unchanged rules keep their match, edited ones update the rule sharing their order
+        let desired = [rule(0, "new"), rule(1, "b"), rule(2, "c edited")];
+        let desired: Vec<&Rule> = desired.iter().collect();
+
+        assert_eq!(
+            match_rules(&originals, &desired),
+            vec![Some(0), Some(1), Some(2)]
+        );
+
+        let desired = [rule(0, "b"), rule(1, "new"), rule(5, "c edited")];
+        let desired: Vec<&Rule> = desired.iter().collect();
+
+        assert_eq!(match_rules(&originals, &desired), vec![Some(1), None, None]);
+
+        // Moved rules keep their match when their description is unchanged
+        let mut moved = rule(7, "c");
+        moved.policy = String::from("block");
+        let desired = [rule(0, "new"), moved];
+        let desired: Vec<&Rule> = desired.iter().collect();
+
+        assert_eq!(match_rules(&originals, &desired), vec![Some(0), Some(2)]);
+
+        // Rules with an id keep their match, whatever else changed
+        let mut originals = originals;
+        originals[2].id = String::from("c-id");
+        let mut renamed = rule(0, "renamed");
+        renamed.id = String::from("c-id");
+        let desired = [renamed, rule(1, "a")];
+        let desired: Vec<&Rule> = desired.iter().collect();
+
+        assert_eq!(match_rules(&originals, &desired), vec![Some(2), Some(0)]);
+    }
+}
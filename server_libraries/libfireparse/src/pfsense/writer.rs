@@ -0,0 +1,276 @@
+use roxmltree::Document;
+
+use super::{aliases_writer::PfSenseAliasesWriter, rules_writer::PfSenseRulesWriter};
+use crate::{
+    Configuration, FireparseError, utils,
+    xml_editor::{XmlEdits, indent_unit},
+};
+
+/// A writer rendering an edited `Configuration` back into a pfSense `config.xml`.
+pub struct PfSenseWriter {}
+
+impl PfSenseWriter {
+    /// Renders a configuration into the `config.xml` it was parsed from.
+    ///
+    /// The original document is taken from `raw_content`. Rules, aliases and NAT entries are
+    /// inserted, updated or removed as needed, while every other part of the document
+    /// (including the formatting) is kept byte-for-byte. Rendering an unmodified configuration
+    /// returns the original document.
+    ///
+    /// # Arguments
+    /// * `configuration` - The edited configuration.
+    ///
+    /// # Returns
+    /// * `Ok(String)` - The updated `config.xml`.
+    /// * `Err(FireparseError)` - If the original document is invalid or the configuration cannot be written.
+    pub fn render(configuration: &Configuration) -> Result<String, FireparseError> {
+        let source = utils::decode_raw_content(&configuration.raw_content, "PfSenseWriter")?;
+
+        let document =
+            Document::parse(&source).map_err(|e| FireparseError::WriterError(e.to_string()))?;
+
+        let root = document.root_element();
+
+        if !root.has_tag_name("pfsense") {
+            return Err(FireparseError::WriterError(String::from(
+                "PfSenseWriter: 'config.xml' is not a pfSense configuration",
+            )));
+        }
+
+        let unit = indent_unit(&document, &source);
+        let mut edits = XmlEdits::default();
+
+        PfSenseAliasesWriter::write(
+            &mut edits,
+            &document,
+            &source,
+            &configuration.aliases,
+            &unit,
+        )?;
+        PfSenseRulesWriter::write(&mut edits, &source, root, &configuration.rules, &unit)?;
+
+        if edits.is_empty() {
+            return Ok(source);
+        }
+
+        edits.apply(&source, 0..source.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PfSenseWriter;
+    use crate::{Alias, Configuration, FileData, Rule, pfsense::PfSenseParser};
+    use nullnet_libconfmon::{InterfaceSnapshot, NETWORK_INTERFACES_ENTRY};
+
+    const CONFIG: &str = "<?xml version=\"1.0\"?>
+<pfsense>
+\t<version>23.3</version>
+\t<system>
+\t\t<hostname>pfSense</hostname>
+\t\t<domain>home.arpa</domain>
+\t</system>
+\t<aliases>
+\t\t<alias>
+\t\t\t<name>Ports</name>
+\t\t\t<type>port</type>
+\t\t\t<address>80 443</address>
+\t\t\t<descr><![CDATA[Web ports]]></descr>
+\t\t\t<detail><![CDATA[HTTP||HTTPS]]></detail>
+\t\t</alias>
+\t\t<alias>
+\t\t\t<name>Servers</name>
+\t\t\t<type>host</type>
+\t\t\t<address>10.0.0.10 10.0.0.11</address>
+\t\t\t<descr><![CDATA[]]></descr>
+\t\t\t<detail><![CDATA[Entry added Mon, 01 Jan 2024||]]></detail>
+\t\t</alias>
+\t</aliases>
+\t<nat>
+\t\t<outbound>
+\t\t\t<mode>automatic</mode>
+\t\t</outbound>
+\t\t<rule>
+\t\t\t<source>
+\t\t\t\t<any></any>
+\t\t\t</source>
+\t\t\t<destination>
+\t\t\t\t<network>wanip</network>
+\t\t\t\t<port>8080</port>
+\t\t\t</destination>
+\t\t\t<ipprotocol>inet</ipprotocol>
+\t\t\t<protocol>tcp</protocol>
+\t\t\t<target>10.0.0.10</target>
+\t\t\t<local-port>80</local-port>
+\t\t\t<interface>wan</interface>
+\t\t\t<descr><![CDATA[Web server]]></descr>
+\t\t</rule>
+\t</nat>
+\t<filter>
+\t\t<rule>
+\t\t\t<id></id>
+\t\t\t<tracker>1700000001</tracker>
+\t\t\t<type>pass</type>
+\t\t\t<interface>lan</interface>
+\t\t\t<ipprotocol>inet</ipprotocol>
+\t\t\t<tag></tag>
+\t\t\t<statetype><![CDATA[keep state]]></statetype>
+\t\t\t<source>
+\t\t\t\t<network>lan</network>
+\t\t\t</source>
+\t\t\t<destination>
+\t\t\t\t<any></any>
+\t\t\t</destination>
+\t\t\t<descr><![CDATA[Default allow LAN to any rule]]></descr>
+\t\t</rule>
+\t\t<rule>
+\t\t\t<tracker>1700000002</tracker>
+\t\t\t<type>block</type>
+\t\t\t<interface>wan</interface>
+\t\t\t<ipprotocol>inet</ipprotocol>
+\t\t\t<protocol>tcp</protocol>
+\t\t\t<source>
+\t\t\t\t<any></any>
+\t\t\t</source>
+\t\t\t<destination>
+\t\t\t\t<address>Servers</address>
+\t\t\t\t<port>22</port>
+\t\t\t</destination>
+\t\t\t<descr><![CDATA[Block SSH]]></descr>
+\t\t\t<created>
+\t\t\t\t<time>1700000002</time>
+\t\t\t\t<username><![CDATA[admin@10.0.0.5 (Local Database)]]></username>
+\t\t\t</created>
+\t\t</rule>
+\t\t<separator>
+\t\t\t<lan></lan>
+\t\t</separator>
+\t</filter>
+\t<!-- Packages keep their own settings -->
+\t<installedpackages>
+\t\t<package><name>pfBlockerNG</name></package>
+\t</installedpackages>
+</pfsense>
+";
+
+    fn parse(config: &str) -> Configuration {
+        let interfaces = InterfaceSnapshot::serialize_snapshot(&vec![]).unwrap();
+
+        let snapshot = vec![
+            FileData::new("config.xml", config.as_bytes().to_vec(), 0),
+            FileData::new(NETWORK_INTERFACES_ENTRY, interfaces, 0),
+        ];
+
+        PfSenseParser::parse(snapshot).unwrap_or_else(|_| panic!("Failed to parse"))
+    }
+
+    fn render(configuration: &Configuration) -> String {
+        PfSenseWriter::render(configuration).unwrap_or_else(|_| panic!("Failed to render"))
+    }
+
+    #[test]
+    fn test_roundtrip_without_edits_is_lossless() {
+        assert_eq!(render(&parse(CONFIG)), CONFIG);
+    }
+
+    #[test]
+    fn test_update_rules_and_aliases() {
+        let mut configuration = parse(CONFIG);
+
+        // Update the SSH rule, disable the NAT rule and add a rule at the top of the filter
+        let ssh = configuration
+            .rules
+            .iter_mut()
+            .find(|r| r.description == "Block SSH")
+            .unwrap();
+        ssh.policy = String::from("reject");
+        ssh.destination_port = String::from("2222");
+
+        let nat = configuration
+            .rules
+            .iter_mut()
+            .find(|r| r.r#type == "nat")
+            .unwrap();
+        nat.disabled = true;
+
+        for rule in configuration
+            .rules
+            .iter_mut()
+            .filter(|r| r.r#type == "filter")
+        {
+            rule.order += 1;
+        }
+
+        configuration.rules.push(Rule {
+            disabled: false,
+            r#type: String::from("filter"),
+            policy: String::from("pass"),
+            protocol: String::from("IPv6/udp"),
+            source_inversed: true,
+            source_port: String::from("*"),
+            source_addr: String::from("lan"),
+            source_type: String::from("network"),
+            destination_port: String::from("53"),
+            destination_addr: String::from("10.0.0.1"),
+            destination_type: String::from("address"),
+            destination_inversed: false,
+            description: String::from("DNS <internal>"),
+            interface: String::from("lan"),
+            direction: String::from("in"),
+            id: String::new(),
+            updated_at: None,
+            order: 0,
+        });
+
+        configuration.aliases[0].value = String::from("80 443 8080");
+        configuration.aliases[0].detail = String::from("HTTP||HTTPS||Proxy");
+        configuration.aliases.remove(1);
+        configuration.aliases.push(Alias {
+            r#type: String::from("urltable"),
+            name: String::from("Blocklist"),
+            value: String::from("https://example.com/list.txt"),
+            description: String::from("Remote list"),
+            detail: String::new(),
+        });
+
+        let output = render(&configuration);
+        let reparsed = parse(&output);
+
+        assert_eq!(reparsed.aliases, configuration.aliases);
+
+        let mut expected = configuration.rules.clone();
+        expected.sort_by(|a, b| a.r#type.cmp(&b.r#type).then(a.order.cmp(&b.order)));
+        let mut actual = reparsed.rules.clone();
+        actual.sort_by(|a, b| a.r#type.cmp(&b.r#type).then(a.order.cmp(&b.order)));
+        assert_eq!(actual, expected);
+
+        // Elements not modeled by libfireparse are kept
+        assert!(output.contains("<tracker>1700000002</tracker>"));
+        assert!(output.contains("<detail><![CDATA[HTTP||HTTPS||Proxy]]></detail>"));
+        assert!(output.contains("<local-port>80</local-port>"));
+        assert!(output.contains("\t\t<separator>\n\t\t\t<lan></lan>\n\t\t</separator>\n"));
+        assert!(output.contains("<!-- Packages keep their own settings -->"));
+        assert!(!output.contains("Servers</name>"));
+        assert!(output.contains("\t\t\t\t<port>2222</port>\n"));
+        assert!(output.contains("<descr><![CDATA[DNS <internal>]]></descr>"));
+    }
+
+    #[test]
+    fn test_create_missing_sections() {
+        let config = "<pfsense>\n  <system><hostname>fw</hostname></system>\n</pfsense>\n";
+        let mut configuration = parse(config);
+
+        configuration.aliases.push(Alias {
+            r#type: String::from("host"),
+            name: String::from("Dns"),
+            value: String::from("1.1.1.1"),
+            description: String::new(),
+            detail: String::new(),
+        });
+
+        assert_eq!(
+            render(&configuration),
+            "<pfsense>\n  <system><hostname>fw</hostname></system>\n  <aliases>\n    <alias>\n      <name>Dns</name>\n      <type>host</type>\n      <address>1.1.1.1</address>\n    </alias>\n  </aliases>\n</pfsense>\n"
+        );
+    }
+}
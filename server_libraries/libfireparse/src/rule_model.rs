@@ -0,0 +1,630 @@
+use std::{fmt, net::IpAddr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Rule;
+
+const ANY_ADDR_VALUE: &str = "*";
+const ANY_PORT_VALUE: &str = "*";
+const NETWORK_TYPE_VALUE: &str = "network";
+const ADDRESS_TYPE_VALUE: &str = "address";
+const SELF_ADDR_VALUE: &str = "(self)";
+const INTERFACE_ADDR_SUFFIX: &str = "ip";
+
+/// The IP version a rule applies to, as found in the prefix of `Rule.protocol` (e.g. `IPv4` in `IPv4/tcp`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IpVersion {
+    V4,
+    V6,
+    /// Both versions (`none`).
+    Any,
+}
+
+impl IpVersion {
+    /// Returns the IP version of an address, network (`10.0.0.0/8`) or range (`10.0.0.1-10.0.0.9`).
+    ///
+    /// # Arguments
+    /// * `address` - The address to inspect.
+    ///
+    /// # Returns
+    /// The `IpVersion` of the address, or `None` if it is not an IP address (e.g. an alias).
+    pub fn of_address(address: &str) -> Option<IpVersion> {
+        let first = address.split(['/', '-']).next()?;
+
+        match first.parse::<IpAddr>().ok()? {
+            IpAddr::V4(_) => Some(IpVersion::V4),
+            IpAddr::V6(_) => Some(IpVersion::V6),
+        }
+    }
+
+    /// Returns `true` if both versions can apply to the same packet.
+    pub fn overlaps(self, other: IpVersion) -> bool {
+        self == IpVersion::Any || other == IpVersion::Any || self == other
+    }
+}
+
+impl From<&str> for IpVersion {
+    fn from(value: &str) -> Self {
+        match value {
+            "IPv4" => IpVersion::V4,
+            "IPv6" => IpVersion::V6,
+            _ => IpVersion::Any,
+        }
+    }
+}
+
+impl fmt::Display for IpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpVersion::V4 => write!(f, "IPv4"),
+            IpVersion::V6 => write!(f, "IPv6"),
+            IpVersion::Any => write!(f, "none"),
+        }
+    }
+}
+
+/// The transport protocol a rule matches, as found after the IP version prefix of `Rule.protocol`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    Any,
+    Tcp,
+    Udp,
+    /// pfSense's `tcp/udp`, matching both protocols.
+    TcpUdp,
+    Icmp,
+    /// `ipv6-icmp` (or nftables' `icmpv6`), written back as `ipv6-icmp`.
+    Icmpv6,
+    /// Any other protocol, kept as written (e.g. `esp`, `gre`, `47`).
+    Other(String),
+}
+
+impl Protocol {
+    /// Returns `true` if the protocol carries ports, i.e. if port matches apply to it.
+    pub fn has_ports(&self) -> bool {
+        matches!(self, Protocol::Tcp | Protocol::Udp | Protocol::TcpUdp)
+    }
+}
+
+impl From<&str> for Protocol {
+    fn from(value: &str) -> Self {
+        match value {
+            "any" | "" => Protocol::Any,
+            "tcp" => Protocol::Tcp,
+            "udp" => Protocol::Udp,
+            "tcp/udp" => Protocol::TcpUdp,
+            "icmp" => Protocol::Icmp,
+            "ipv6-icmp" | "icmpv6" => Protocol::Icmpv6,
+            other => Protocol::Other(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Protocol::Any => write!(f, "any"),
+            Protocol::Tcp => write!(f, "tcp"),
+            Protocol::Udp => write!(f, "udp"),
+            Protocol::TcpUdp => write!(f, "tcp/udp"),
+            Protocol::Icmp => write!(f, "icmp"),
+            Protocol::Icmpv6 => write!(f, "ipv6-icmp"),
+            Protocol::Other(other) => write!(f, "{other}"),
+        }
+    }
+}
+
+/// What a rule does with the traffic it matches, as found in `Rule.policy`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    Pass,
+    Block,
+    Reject,
+    /// Any other policy, kept as written (e.g. `log`, or the chain of an nftables `jump`).
+    Other(String),
+}
+
+impl From<&str> for Action {
+    fn from(value: &str) -> Self {
+        match value {
+            "pass" => Action::Pass,
+            "block" => Action::Block,
+            "reject" => Action::Reject,
+            other => Action::Other(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Pass => write!(f, "pass"),
+            Action::Block => write!(f, "block"),
+            Action::Reject => write!(f, "reject"),
+            Action::Other(other) => write!(f, "{other}"),
+        }
+    }
+}
+
+/// The direction of traffic relative to an interface, as in pfSense's `<direction>`.
+///
+/// Interface rules of pfSense and OPNsense apply to inbound traffic, floating rules may apply
+/// to either direction. Rules parsed from Linux rulesets take the direction of their chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    In,
+    Out,
+    Any,
+}
+
+impl From<&str> for Direction {
+    fn from(value: &str) -> Self {
+        match value {
+            "in" => Direction::In,
+            "out" => Direction::Out,
+            _ => Direction::Any,
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::In => write!(f, "in"),
+            Direction::Out => write!(f, "out"),
+            Direction::Any => write!(f, "any"),
+        }
+    }
+}
+
+/// The ports matched by an endpoint, as found in `Rule.source_port` and `Rule.destination_port`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PortSpec {
+    /// Any port (`*`).
+    Any,
+    Single(u16),
+    /// An inclusive range (`1000-2000`, or `1000:2000`).
+    Range(u16, u16),
+    /// Several ports or ranges (`80,443,8000-8080`), as produced by iptables' `multiport`.
+    List(Vec<PortSpec>),
+    /// A port alias, or any value that is not a port number.
+    Alias(String),
+}
+
+impl PortSpec {
+    /// Parses a single port or range.
+    fn parse_port(value: &str) -> Option<PortSpec> {
+        if let Ok(port) = value.parse() {
+            return Some(PortSpec::Single(port));
+        }
+
+        let (start, end) = value.split_once(['-', ':'])?;
+        Some(PortSpec::Range(start.parse().ok()?, end.parse().ok()?))
+    }
+}
+
+impl From<&str> for PortSpec {
+    fn from(value: &str) -> Self {
+        if value == ANY_PORT_VALUE || value.is_empty() {
+            return PortSpec::Any;
+        }
+
+        if value.contains(',') {
+            let ports: Option<Vec<PortSpec>> = value.split(',').map(PortSpec::parse_port).collect();
+
+            if let Some(ports) = ports {
+                return PortSpec::List(ports);
+            }
+        }
+
+        PortSpec::parse_port(value).unwrap_or_else(|| PortSpec::Alias(value.to_string()))
+    }
+}
+
+impl fmt::Display for PortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortSpec::Any => write!(f, "{ANY_PORT_VALUE}"),
+            PortSpec::Single(port) => write!(f, "{port}"),
+            PortSpec::Range(start, end) => write!(f, "{start}-{end}"),
+            PortSpec::List(ports) => {
+                let ports: Vec<String> = ports.iter().map(ToString::to_string).collect();
+                write!(f, "{}", ports.join(","))
+            }
+            PortSpec::Alias(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// The addresses matched by an endpoint, as found in `Rule.source_addr` and `Rule.destination_addr`
+/// along with `Rule.source_type` and `Rule.destination_type`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Address {
+    /// Any address (`*`).
+    Any,
+    Ip(IpAddr),
+    /// A network in CIDR notation (`10.0.0.0/8`).
+    Cidr(IpAddr, u8),
+    /// An inclusive range (`10.0.0.1-10.0.0.9`).
+    Range(IpAddr, IpAddr),
+    /// Several addresses (`10.0.0.1,10.0.0.2`), as accepted by iptables' `-s` and `-d`.
+    List(Vec<Address>),
+    /// The subnet of an interface (a `network` keyword such as `lan` or `opt1`).
+    Network(String),
+    /// The address of an interface (a `network` keyword such as `lanip` or `wanip`), holding the interface name.
+    InterfaceAddress(String),
+    /// Every address of the firewall itself (`(self)`).
+    Firewall,
+    /// A host or network alias, or any value that is not an address.
+    Alias(String),
+}
+
+impl Address {
+    /// Parses an endpoint address along with its type.
+    ///
+    /// # Arguments
+    /// * `addr` - The address, e.g. `Rule.source_addr`.
+    /// * `r#type` - The type of the address, e.g. `Rule.source_type` (`"network"` or `"address"`).
+    ///
+    /// # Returns
+    /// The typed `Address`. Values that cannot be recognized are kept as `Address::Alias`.
+    pub fn parse(addr: &str, r#type: &str) -> Address {
+        if addr == ANY_ADDR_VALUE || addr.is_empty() {
+            return Address::Any;
+        }
+
+        if r#type == NETWORK_TYPE_VALUE {
+            if addr == SELF_ADDR_VALUE {
+                return Address::Firewall;
+            }
+
+            return match addr.strip_suffix(INTERFACE_ADDR_SUFFIX) {
+                Some(interface) if !interface.is_empty() => {
+                    Address::InterfaceAddress(interface.to_string())
+                }
+                _ => Address::Network(addr.to_string()),
+            };
+        }
+
+        if addr.contains(',') {
+            let addresses: Option<Vec<Address>> = addr.split(',').map(Self::parse_ip).collect();
+
+            if let Some(addresses) = addresses {
+                return Address::List(addresses);
+            }
+        }
+
+        Self::parse_ip(addr).unwrap_or_else(|| Address::Alias(addr.to_string()))
+    }
+
+    /// Parses a single address, network or range.
+    fn parse_ip(value: &str) -> Option<Address> {
+        if let Ok(ip) = value.parse() {
+            return Some(Address::Ip(ip));
+        }
+
+        if let Some((ip, prefix)) = value.split_once('/') {
+            let ip: IpAddr = ip.parse().ok()?;
+            let prefix: u8 = prefix.parse().ok()?;
+            let max_prefix = if ip.is_ipv4() { 32 } else { 128 };
+
+            return (prefix <= max_prefix).then_some(Address::Cidr(ip, prefix));
+        }
+
+        let (start, end) = value.split_once('-')?;
+        Some(Address::Range(start.parse().ok()?, end.parse().ok()?))
+    }
+
+    /// Returns the type of the address, as stored in `Rule.source_type` and `Rule.destination_type`.
+    pub fn r#type(&self) -> &'static str {
+        match self {
+            Address::Network(_) | Address::InterfaceAddress(_) | Address::Firewall => {
+                NETWORK_TYPE_VALUE
+            }
+            _ => ADDRESS_TYPE_VALUE,
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Address::Any => write!(f, "{ANY_ADDR_VALUE}"),
+            Address::Ip(ip) => write!(f, "{ip}"),
+            Address::Cidr(ip, prefix) => write!(f, "{ip}/{prefix}"),
+            Address::Range(start, end) => write!(f, "{start}-{end}"),
+            Address::List(addresses) => {
+                let addresses: Vec<String> = addresses.iter().map(ToString::to_string).collect();
+                write!(f, "{}", addresses.join(","))
+            }
+            Address::Network(name) => write!(f, "{name}"),
+            Address::InterfaceAddress(interface) => write!(f, "{interface}{INTERFACE_ADDR_SUFFIX}"),
+            Address::Firewall => write!(f, "{SELF_ADDR_VALUE}"),
+            Address::Alias(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// The source or destination of a rule.
+///
+/// # Fields
+/// - `address`: The addresses matched by the endpoint.
+/// - `port`: The ports matched by the endpoint.
+/// - `negated`: Whether the endpoint matches everything but `address`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Endpoint {
+    pub address: Address,
+    pub port: PortSpec,
+    pub negated: bool,
+}
+
+impl Endpoint {
+    /// Builds an endpoint from its string form, as stored in `Rule`.
+    ///
+    /// # Arguments
+    /// * `addr` - The address, e.g. `Rule.source_addr`.
+    /// * `port` - The port, e.g. `Rule.source_port`.
+    /// * `r#type` - The type of the address, e.g. `Rule.source_type`.
+    /// * `inversed` - Whether the endpoint is negated, e.g. `Rule.source_inversed`.
+    pub fn from_parts(addr: &str, port: &str, r#type: &str, inversed: bool) -> Endpoint {
+        Endpoint {
+            address: Address::parse(addr, r#type),
+            port: PortSpec::from(port),
+            negated: inversed,
+        }
+    }
+
+    /// Returns the string form of the endpoint, as stored in `Rule`.
+    ///
+    /// # Returns
+    /// A tuple `(String, String, String, bool)` holding the address, the port, the type and the negation.
+    pub fn to_parts(&self) -> (String, String, String, bool) {
+        (
+            self.address.to_string(),
+            self.port.to_string(),
+            self.address.r#type().to_string(),
+            self.negated,
+        )
+    }
+}
+
+/// A `Rule` with its fields parsed into typed representations.
+///
+/// It converts from and to `Rule` without loss for the values produced by the parsers,
+/// and (de)serializes in the same format as `Rule`.
+///
+/// # Fields
+/// - `disabled`: Whether the rule is disabled.
+/// - `r#type`: The type of the rule (`"filter"` or `"nat"`).
+/// - `action`: What the rule does with the traffic it matches.
+/// - `ip_version`: The IP version the rule applies to.
+/// - `protocol`: The protocol the rule matches.
+/// - `source`: The source of the traffic.
+/// - `destination`: The destination of the traffic.
+/// - `description`: The description of the rule.
+/// - `interface`: The interface the rule applies to.
+/// - `direction`: The direction of the traffic the rule applies to, relative to `interface`.
+/// - `id`: The stable id of the rule, empty if the platform has none.
+/// - `updated_at`: The time of the last update of the rule, in milliseconds since the UNIX epoch.
+/// - `order`: The position of the rule among the rules of its type.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(from = "Rule", into = "Rule")]
+pub struct TypedRule {
+    pub disabled: bool,
+    pub r#type: String,
+    pub action: Action,
+    pub ip_version: IpVersion,
+    pub protocol: Protocol,
+    pub source: Endpoint,
+    pub destination: Endpoint,
+    pub description: String,
+    pub interface: String,
+    pub direction: Direction,
+    pub id: String,
+    pub updated_at: Option<u64>,
+    pub order: u64,
+}
+
+impl From<&Rule> for TypedRule {
+    fn from(rule: &Rule) -> Self {
+        let (ip_version, protocol) = rule
+            .protocol
+            .split_once('/')
+            .unwrap_or(("none", &rule.protocol));
+
+        TypedRule {
+            disabled: rule.disabled,
+            r#type: rule.r#type.clone(),
+            action: Action::from(rule.policy.as_str()),
+            ip_version: IpVersion::from(ip_version),
+            protocol: Protocol::from(protocol),
+            source: Endpoint::from_parts(
+                &rule.source_addr,
+                &rule.source_port,
+                &rule.source_type,
+                rule.source_inversed,
+            ),
+            destination: Endpoint::from_parts(
+                &rule.destination_addr,
+                &rule.destination_port,
+                &rule.destination_type,
+                rule.destination_inversed,
+            ),
+            description: rule.description.clone(),
+            interface: rule.interface.clone(),
+            direction: Direction::from(rule.direction.as_str()),
+            id: rule.id.clone(),
+            updated_at: rule.updated_at,
+            order: rule.order,
+        }
+    }
+}
+
+impl From<Rule> for TypedRule {
+    fn from(rule: Rule) -> Self {
+        TypedRule::from(&rule)
+    }
+}
+
+impl From<&TypedRule> for Rule {
+    fn from(rule: &TypedRule) -> Self {
+        let (source_addr, source_port, source_type, source_inversed) = rule.source.to_parts();
+
+        let (destination_addr, destination_port, destination_type, destination_inversed) =
+            rule.destination.to_parts();
+
+        Rule {
+            disabled: rule.disabled,
+            r#type: rule.r#type.clone(),
+            policy: rule.action.to_string(),
+            protocol: format!("{}/{}", rule.ip_version, rule.protocol),
+            source_inversed,
+            source_port,
+            source_addr,
+            source_type,
+            destination_port,
+            destination_addr,
+            destination_type,
+            destination_inversed,
+            description: rule.description.clone(),
+            interface: rule.interface.clone(),
+            direction: rule.direction.to_string(),
+            id: rule.id.clone(),
+            updated_at: rule.updated_at,
+            order: rule.order,
+        }
+    }
+}
+
+impl From<TypedRule> for Rule {
+    fn from(rule: TypedRule) -> Self {
+        Rule::from(&rule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Address, Endpoint, IpVersion, PortSpec, Protocol, TypedRule};
+    use crate::Rule;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn rule() -> Rule {
+        Rule {
+            disabled: false,
+            r#type: String::from("filter"),
+            policy: String::from("block"),
+            protocol: String::from("IPv4/tcp/udp"),
+            source_inversed: true,
+            source_port: String::from("*"),
+            source_addr: String::from("lan"),
+            source_type: String::from("network"),
+            destination_port: String::from("80,443,8000-8080"),
+            destination_addr: String::from("Servers"),
+            destination_type: String::from("address"),
+            destination_inversed: false,
+            description: String::from("Block web"),
+            interface: String::from("wan"),
+            direction: String::from("in"),
+            id: String::new(),
+            updated_at: None,
+            order: 3,
+        }
+    }
+
+    #[test]
+    fn test_typed_rule_from_rule() {
+        let typed = TypedRule::from(&rule());
+
+        assert_eq!(typed.action, Action::Block);
+        assert_eq!(typed.ip_version, IpVersion::V4);
+        assert_eq!(typed.protocol, Protocol::TcpUdp);
+        assert_eq!(
+            typed.source,
+            Endpoint {
+                address: Address::Network(String::from("lan")),
+                port: PortSpec::Any,
+                negated: true,
+            }
+        );
+        assert_eq!(
+            typed.destination,
+            Endpoint {
+                address: Address::Alias(String::from("Servers")),
+                port: PortSpec::List(vec![
+                    PortSpec::Single(80),
+                    PortSpec::Single(443),
+                    PortSpec::Range(8000, 8080),
+                ]),
+                negated: false,
+            }
+        );
+
+        assert_eq!(Rule::from(typed), rule());
+    }
+
+    #[test]
+    fn test_parse_addresses() {
+        assert_eq!(Address::parse("*", "address"), Address::Any);
+        assert_eq!(
+            Address::parse("10.0.0.1", "address"),
+            Address::Ip(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)))
+        );
+        assert_eq!(
+            Address::parse("10.0.0.0/8", "address"),
+            Address::Cidr(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 8)
+        );
+        assert_eq!(
+            Address::parse("10.0.0.0/40", "address"),
+            Address::Alias(String::from("10.0.0.0/40"))
+        );
+        assert_eq!(
+            Address::parse("lanip", "network"),
+            Address::InterfaceAddress(String::from("lan"))
+        );
+        assert_eq!(Address::parse("(self)", "network"), Address::Firewall);
+        assert_eq!(
+            Address::parse("opt1", "network"),
+            Address::Network(String::from("opt1"))
+        );
+
+        for (addr, r#type) in [
+            ("2001:db8::/32", "address"),
+            ("10.0.0.1-10.0.0.9", "address"),
+            ("10.0.0.1,10.0.0.2/31", "address"),
+            ("wanip", "network"),
+            ("(self)", "network"),
+            ("lan", "network"),
+            ("Servers", "address"),
+        ] {
+            let address = Address::parse(addr, r#type);
+            assert_eq!(
+                (address.to_string().as_str(), address.r#type()),
+                (addr, r#type)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_ports_and_protocols() {
+        assert_eq!(PortSpec::from("*"), PortSpec::Any);
+        assert_eq!(PortSpec::from("22"), PortSpec::Single(22));
+        assert_eq!(PortSpec::from("1000:2000"), PortSpec::Range(1000, 2000));
+        assert_eq!(
+            PortSpec::from("Ports"),
+            PortSpec::Alias(String::from("Ports"))
+        );
+        assert_eq!(
+            PortSpec::from("80,https"),
+            PortSpec::Alias(String::from("80,https"))
+        );
+
+        assert_eq!(Protocol::from("icmpv6"), Protocol::Icmpv6);
+        assert_eq!(Protocol::from("esp").to_string(), "esp");
+        assert_eq!(Action::from("log"), Action::Other(String::from("log")));
+
+        for protocol in ["IPv6/ipv6-icmp", "none/any", "IPv4/gre"] {
+            let mut rule = rule();
+            rule.protocol = String::from(protocol);
+            assert_eq!(Rule::from(TypedRule::from(&rule)).protocol, protocol);
+        }
+    }
+}
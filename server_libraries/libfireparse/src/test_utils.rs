@@ -0,0 +1,76 @@
+use crate::{Alias, Configuration, IpAddress, NetworkInterface, Rule, SSHConfig};
+
+/// Builds a filter rule on `lan` matching any source and destination.
+pub fn rule(order: u64, policy: &str, protocol: &str, description: &str) -> Rule {
+    Rule {
+        disabled: false,
+        r#type: String::from("filter"),
+        policy: String::from(policy),
+        protocol: String::from(protocol),
+        source_inversed: false,
+        source_port: String::from("*"),
+        source_addr: String::from("*"),
+        source_type: String::from("address"),
+        destination_port: String::from("*"),
+        destination_addr: String::from("*"),
+        destination_type: String::from("address"),
+        destination_inversed: false,
+        description: String::from(description),
+        interface: String::from("lan"),
+        direction: String::from("in"),
+        id: String::new(),
+        updated_at: None,
+        order,
+    }
+}
+
+/// Builds an alias.
+pub fn alias(r#type: &str, name: &str, value: &str, description: &str) -> Alias {
+    Alias {
+        r#type: String::from(r#type),
+        name: String::from(name),
+        value: String::from(value),
+        description: String::from(description),
+        detail: String::new(),
+    }
+}
+
+/// Builds an interface holding the given addresses.
+pub fn interface(name: &str, device: &str, addresses: &[&str]) -> NetworkInterface {
+    NetworkInterface {
+        name: String::from(name),
+        device: String::from(device),
+        addresses: addresses
+            .iter()
+            .map(|address| IpAddress {
+                address: String::from(*address),
+                version: if address.contains(':') { 6 } else { 4 },
+            })
+            .collect(),
+    }
+}
+
+/// Builds the `lan` interface, on `em1` with `192.168.1.1/24`.
+pub fn lan() -> NetworkInterface {
+    interface("lan", "em1", &["192.168.1.1/24"])
+}
+
+/// Builds a configuration of a firewall named `fw`, without `raw_content`.
+pub fn configuration(
+    rules: Vec<Rule>,
+    aliases: Vec<Alias>,
+    interfaces: Vec<NetworkInterface>,
+) -> Configuration {
+    Configuration {
+        rules,
+        aliases,
+        interfaces,
+        raw_content: String::new(),
+        hostname: String::from("fw"),
+        gui_protocol: String::from("https"),
+        ssh: SSHConfig {
+            enabled: false,
+            port: 22,
+        },
+    }
+}
@@ -0,0 +1,598 @@
+use roxmltree::Document;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{Alias, Configuration, FireparseError, NetworkInterface, Platform, Rule, utils};
+
+/// Alias types sharing the same meaning on pfSense and OPNsense.
+const COMMON_ALIAS_TYPES: [&str; 5] = ["host", "network", "port", "url", "urltable"];
+
+/// An item of a configuration that could not be (fully) translated.
+///
+/// # Fields
+/// - `item`: The item concerned, e.g. `alias 'Blocklist'` or `package 'pfBlockerNG'`.
+/// - `reason`: Why the item was dropped or needs a manual review.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TranslationIssue {
+    pub item: String,
+    pub reason: String,
+}
+
+/// The result of a translation.
+///
+/// # Fields
+/// - `configuration`: The translated configuration, ready to be rendered with `Writer::render`.
+/// - `issues`: The items that could not be translated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Translation {
+    pub configuration: Configuration,
+    pub issues: Vec<TranslationIssue>,
+}
+
+/// A translator moving the rules and aliases of a configuration between pfSense and OPNsense.
+pub struct Translator {}
+
+impl Translator {
+    /// Translates a configuration into the structure of another platform.
+    ///
+    /// The result is based on `target`, the configuration of the destination firewall, whose
+    /// rules and aliases are replaced by the translated ones. Rendering it with `Writer::render`
+    /// produces the destination `config.xml`.
+    ///
+    /// Interfaces are assigned by name (`wan`, `lan`, `opt1`, ...) or, failing that, by device.
+    /// Rules on interfaces missing on the destination and aliases of types the destination doesn't
+    /// support are dropped, as are the rules referencing them. Settings of packages and plugins
+    /// are never translated. Every dropped or incomplete item is reported.
+    ///
+    /// # Arguments
+    /// * `source` - The configuration to translate.
+    /// * `from` - The platform of `source`.
+    /// * `target` - The configuration of the destination firewall.
+    /// * `to` - The platform of `target`.
+    ///
+    /// # Returns
+    /// * `Ok(Translation)` - The translated configuration and the report of untranslated items.
+    /// * `Err(FireparseError)` - If a platform is unsupported or the source document is invalid.
+    pub fn translate(
+        source: &Configuration,
+        from: Platform,
+        target: &Configuration,
+        to: Platform,
+    ) -> Result<Translation, FireparseError> {
+        for platform in [from, to] {
+            if platform == Platform::Linux {
+                return Err(FireparseError::UnsupportedPlatform(format!(
+                    "Translation is not supported for {platform:?}"
+                )));
+            }
+        }
+
+        let mut issues = vec![];
+
+        let interfaces = map_interfaces(&source.interfaces, &target.interfaces, &mut issues);
+
+        let mut aliases = vec![];
+        let mut dropped_aliases = vec![];
+
+        for alias in &source.aliases {
+            match translate_alias(alias, from, to) {
+                Ok(alias) => aliases.push(alias),
+                Err(reason) => {
+                    issues.push(TranslationIssue {
+                        item: format!("alias '{}'", alias.name),
+                        reason,
+                    });
+                    dropped_aliases.push(alias.name.as_str());
+                }
+            }
+        }
+
+        let mut rules: Vec<Rule> = vec![];
+
+        // Rules are renumbered per type, so they must be taken in their evaluation order
+        let mut source_rules: Vec<&Rule> = source.rules.iter().collect();
+        source_rules.sort_by_key(|rule| rule.order);
+
+        for rule in source_rules {
+            match translate_rule(rule, &interfaces, &dropped_aliases) {
+                Ok(mut translated) => {
+                    translated.order =
+                        rules.iter().filter(|r| r.r#type == rule.r#type).count() as u64;
+
+                    if rule.r#type == "nat" {
+                        issues.push(TranslationIssue {
+                            item: rule_item(rule),
+                            reason: String::from(
+                                "The redirect target is not part of the shared model and must be set again",
+                            ),
+                        });
+                    }
+
+                    rules.push(translated);
+                }
+                Err(reason) => issues.push(TranslationIssue {
+                    item: rule_item(rule),
+                    reason,
+                }),
+            }
+        }
+
+        issues.append(&mut unmodeled_settings(source, from)?);
+
+        Ok(Translation {
+            configuration: Configuration {
+                rules,
+                aliases,
+                ..target.clone()
+            },
+            issues,
+        })
+    }
+}
+
+/// Maps the interfaces of the source to the ones of the destination.
+///
+/// # Returns
+/// For each source interface that could be assigned, the name of the destination interface.
+fn map_interfaces(
+    source: &[NetworkInterface],
+    target: &[NetworkInterface],
+    issues: &mut Vec<TranslationIssue>,
+) -> HashMap<String, String> {
+    let mut mapping = HashMap::new();
+
+    for interface in source {
+        let assigned = target
+            .iter()
+            .find(|t| t.name == interface.name)
+            .or_else(|| target.iter().find(|t| t.device == interface.device));
+
+        match assigned {
+            Some(assigned) => {
+                mapping.insert(interface.name.clone(), assigned.name.clone());
+            }
+            None => issues.push(TranslationIssue {
+                item: format!("interface '{}'", interface.name),
+                reason: format!(
+                    "No interface named '{}' or using device '{}' on the destination",
+                    interface.name, interface.device
+                ),
+            }),
+        }
+    }
+
+    mapping
+}
+
+/// Translates an alias, converting the separator of its values.
+///
+/// pfSense separates the values of an alias with spaces, OPNsense with new lines.
+fn translate_alias(alias: &Alias, from: Platform, to: Platform) -> Result<Alias, String> {
+    let r#type = match alias.r#type.as_str() {
+        r#type if from == to || COMMON_ALIAS_TYPES.contains(&r#type) => r#type,
+        // pfSense network aliases may hold other aliases, as OPNsense network groups do
+        "networkgroup" if to == Platform::PfSense => "network",
+        r#type => {
+            return Err(format!(
+                "Alias type '{}' is not supported on {to:?}",
+                r#type
+            ));
+        }
+    };
+
+    let value = match (from, to) {
+        (Platform::PfSense, Platform::OPNsense) => alias
+            .value
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("\n"),
+        (Platform::OPNsense, Platform::PfSense) => {
+            alias.value.split_whitespace().collect::<Vec<_>>().join(" ")
+        }
+        _ => alias.value.clone(),
+    };
+
+    Ok(Alias {
+        r#type: r#type.to_string(),
+        value,
+        ..alias.clone()
+    })
+}
+
+/// Translates a rule, assigning its interface and the interface networks it references.
+fn translate_rule(
+    rule: &Rule,
+    interfaces: &HashMap<String, String>,
+    dropped_aliases: &[&str],
+) -> Result<Rule, String> {
+    for value in [
+        &rule.source_addr,
+        &rule.source_port,
+        &rule.destination_addr,
+        &rule.destination_port,
+    ] {
+        if dropped_aliases.contains(&value.as_str()) {
+            return Err(format!("References the untranslated alias '{value}'"));
+        }
+    }
+
+    let interface = match rule.interface.as_str() {
+        "none" => String::from("none"),
+        name => interfaces
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Interface '{name}' is not assigned on the destination"))?,
+    };
+
+    // Ids identify rules in the source configuration only
+    Ok(Rule {
+        interface,
+        id: String::new(),
+        source_addr: map_network(&rule.source_addr, &rule.source_type, interfaces),
+        destination_addr: map_network(&rule.destination_addr, &rule.destination_type, interfaces),
+        ..rule.clone()
+    })
+}
+
+/// Maps the interface networks (`lan`) and addresses (`lanip`) used as rule endpoints.
+fn map_network(addr: &str, r#type: &str, interfaces: &HashMap<String, String>) -> String {
+    if r#type != "network" {
+        return addr.to_string();
+    }
+
+    let (name, suffix) = match addr.strip_suffix("ip") {
+        Some(name) if interfaces.contains_key(name) => (name, "ip"),
+        _ => (addr, ""),
+    };
+
+    match interfaces.get(name) {
+        Some(mapped) => format!("{mapped}{suffix}"),
+        None => addr.to_string(),
+    }
+}
+
+/// Describes a rule in the report.
+fn rule_item(rule: &Rule) -> String {
+    match rule.description.is_empty() {
+        true => format!("{} rule #{}", rule.r#type, rule.order),
+        false => format!(
+            "{} rule #{} '{}'",
+            rule.r#type, rule.order, rule.description
+        ),
+    }
+}
+
+/// Lists the settings of the source document that are specific to its platform:
+/// installed packages on pfSense, and plugin and MVC firewall settings on OPNsense.
+fn unmodeled_settings(
+    source: &Configuration,
+    from: Platform,
+) -> Result<Vec<TranslationIssue>, FireparseError> {
+    let content = utils::decode_raw_content(&source.raw_content, "Translator")?;
+    let document =
+        Document::parse(&content).map_err(|e| FireparseError::ParserError(e.to_string()))?;
+    let root = document.root_element();
+
+    let mut issues = vec![];
+
+    match from {
+        Platform::PfSense => {
+            let packages = root
+                .children()
+                .filter(|e| e.has_tag_name("installedpackages"))
+                .flat_map(|e| e.children())
+                .filter(|e| e.has_tag_name("package"));
+
+            for package in packages {
+                let name = package
+                    .children()
+                    .find(|e| e.has_tag_name("name"))
+                    .and_then(|e| e.text())
+                    .unwrap_or("none");
+
+                issues.push(TranslationIssue {
+                    item: format!("package '{name}'"),
+                    reason: String::from("Package settings are not translated"),
+                });
+            }
+        }
+        Platform::OPNsense => {
+            let sections = root
+                .children()
+                .filter(|e| e.has_tag_name("OPNsense"))
+                .flat_map(|e| e.children())
+                .filter(|e| e.is_element());
+
+            for section in sections {
+                if section.has_tag_name("Firewall") {
+                    // Only aliases are part of the shared model
+                    for model in section
+                        .children()
+                        .filter(|e| e.is_element() && !e.has_tag_name("Alias"))
+                    {
+                        issues.push(TranslationIssue {
+                            item: format!("Firewall/{}", model.tag_name().name()),
+                            reason: String::from("Firewall settings outside of the legacy rules and aliases are not translated"),
+                        });
+                    }
+                } else {
+                    issues.push(TranslationIssue {
+                        item: String::from(section.tag_name().name()),
+                        reason: String::from("Plugin settings are not translated"),
+                    });
+                }
+            }
+        }
+        Platform::Linux => {}
+    }
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TranslationIssue, Translator};
+    use crate::{
+        Configuration, FileData, Parser, Platform, Writer, test_utils::rule,
+        utils::decode_raw_content,
+    };
+    use nullnet_libconfmon::{InterfaceSnapshot, NETWORK_INTERFACES_ENTRY};
+
+    const PFSENSE_CONFIG: &str = r#"<?xml version="1.0"?>
+<pfsense>
+    <interfaces>
+        <wan>
+            <if>igb0</if>
+        </wan>
+        <lan>
+            <if>igb1</if>
+        </lan>
+        <opt1>
+            <if>igb2</if>
+        </opt1>
+    </interfaces>
+    <aliases>
+        <alias>
+            <name>Servers</name>
+            <type>host</type>
+            <address>10.0.0.10 10.0.0.11</address>
+            <descr><![CDATA[Web servers]]></descr>
+        </alias>
+        <alias>
+            <name>Feeds</name>
+            <type>urltable_ports</type>
+            <url>https://example.com/ports.txt</url>
+        </alias>
+    </aliases>
+    <nat>
+        <rule>
+            <protocol>tcp</protocol>
+            <interface>wan</interface>
+            <ipprotocol>inet</ipprotocol>
+            <source>
+                <any></any>
+            </source>
+            <destination>
+                <network>wanip</network>
+                <port>443</port>
+            </destination>
+            <target>10.0.0.10</target>
+            <descr><![CDATA[HTTPS]]></descr>
+        </rule>
+    </nat>
+    <filter>
+        <rule>
+            <type>pass</type>
+            <interface>lan</interface>
+            <ipprotocol>inet</ipprotocol>
+            <source>
+                <network>lan</network>
+            </source>
+            <destination>
+                <address>Servers</address>
+            </destination>
+            <descr><![CDATA[LAN to servers]]></descr>
+        </rule>
+        <rule>
+            <type>block</type>
+            <interface>opt1</interface>
+            <ipprotocol>inet</ipprotocol>
+            <source>
+                <any></any>
+            </source>
+            <destination>
+                <any></any>
+            </destination>
+            <descr><![CDATA[Isolate DMZ]]></descr>
+        </rule>
+        <rule>
+            <type>block</type>
+            <interface>wan</interface>
+            <ipprotocol>inet</ipprotocol>
+            <protocol>tcp</protocol>
+            <source>
+                <any></any>
+            </source>
+            <destination>
+                <any></any>
+                <port>Feeds</port>
+            </destination>
+        </rule>
+    </filter>
+    <installedpackages>
+        <package>
+            <name>pfBlockerNG</name>
+        </package>
+    </installedpackages>
+</pfsense>
+"#;
+
+    const OPNSENSE_CONFIG: &str = r#"<?xml version="1.0"?>
+<opnsense>
+  <interfaces>
+    <wan>
+      <if>vtnet0</if>
+    </wan>
+    <lan>
+      <if>vtnet1</if>
+    </lan>
+  </interfaces>
+  <filter>
+    <rule>
+      <type>pass</type>
+      <interface>lan</interface>
+      <ipprotocol>inet</ipprotocol>
+      <descr>Default allow LAN to any rule</descr>
+      <source>
+        <network>lan</network>
+      </source>
+      <destination>
+        <any/>
+      </destination>
+    </rule>
+  </filter>
+  <OPNsense>
+    <Firewall>
+      <Alias version="1.0.1">
+        <aliases/>
+      </Alias>
+      <Filter version="1.0.4">
+        <rules/>
+      </Filter>
+    </Firewall>
+    <IDS version="1.0.9"/>
+  </OPNsense>
+</opnsense>
+"#;
+
+    fn parse(platform: Platform, config: &str) -> Configuration {
+        let interfaces = InterfaceSnapshot::serialize_snapshot(&vec![]).unwrap();
+
+        let snapshot = vec![
+            FileData::new("config.xml", config.as_bytes().to_vec(), 0),
+            FileData::new(NETWORK_INTERFACES_ENTRY, interfaces, 0),
+        ];
+
+        Parser::parse(platform, snapshot).unwrap_or_else(|_| panic!("Failed to parse"))
+    }
+
+    fn issue(item: &str) -> impl Fn(&TranslationIssue) -> bool + '_ {
+        move |issue| issue.item == item
+    }
+
+    #[test]
+    fn test_pfsense_to_opnsense() {
+        let source = parse(Platform::PfSense, PFSENSE_CONFIG);
+        let target = parse(Platform::OPNsense, OPNSENSE_CONFIG);
+
+        let translation =
+            Translator::translate(&source, Platform::PfSense, &target, Platform::OPNsense)
+                .unwrap_or_else(|_| panic!("Failed to translate"));
+
+        let configuration = &translation.configuration;
+        assert_eq!(configuration.aliases.len(), 1);
+        assert_eq!(configuration.aliases[0].value, "10.0.0.10\n10.0.0.11");
+        assert_eq!(configuration.interfaces, target.interfaces);
+
+        let descriptions: Vec<&str> = configuration
+            .rules
+            .iter()
+            .map(|rule| rule.description.as_str())
+            .collect();
+        assert_eq!(descriptions, vec!["LAN to servers", "HTTPS"]);
+        assert!(configuration.rules.iter().all(|rule| rule.order == 0));
+
+        let issues = &translation.issues;
+        assert!(issues.iter().any(issue("interface 'opt1'")));
+        assert!(issues.iter().any(issue("alias 'Feeds'")));
+        assert!(issues.iter().any(issue("filter rule #1 'Isolate DMZ'")));
+        assert!(issues.iter().any(issue("filter rule #2")));
+        assert!(issues.iter().any(issue("nat rule #0 'HTTPS'")));
+        assert!(issues.iter().any(issue("package 'pfBlockerNG'")));
+
+        // The result renders into a valid OPNsense configuration
+        let rendered = Writer::render(Platform::OPNsense, configuration)
+            .unwrap_or_else(|_| panic!("Failed to render"));
+        let mut reparsed = parse(Platform::OPNsense, &rendered);
+
+        // The rule updating the existing one keeps its element, the new one is given a uuid
+        assert_eq!(rendered.matches("<rule uuid=").count(), 1);
+        for rule in &mut reparsed.rules {
+            rule.id.clear();
+        }
+
+        assert_eq!(reparsed.aliases, configuration.aliases);
+        assert_eq!(reparsed.rules, configuration.rules);
+    }
+
+    #[test]
+    fn test_opnsense_to_pfsense() {
+        let mut source = parse(Platform::OPNsense, OPNSENSE_CONFIG);
+        source.aliases = parse(Platform::PfSense, PFSENSE_CONFIG)
+            .aliases
+            .into_iter()
+            .map(|mut alias| {
+                alias.value = alias.value.replace(' ', "\n");
+                alias
+            })
+            .collect();
+        source.aliases[1].r#type = String::from("geoip");
+
+        let target = parse(Platform::PfSense, PFSENSE_CONFIG);
+
+        let translation =
+            Translator::translate(&source, Platform::OPNsense, &target, Platform::PfSense)
+                .unwrap_or_else(|_| panic!("Failed to translate"));
+
+        assert_eq!(translation.configuration.aliases.len(), 1);
+        assert_eq!(
+            translation.configuration.aliases[0].value,
+            "10.0.0.10 10.0.0.11"
+        );
+        assert_eq!(translation.configuration.rules, source.rules);
+        assert_eq!(
+            decode_raw_content(&translation.configuration.raw_content, "test").ok(),
+            Some(String::from(PFSENSE_CONFIG))
+        );
+
+        let issues = &translation.issues;
+        assert!(issues.iter().any(issue("alias 'Feeds'")));
+        assert!(issues.iter().any(issue("Firewall/Filter")));
+        assert!(issues.iter().any(issue("IDS")));
+        assert!(!issues.iter().any(issue("Firewall/Alias")));
+    }
+
+    #[test]
+    fn test_rules_keep_their_order() {
+        let mut source = parse(Platform::PfSense, PFSENSE_CONFIG);
+        source.rules = vec![
+            rule(2, "pass", "IPv4/tcp", "Third"),
+            rule(0, "pass", "IPv4/tcp", "First"),
+            rule(1, "block", "IPv4/tcp", "Second"),
+        ];
+
+        let target = parse(Platform::OPNsense, OPNSENSE_CONFIG);
+
+        let translation =
+            Translator::translate(&source, Platform::PfSense, &target, Platform::OPNsense)
+                .unwrap_or_else(|_| panic!("Failed to translate"));
+
+        let rules: Vec<(u64, &str)> = translation
+            .configuration
+            .rules
+            .iter()
+            .map(|rule| (rule.order, rule.description.as_str()))
+            .collect();
+
+        assert_eq!(rules, vec![(0, "First"), (1, "Second"), (2, "Third")]);
+    }
+
+    #[test]
+    fn test_linux_is_unsupported() {
+        let source = parse(Platform::PfSense, PFSENSE_CONFIG);
+
+        assert!(
+            Translator::translate(&source, Platform::PfSense, &source, Platform::Linux).is_err()
+        );
+    }
+}
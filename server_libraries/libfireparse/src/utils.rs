@@ -1,5 +1,10 @@
+use crate::{FireparseError, Rule};
 use base64::{Engine as _, engine::general_purpose};
-use nullnet_libconfmon::{FileData, Snapshot};
+use nullnet_libconfmon::{FileData, HOSTNAME_ENTRY, Snapshot};
+use roxmltree::Node;
+
+/// A criterion deciding whether a rule corresponds to another one (e.g. an edited version of it).
+pub type RuleMatcher = dyn Fn(&Rule, &Rule) -> bool;
 
 /// Encodes binary data into a Base64-encoded string.
 ///
@@ -18,6 +23,27 @@ where
     general_purpose::STANDARD.encode(data)
 }
 
+/// Decodes the `raw_content` of a `Configuration` back into the original document.
+///
+/// # Arguments
+/// * `raw_content` - The Base64-encoded document.
+/// * `context` - The name of the caller, used in error messages.
+///
+/// # Returns
+/// * `Ok(String)` - The original document.
+/// * `Err(FireparseError)` - If the content is not valid Base64 or UTF-8.
+pub fn decode_raw_content(raw_content: &str, context: &str) -> Result<String, FireparseError> {
+    let data = general_purpose::STANDARD.decode(raw_content).map_err(|e| {
+        FireparseError::WriterError(format!("{context}: Failed to decode the raw content: {e}"))
+    })?;
+
+    String::from_utf8(data).map_err(|e| {
+        FireparseError::WriterError(format!(
+            "{context}: Failed to parse the raw content as UTF-8: {e}"
+        ))
+    })
+}
+
 /// Finds a file entry in a given snapshot by its filename.
 ///
 /// # Arguments
@@ -32,3 +58,105 @@ pub fn find_in_snapshot<'a>(snapshot: &'a Snapshot, filename: &str) -> Option<&'
         .iter()
         .find(|file_data| file_data.filename == filename)
 }
+
+/// Reads the hostname attached to a snapshot by the watcher, if any.
+///
+/// # Arguments
+/// - `snapshot`: A reference to a `Snapshot`.
+///
+/// # Returns
+/// - `Some(String)`: The content of the `#Hostname` entry, trimmed.
+/// - `None`: If the entry is missing, empty or not valid UTF-8.
+pub fn hostname_from_snapshot(snapshot: &Snapshot) -> Option<String> {
+    let entry = find_in_snapshot(snapshot, HOSTNAME_ENTRY)?;
+    let hostname = std::str::from_utf8(&entry.content).ok()?.trim();
+
+    (!hostname.is_empty()).then(|| hostname.to_string())
+}
+
+/// Reads the stable id of a `<rule>` element of a `config.xml`.
+///
+/// # Arguments
+/// - `rule`: The `<rule>` element.
+///
+/// # Returns
+/// The `uuid` attribute (OPNsense) or, failing that, the `<tracker>` (pfSense). Empty if neither is set.
+pub fn rule_id(rule: Node) -> String {
+    rule.attribute("uuid")
+        .or_else(|| {
+            rule.children()
+                .find(|e| e.has_tag_name("tracker"))
+                .and_then(|e| e.text())
+        })
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// Reads the time of the last update of a `<rule>` element of a `config.xml`.
+///
+/// # Arguments
+/// - `rule`: The `<rule>` element.
+///
+/// # Returns
+/// - `Some(u64)`: The time of `<updated>` or, failing that, `<created>`, in milliseconds since the UNIX epoch.
+/// - `None`: If neither holds a valid time.
+pub fn rule_updated_at(rule: Node) -> Option<u64> {
+    ["updated", "created"].into_iter().find_map(|tag| {
+        let seconds = rule
+            .children()
+            .find(|e| e.has_tag_name(tag))?
+            .children()
+            .find(|e| e.has_tag_name("time"))?
+            .text()?
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)?;
+
+        Some((seconds * 1000.0) as u64)
+    })
+}
+
+/// Returns `true` if two rules share the same stable id.
+pub fn same_rule_id(a: &Rule, b: &Rule) -> bool {
+    !a.id.is_empty() && a.id == b.id
+}
+
+/// Matches rules to the rules they correspond to.
+///
+/// The matchers are tried in turn, each on the rules left unmatched by the previous ones.
+/// A rule of `after` is matched to the first unmatched rule of `before` accepted by the matcher.
+///
+/// # Arguments
+/// - `before`: The original rules.
+/// - `after`: The rules to match.
+/// - `matchers`: The criteria, from the strongest to the weakest.
+///
+/// # Returns
+/// For each rule of `after`, the index of the matching rule of `before`, if any.
+pub fn match_rules(
+    before: &[&Rule],
+    after: &[&Rule],
+    matchers: &[&RuleMatcher],
+) -> Vec<Option<usize>> {
+    let mut matches = vec![None; after.len()];
+    let mut matched = vec![false; before.len()];
+
+    for matcher in matchers {
+        for (found, rule) in matches.iter_mut().zip(after) {
+            if found.is_some() {
+                continue;
+            }
+
+            if let Some(index) =
+                (0..before.len()).find(|&i| !matched[i] && matcher(before[i], rule))
+            {
+                matched[index] = true;
+                *found = Some(index);
+            }
+        }
+    }
+
+    matches
+}
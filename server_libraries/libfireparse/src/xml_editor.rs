@@ -0,0 +1,448 @@
+use roxmltree::{Document, Node};
+use std::ops::Range;
+
+use crate::FireparseError;
+
+/// Indentation used when a document gives no hint about its own.
+const DEFAULT_INDENT_UNIT: &str = "\t";
+
+/// A set of textual edits applied to an XML source.
+///
+/// Edits replace byte ranges of the original text, so everything outside of them
+/// is kept byte-for-byte. Insertions at the same position keep the order in which they were added,
+/// and come before a replacement starting at that position.
+#[derive(Default)]
+pub struct XmlEdits {
+    edits: Vec<(Range<usize>, String)>,
+}
+
+impl XmlEdits {
+    /// Replaces a range of the source with `text`.
+    pub fn replace(&mut self, range: Range<usize>, text: String) {
+        self.edits.push((range, text));
+    }
+
+    /// Inserts `text` at a position of the source.
+    pub fn insert(&mut self, position: usize, text: String) {
+        self.edits.push((position..position, text));
+    }
+
+    /// Returns `true` if no edit was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Applies the edits falling within `range` and returns the resulting text of the range.
+    ///
+    /// # Arguments
+    /// * `source` - The original XML text.
+    /// * `range` - The range of the source to render, usually the range of an element.
+    ///
+    /// # Returns
+    /// * `Ok(String)` - The text of the range, with the edits applied.
+    /// * `Err(FireparseError)` - If two edits overlap or an edit falls outside of `range`.
+    pub fn apply(mut self, source: &str, range: Range<usize>) -> Result<String, FireparseError> {
+        self.edits.sort_by_key(|(edit, _)| (edit.start, edit.end));
+
+        let mut output = String::with_capacity(range.len());
+        let mut cursor = range.start;
+
+        for (edit, text) in self.edits {
+            if edit.start < cursor || edit.end > range.end {
+                return Err(FireparseError::WriterError(format!(
+                    "XmlEdits: Edit of {edit:?} overlaps another edit or falls outside of {range:?}"
+                )));
+            }
+
+            output.push_str(&source[cursor..edit.start]);
+            output.push_str(&text);
+            cursor = edit.end;
+        }
+
+        output.push_str(&source[cursor..range.end]);
+        Ok(output)
+    }
+}
+
+/// Returns the indentation of the line an element starts on, if only whitespace precedes it.
+pub fn indent_of<'a>(source: &'a str, node: Node) -> &'a str {
+    let start = node.range().start;
+    let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+    let prefix = &source[line_start..start];
+
+    if prefix.chars().all(char::is_whitespace) {
+        prefix
+    } else {
+        ""
+    }
+}
+
+/// Detects the indentation unit of a document (e.g. a tab or four spaces).
+pub fn indent_unit(document: &Document, source: &str) -> String {
+    for parent in document.descendants().filter(|node| node.is_element()) {
+        if let Some(child) = parent.children().find(|node| node.is_element()) {
+            let outer = indent_of(source, parent);
+            let inner = indent_of(source, child);
+
+            if inner.len() > outer.len() && inner.starts_with(outer) {
+                return inner[outer.len()..].to_string();
+            }
+        }
+    }
+
+    String::from(DEFAULT_INDENT_UNIT)
+}
+
+/// Returns the indentation of the children of an element.
+pub fn child_indent(source: &str, node: Node, unit: &str) -> String {
+    match node.children().find(|child| child.is_element()) {
+        Some(child) => indent_of(source, child).to_string(),
+        None => format!("{}{unit}", indent_of(source, node)),
+    }
+}
+
+/// Finds the first child element with the given tag name.
+pub fn find_child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(tag))
+}
+
+/// Returns `true` if the element is written as `<tag/>`.
+pub fn is_self_closing(source: &str, node: Node) -> bool {
+    source[node.range()].ends_with("/>")
+}
+
+/// Returns the position right after the start tag of a (non self-closing) element.
+fn content_start(source: &str, node: Node) -> usize {
+    let range = node.range();
+
+    match node.first_child() {
+        Some(child) => child.range().start,
+        None => range.start + source[range].find('>').map_or(0, |index| index + 1),
+    }
+}
+
+/// Returns the position of the end tag of a (non self-closing) element.
+fn content_end(source: &str, node: Node) -> usize {
+    let range = node.range();
+
+    match node.last_child() {
+        Some(child) => child.range().end,
+        None => range.start + source[range].rfind("</").unwrap_or(0),
+    }
+}
+
+/// Returns the range of an element extended to the whitespace preceding it on its line,
+/// so that removing it does not leave an empty line behind.
+fn removal_range(source: &str, node: Node) -> Range<usize> {
+    let range = node.range();
+    let before = &source[..range.start];
+    let trimmed = before.trim_end_matches([' ', '\t']);
+
+    match trimmed.strip_suffix('\n') {
+        Some(rest) => rest.strip_suffix('\r').unwrap_or(rest).len()..range.end,
+        None => range,
+    }
+}
+
+/// Escapes the characters that are not allowed in XML text.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Renders `<tag>text</tag>`, escaping the text.
+pub fn text_element(tag: &str, text: &str) -> String {
+    format!("<{tag}>{}</{tag}>", escape(text))
+}
+
+/// Renders `<tag><![CDATA[text]]></tag>`.
+pub fn cdata_element(tag: &str, text: &str) -> String {
+    format!(
+        "<{tag}><![CDATA[{}]]></{tag}>",
+        text.replace("]]>", "]]]]><![CDATA[>")
+    )
+}
+
+/// Renders `<tag></tag>`.
+pub fn empty_element(tag: &str) -> String {
+    format!("<{tag}></{tag}>")
+}
+
+/// Renders an element holding other elements, one per line.
+///
+/// # Arguments
+/// * `tag` - The tag name of the element.
+/// * `children` - The rendered children, whose nested lines are indented with `indent` followed by `unit`.
+/// * `indent` - The indentation of the element itself.
+/// * `unit` - The indentation unit.
+pub fn parent_element(tag: &str, children: &[String], indent: &str, unit: &str) -> String {
+    if children.is_empty() {
+        return empty_element(tag);
+    }
+
+    let mut output = format!("<{tag}>");
+
+    for child in children {
+        output.push('\n');
+        output.push_str(indent);
+        output.push_str(unit);
+        output.push_str(child);
+    }
+
+    output.push('\n');
+    output.push_str(indent);
+    output.push_str(&format!("</{tag}>"));
+    output
+}
+
+/// Sets, replaces or removes a child element.
+///
+/// A new child is appended after the last child element, on its own line.
+///
+/// # Arguments
+/// * `edits` - The edits the change is recorded in.
+/// * `source` - The original XML text.
+/// * `parent` - The element holding the child.
+/// * `tag` - The tag name of the child.
+/// * `element` - The rendered child, or `None` to remove it.
+/// * `unit` - The indentation unit.
+pub fn set_child(
+    edits: &mut XmlEdits,
+    source: &str,
+    parent: Node,
+    tag: &str,
+    element: Option<String>,
+    unit: &str,
+) {
+    match (find_child(parent, tag), element) {
+        (Some(child), Some(element)) => edits.replace(child.range(), element),
+        (Some(child), None) => edits.replace(removal_range(source, child), String::new()),
+        (None, Some(element)) => append_child(edits, source, parent, element, unit),
+        (None, None) => {}
+    }
+}
+
+/// Sets the text of a child element, keeping a CDATA section if the child used one.
+pub fn set_child_text(
+    edits: &mut XmlEdits,
+    source: &str,
+    parent: Node,
+    tag: &str,
+    text: Option<&str>,
+    unit: &str,
+) {
+    let cdata =
+        find_child(parent, tag).is_some_and(|child| source[child.range()].contains("<![CDATA["));
+
+    let element = text.map(|text| match cdata {
+        true => cdata_element(tag, text),
+        false => text_element(tag, text),
+    });
+
+    set_child(edits, source, parent, tag, element, unit);
+}
+
+/// Appends a rendered element as the last child of `parent`.
+pub fn append_child(edits: &mut XmlEdits, source: &str, parent: Node, element: String, unit: &str) {
+    let indent = child_indent(source, parent, unit);
+
+    match parent.children().rfind(|child| child.is_element()) {
+        Some(last) => edits.insert(last.range().end, format!("\n{indent}{element}")),
+        None if is_self_closing(source, parent) => {
+            let range = parent.range();
+            let tag = parent.tag_name().name();
+            let open = source[range.clone()].trim_end_matches("/>").trim_end();
+
+            edits.replace(
+                range,
+                format!(
+                    "{open}>\n{indent}{element}\n{}</{tag}>",
+                    indent_of(source, parent)
+                ),
+            );
+        }
+        None => edits.replace(
+            content_start(source, parent)..content_end(source, parent),
+            format!("\n{indent}{element}\n{}", indent_of(source, parent)),
+        ),
+    }
+}
+
+/// Replaces the items of a collection element (e.g. the `<rule>`s of `<filter>`).
+///
+/// Items are written into the positions of the existing ones, so any other child of the
+/// collection stays where it is. Additional items are appended after the last existing one,
+/// and leftover existing items are removed. Items identical to the existing ones produce no edit.
+///
+/// # Arguments
+/// * `edits` - The edits the changes are recorded in.
+/// * `source` - The original XML text.
+/// * `collection` - The collection element.
+/// * `tag` - The tag name of the items.
+/// * `items` - The rendered items, in order, indented for `item_indent(...)`.
+/// * `unit` - The indentation unit.
+pub fn replace_items(
+    edits: &mut XmlEdits,
+    source: &str,
+    collection: Node,
+    tag: &str,
+    items: &[String],
+    unit: &str,
+) {
+    let slots: Vec<Node> = collection
+        .children()
+        .filter(|child| child.has_tag_name(tag))
+        .collect();
+
+    for (slot, item) in slots.iter().zip(items) {
+        if &source[slot.range()] != item {
+            edits.replace(slot.range(), item.clone());
+        }
+    }
+
+    if let Some(last) = slots.last() {
+        let indent = indent_of(source, *last);
+
+        for item in items.iter().skip(slots.len()) {
+            edits.insert(last.range().end, format!("\n{indent}{item}"));
+        }
+    } else if !items.is_empty() {
+        // Appending to an empty collection rewrites its content, so the items go in a single edit
+        let indent = child_indent(source, collection, unit);
+        let element = items.join(&format!("\n{indent}"));
+        append_child(edits, source, collection, element, unit);
+    }
+
+    for slot in slots.iter().skip(items.len()) {
+        edits.replace(removal_range(source, *slot), String::new());
+    }
+}
+
+/// Returns the indentation of the items of a collection element.
+pub fn item_indent(source: &str, collection: Node, tag: &str, unit: &str) -> String {
+    match find_child(collection, tag) {
+        Some(item) => indent_of(source, item).to_string(),
+        None => child_indent(source, collection, unit),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{XmlEdits, find_child, indent_unit, replace_items, set_child_text};
+    use roxmltree::Document;
+
+    #[test]
+    fn test_set_child_text() {
+        let source = "<a>\n\t<b><![CDATA[old]]></b>\n\t<c>1</c>\n</a>";
+        let document = Document::parse(source).unwrap();
+        let root = document.root_element();
+        let unit = indent_unit(&document, source);
+
+        let mut edits = XmlEdits::default();
+        set_child_text(&mut edits, source, root, "b", Some("new"), &unit);
+        set_child_text(&mut edits, source, root, "c", None, &unit);
+        set_child_text(&mut edits, source, root, "d", Some("x & y"), &unit);
+
+        assert_eq!(
+            edits
+                .apply(source, root.range())
+                .unwrap_or_else(|_| panic!("Failed to apply")),
+            "<a>\n\t<b><![CDATA[new]]></b>\n\t<d>x &amp; y</d>\n</a>"
+        );
+    }
+
+    #[test]
+    fn test_replace_items() {
+        let source = "<list>\n  <item>1</item>\n  <other/>\n  <item>2</item>\n</list>";
+        let document = Document::parse(source).unwrap();
+        let root = document.root_element();
+
+        let mut edits = XmlEdits::default();
+        let items = vec![
+            String::from("<item>1</item>"),
+            String::from("<item>3</item>"),
+            String::from("<item>4</item>"),
+        ];
+        replace_items(&mut edits, source, root, "item", &items, "  ");
+
+        assert_eq!(
+            edits
+                .apply(source, root.range())
+                .unwrap_or_else(|_| panic!("Failed to apply")),
+            "<list>\n  <item>1</item>\n  <other/>\n  <item>3</item>\n  <item>4</item>\n</list>"
+        );
+
+        let mut edits = XmlEdits::default();
+        replace_items(&mut edits, source, root, "item", &items[..1], "  ");
+
+        assert_eq!(
+            edits
+                .apply(source, root.range())
+                .unwrap_or_else(|_| panic!("Failed to apply")),
+            "<list>\n  <item>1</item>\n  <other/>\n</list>"
+        );
+    }
+
+    #[test]
+    fn test_append_to_empty_elements() {
+        let source = "<root>\n    <list/>\n    <other></other>\n</root>";
+        let document = Document::parse(source).unwrap();
+        let root = document.root_element();
+        let unit = indent_unit(&document, source);
+
+        let mut edits = XmlEdits::default();
+        let items = vec![
+            String::from("<item>1</item>"),
+            String::from("<item>2</item>"),
+        ];
+        replace_items(
+            &mut edits,
+            source,
+            find_child(root, "list").unwrap(),
+            "item",
+            &items,
+            &unit,
+        );
+        replace_items(
+            &mut edits,
+            source,
+            find_child(root, "other").unwrap(),
+            "item",
+            &items,
+            &unit,
+        );
+
+        assert_eq!(
+            edits
+                .apply(source, root.range())
+                .unwrap_or_else(|_| panic!("Failed to apply")),
+            "<root>\n    <list>\n        <item>1</item>\n        <item>2</item>\n    </list>\n    <other>\n        <item>1</item>\n        <item>2</item>\n    </other>\n</root>"
+        );
+    }
+
+    #[test]
+    fn test_reject_overlapping_edits() {
+        let source = "<root><a>1</a><b>2</b></root>";
+
+        let mut edits = XmlEdits::default();
+        edits.insert(6, String::from("<c/>"));
+        edits.replace(6..14, String::from("<a>3</a>"));
+        edits.insert(14, String::from("<d/>"));
+        assert_eq!(
+            edits
+                .apply(source, 0..source.len())
+                .unwrap_or_else(|_| panic!("Failed to apply")),
+            "<root><c/><a>3</a><d/><b>2</b></root>"
+        );
+
+        let mut edits = XmlEdits::default();
+        edits.replace(6..14, String::from("<a>3</a>"));
+        edits.replace(9..10, String::from("4"));
+        assert!(edits.apply(source, 0..source.len()).is_err());
+
+        let mut edits = XmlEdits::default();
+        edits.replace(6..14, String::from("<a>3</a>"));
+        assert!(edits.apply(source, 14..source.len()).is_err());
+    }
+}